@@ -0,0 +1,254 @@
+//! Generates the progressive multi-jittered (0, 2) point sets with blue noise properties of the
+//! PMJ02BN sampler, which are checked in at `lili/src/math/tables/pmj02bn.bin` because generating
+//! them takes a few seconds.
+//!
+//! The sets follow Christensen, Kensler and Kilpatrick, "Progressive Multi-Jittered Sample
+//! Sequences" (2018), and only need generating again when the construction changes:
+//!
+//! ```text
+//! cargo run --release --example pmj02bn_tables -- lili/src/math/tables/pmj02bn.bin
+//! ```
+use std::{env, fs, path::PathBuf, process};
+
+use lili::math::{
+    pmj02tables::{N_PMJ02BN_SAMPLES, N_PMJ02BN_SETS, PMJ02BN_BITS as BITS},
+    points::Point2f,
+    rng::Rng,
+    Float,
+};
+use rayon::prelude::*;
+
+fn main() {
+    let Some(out_path) = env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("usage: pmj02bn_tables <output file>");
+        process::exit(1);
+    };
+
+    // The sets are independent, so they are generated in parallel
+    let sets: Vec<Vec<[u32; 2]>> = (0..N_PMJ02BN_SETS)
+        .into_par_iter()
+        .map(|set_index| generate_pmj02bn(set_index as u64))
+        .collect();
+    let bytes: Vec<u8> = sets
+        .iter()
+        .flatten()
+        .flatten()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    if let Err(err) = fs::write(&out_path, bytes) {
+        eprintln!("{}: {err}", out_path.display());
+        process::exit(1);
+    }
+}
+
+/// The number of candidate positions that each point chooses from.
+const N_CANDIDATES: usize = 8;
+
+/// Generates a point set by progressively extending it, as in the paper.
+///
+/// Once there are `n` points, for `n` a power of four, each of them lies in one quadrant of its
+/// cell of a grid of `n` cells. The next `n` points each go in the diagonally opposite quadrant
+/// of one of these points, and the `2n` points after that in the two remaining quadrants. Within
+/// its quadrant, a point must lie in an elementary interval that no earlier point occupies,
+/// which leaves a single cell of the finest intervals. It tries several random positions in that
+/// cell and keeps the one farthest from the earlier points.
+fn generate_pmj02bn(seed: u64) -> Vec<[u32; 2]> {
+    let mut rng = Rng::new(seed, 0);
+    let mut points: Vec<[u32; 2]> = Vec::with_capacity(N_PMJ02BN_SAMPLES);
+    let mut grid = PointGrid::new();
+    let mut strata = Strata::new(0, &points);
+
+    let jittered = |cell: [u32; 2], log2_n: u32, rng: &mut Rng| {
+        let free_bits = BITS - log2_n;
+        cell.map(|c| c << free_bits | rng.uniform_u32() >> (32 - free_bits))
+    };
+
+    while points.len() < N_PMJ02BN_SAMPLES {
+        let i = points.len();
+        let bits = if i == 0 {
+            jittered([0, 0], 0, &mut rng)
+        } else {
+            // The points up to the next power of two form a (0, 2)-net
+            if i.is_power_of_two() {
+                strata = Strata::new(i.ilog2() + 1, &points);
+            }
+
+            // The quadrants, at a resolution of 2^(k + 1) for 4^k <= i < 4^(k + 1), that the
+            // point may go in
+            let log2_quadrants = i.ilog2() / 2 + 1;
+            let n = 1 << (2 * (log2_quadrants - 1));
+            let quadrant = |p: [u32; 2]| p.map(|c| c >> (BITS - log2_quadrants));
+            let old = quadrant(points[i % n]);
+            let quadrants = if i < 2 * n {
+                [[old[0] ^ 1, old[1] ^ 1]; 2]
+            } else if i < 3 * n {
+                let flip = rng.uniform_u32() & 1;
+                [
+                    [old[0] ^ flip, old[1] ^ flip ^ 1],
+                    [old[0] ^ flip ^ 1, old[1] ^ flip],
+                ]
+            } else {
+                let taken = quadrant(points[i - n]);
+                [[taken[0] ^ 1, taken[1] ^ 1]; 2]
+            };
+            let cell = quadrants
+                .iter()
+                .find_map(|&q| strata.free_cell(q, log2_quadrants))
+                .expect("a quadrant always has a free stratum");
+
+            let mut best = (Float::NEG_INFINITY, [0; 2]);
+            for _ in 0..N_CANDIDATES {
+                let bits = jittered(cell, strata.log2_n, &mut rng);
+                let distance = grid.nearest_distance(to_point(bits));
+                if distance > best.0 {
+                    best = (distance, bits);
+                }
+            }
+            best.1
+        };
+
+        strata.insert(bits);
+        grid.insert(to_point(bits));
+        points.push(bits);
+    }
+    points
+}
+
+/// The point with the given fixed-point coordinates.
+fn to_point(bits: [u32; 2]) -> Point2f {
+    let [x, y] = bits.map(|b| b as Float * (2.0 as Float).powi(-(BITS as i32)));
+    Point2f::new(x, y)
+}
+
+/// Which of the elementary intervals of area `1 / 2^log2_n` hold a point, for every shape of
+/// them.
+struct Strata {
+    log2_n: u32,
+    /// For each number `a` of digits of the x coordinate, whether each of the intervals
+    /// `2^-a` wide and `2^(a - log2_n)` high is occupied, by column and then by row.
+    occupied: Vec<Vec<bool>>,
+}
+
+impl Strata {
+    fn new(log2_n: u32, points: &[[u32; 2]]) -> Self {
+        let mut strata = Self {
+            log2_n,
+            occupied: vec![vec![false; 1 << log2_n]; log2_n as usize + 1],
+        };
+        for &p in points {
+            strata.insert(p);
+        }
+        strata
+    }
+
+    /// The index of the interval in column `x` and row `y` of the intervals with `a` digits of
+    /// the x coordinate.
+    fn index(&self, a: u32, x: u32, y: u32) -> usize {
+        ((x as usize) << (self.log2_n - a)) | y as usize
+    }
+
+    fn insert(&mut self, p: [u32; 2]) {
+        let [x, y] = p.map(|c| c >> (BITS - self.log2_n));
+        for a in 0..=self.log2_n {
+            let i = self.index(a, x >> (self.log2_n - a), y >> a);
+            debug_assert!(
+                !self.occupied[a as usize][i],
+                "the points are not a (0, 2)-net"
+            );
+            self.occupied[a as usize][i] = true;
+        }
+    }
+
+    /// The cell of the finest grid, with `2^log2_n` columns and rows, in `quadrant` of the grid
+    /// with `2^log2_quadrants` columns and rows, whose intervals of every shape are unoccupied.
+    fn free_cell(&self, quadrant: [u32; 2], log2_quadrants: u32) -> Option<[u32; 2]> {
+        let free_bits = self.log2_n - log2_quadrants;
+        (0..1 << free_bits)
+            .map(|x| quadrant[0] << free_bits | x)
+            .find_map(|x| Some([x, self.free_row(x, 0, 0, quadrant[1], log2_quadrants)?]))
+    }
+
+    /// The row of the finest grid, starting with the `depth` digits `y`, in which the cell of
+    /// column `x` lies in unoccupied intervals of every shape, and whose first
+    /// `log2_quadrants` digits are those of `quadrant_y`.
+    fn free_row(
+        &self,
+        x: u32,
+        depth: u32,
+        y: u32,
+        quadrant_y: u32,
+        log2_quadrants: u32,
+    ) -> Option<u32> {
+        // The intervals with `depth` digits of y have the rest of the digits of x
+        let a = self.log2_n - depth;
+        if self.occupied[a as usize][self.index(a, x >> depth, y)] {
+            return None;
+        }
+        if depth == self.log2_n {
+            return Some(y);
+        }
+        let digits: &[u32] = if depth < log2_quadrants {
+            &[(quadrant_y >> (log2_quadrants - 1 - depth)) & 1]
+        } else {
+            &[0, 1]
+        };
+        digits
+            .iter()
+            .find_map(|&d| self.free_row(x, depth + 1, y << 1 | d, quadrant_y, log2_quadrants))
+    }
+}
+
+/// A grid of points on the unit torus, for finding how close the nearest of them is.
+struct PointGrid {
+    cells: Vec<Vec<Point2f>>,
+}
+
+impl PointGrid {
+    const RESOLUTION: usize = 256;
+
+    fn new() -> Self {
+        Self {
+            cells: vec![Vec::new(); Self::RESOLUTION * Self::RESOLUTION],
+        }
+    }
+
+    fn cell(p: Point2f) -> (usize, usize) {
+        let n = Self::RESOLUTION as Float;
+        ((p.x * n) as usize, (p.y * n) as usize)
+    }
+
+    fn insert(&mut self, p: Point2f) {
+        let (x, y) = Self::cell(p);
+        self.cells[y * Self::RESOLUTION + x].push(p);
+    }
+
+    /// The distance from `p` to the nearest point, wrapping around the edges of the square, or
+    /// infinity if there are none.
+    fn nearest_distance(&self, p: Point2f) -> Float {
+        let n = Self::RESOLUTION as i64;
+        let (cx, cy) = Self::cell(p);
+        let mut nearest_squared = Float::INFINITY;
+        for ring in 0..=n / 2 {
+            // Points in farther rings are at least this far away
+            let ring_distance = (ring - 1).max(0) as Float / n as Float;
+            if ring_distance * ring_distance >= nearest_squared {
+                break;
+            }
+            for dy in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs() != ring && dy.abs() != ring {
+                        continue;
+                    }
+                    let x = (cx as i64 + dx).rem_euclid(n) as usize;
+                    let y = (cy as i64 + dy).rem_euclid(n) as usize;
+                    for q in &self.cells[y * Self::RESOLUTION + x] {
+                        let wrap = |d: Float| d.abs().min(1.0 - d.abs());
+                        let (ddx, ddy) = (wrap(p.x - q.x), wrap(p.y - q.y));
+                        nearest_squared = nearest_squared.min(ddx * ddx + ddy * ddy);
+                    }
+                }
+            }
+        }
+        nearest_squared.sqrt()
+    }
+}
@@ -0,0 +1,34 @@
+//! Fits the RGB-to-spectrum tables of the built-in color spaces, which are checked in at
+//! `lili/src/color/tables` because fitting them takes a while.
+//!
+//! The tables only need fitting again when the color spaces or the CIE tables change:
+//!
+//! ```text
+//! cargo run --release --example rgb2spec_opt -- lili/src/color/tables
+//! ```
+use std::{env, fs, path::PathBuf, process};
+
+/// The type of the values in the files shared with the crate, which are fitted in double
+/// precision whatever the precision of the renderer.
+type Float = f64;
+
+#[path = "../../src/color/builtin.rs"]
+mod builtin;
+#[path = "../../src/spectrum/cie.rs"]
+mod cie;
+mod rgb_to_spectrum;
+
+fn main() {
+    let Some(out_dir) = env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("usage: rgb2spec_opt <output directory>");
+        process::exit(1);
+    };
+
+    for space in builtin::BUILTIN_COLOR_SPACES {
+        let path = out_dir.join(format!("{}.rgbspectrum", space.name));
+        if let Err(err) = fs::write(&path, rgb_to_spectrum::fit_table(space)) {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Fits the RGB-to-spectrum tables, following Jakob and Hanika, "A Low-Dimensional Function
+//! Space for Efficient Spectral Upsampling" (2019).
+//!
+//! For every sampled color, Gauss-Newton iterations find the coefficients of the sigmoid
+//! polynomial whose spectrum, under the illuminant of the color space, has the closest color in
+//! CIELAB.
+use crate::{
+    builtin::ColorSpaceDefinition,
+    cie::{daylight, CIE_LAMBDA_START, CIE_LAMBDA_STEP, CIE_XYZ},
+};
+
+/// The number of samples along each axis of a table, which must match
+/// `RGB_TO_SPECTRUM_TABLE_RESOLUTION`.
+const RES: usize = 64;
+
+/// The number of wavelengths the color of a spectrum is integrated over, with Simpson's 3/8 rule,
+/// which puts three of them in every interval of the CIE tables.
+const FINE_SAMPLES: usize = (CIE_XYZ.len() - 1) * 3 + 1;
+
+/// Fits the table of a color space, and returns it in the format that `RgbToSpectrumTable`
+/// reads: the resolution as a `u32`, then the values of the largest component that the table is
+/// sampled at, then the coefficients, all little-endian and as `f32`.
+///
+/// The coefficients are indexed by the largest component, then by the samples of its value, of
+/// the component after it and of the one after that.
+pub fn fit_table(space: &ColorSpaceDefinition) -> Vec<u8> {
+    let fit = Fit::new(space);
+    let mut coeffs = vec![[0.0; 3]; 3 * RES * RES * RES];
+    for l in 0..3 {
+        for yi in 0..RES {
+            for xi in 0..RES {
+                let x = xi as f64 / (RES - 1) as f64;
+                let y = yi as f64 / (RES - 1) as f64;
+                let mut solve = |k: usize, c: &mut [f64; 3]| {
+                    let b = z_node(k);
+                    let mut rgb = [0.0; 3];
+                    rgb[l] = b;
+                    rgb[(l + 1) % 3] = x * b;
+                    rgb[(l + 2) % 3] = y * b;
+                    fit.gauss_newton(&rgb, c);
+                    coeffs[((l * RES + k) * RES + yi) * RES + xi] = to_wavelength_coeffs(c);
+                };
+
+                // Walk outwards from a moderate brightness, where the fit is easiest, each time
+                // starting from the previous solution
+                let start = RES / 5;
+                let mut c = [0.0; 3];
+                for k in start..RES {
+                    solve(k, &mut c);
+                }
+                let mut c = [0.0; 3];
+                for k in (0..start).rev() {
+                    solve(k, &mut c);
+                }
+            }
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(4 * (1 + RES + 3 * coeffs.len()));
+    bytes.extend_from_slice(&(RES as u32).to_le_bytes());
+    for k in 0..RES {
+        bytes.extend_from_slice(&(z_node(k) as f32).to_le_bytes());
+    }
+    for c in coeffs.iter().flatten() {
+        bytes.extend_from_slice(&(*c as f32).to_le_bytes());
+    }
+    bytes
+}
+
+fn smooth_step(x: f64) -> f64 {
+    x * x * (3.0 - 2.0 * x)
+}
+
+/// The `k`th value of the largest component that the tables are sampled at, which are spaced
+/// more densely near 0 and 1.
+fn z_node(k: usize) -> f64 {
+    smooth_step(smooth_step(k as f64 / (RES - 1) as f64))
+}
+
+/// The wavelength of the last samples of the CIE tables.
+fn lambda_max() -> f64 {
+    CIE_LAMBDA_START + CIE_LAMBDA_STEP * (CIE_XYZ.len() - 1) as f64
+}
+
+/// The fit works with wavelengths remapped to between 0 and 1, so the coefficients are converted
+/// back to apply to wavelengths in nanometers.
+fn to_wavelength_coeffs(c: &[f64; 3]) -> [f64; 3] {
+    let c0 = CIE_LAMBDA_START;
+    let c1 = 1.0 / (lambda_max() - CIE_LAMBDA_START);
+    let [a, b, c] = *c;
+    [
+        a * c1 * c1,
+        b * c1 - 2.0 * a * c0 * c1 * c1,
+        c - b * c0 * c1 + a * (c0 * c1) * (c0 * c1),
+    ]
+}
+
+/// The value of the CIE color matching functions at `lambda`, interpolated linearly.
+fn cie_xyz(lambda: f64) -> [f64; 3] {
+    let t = ((lambda - CIE_LAMBDA_START) / CIE_LAMBDA_STEP).max(0.0);
+    let i = (t as usize).min(CIE_XYZ.len() - 2);
+    let dt = t - i as f64;
+    [0, 1, 2].map(|c| (1.0 - dt) * CIE_XYZ[i][c] + dt * CIE_XYZ[i + 1][c])
+}
+
+/// The value at `lambda` of a spectrum given as interleaved wavelength/value pairs, interpolated
+/// linearly.
+fn interpolate(samples: &[f64], lambda: f64) -> f64 {
+    let pairs: Vec<(f64, f64)> = samples.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    let o = pairs
+        .partition_point(|(l, _)| *l <= lambda)
+        .clamp(1, pairs.len() - 1)
+        - 1;
+    let t = (lambda - pairs[o].0) / (pairs[o + 1].0 - pairs[o].0);
+    (1.0 - t) * pairs[o].1 + t * pairs[o + 1].1
+}
+
+/// The color matching functions of a color space, sampled for fitting spectra to colors.
+struct Fit {
+    /// The sampled wavelengths, remapped to between 0 and 1.
+    lambda: Vec<f64>,
+    /// The RGB color matching functions under the illuminant, including the integration weights.
+    rgb_tbl: [Vec<f64>; 3],
+    xyz_whitepoint: [f64; 3],
+    xyz_from_rgb: [[f64; 3]; 3],
+}
+
+impl Fit {
+    fn new(space: &ColorSpaceDefinition) -> Self {
+        let lambda_min = CIE_LAMBDA_START;
+        let h = (lambda_max() - lambda_min) / (FINE_SAMPLES - 1) as f64;
+        let illuminant = daylight(space.white_temperature);
+
+        // Sample the color matching functions and the illuminant, with the weights of Simpson's
+        // 3/8 rule
+        let mut lambda = vec![0.0; FINE_SAMPLES];
+        let mut xyz_tbl = vec![[0.0; 3]; FINE_SAMPLES];
+        let mut illuminant_y = 0.0;
+        for i in 0..FINE_SAMPLES {
+            let l = lambda_min + i as f64 * h;
+            let mut weight = 3.0 / 8.0 * h;
+            if i == 0 || i == FINE_SAMPLES - 1 {
+            } else if (i - 1) % 3 == 2 {
+                weight *= 2.0;
+            } else {
+                weight *= 3.0;
+            }
+
+            lambda[i] = (l - lambda_min) / (lambda_max() - lambda_min);
+            let illum = interpolate(&illuminant, l);
+            xyz_tbl[i] = cie_xyz(l).map(|c| c * illum * weight);
+            illuminant_y += xyz_tbl[i][1];
+        }
+
+        // Normalize the illuminant to a luminance of 1
+        let mut xyz_whitepoint = [0.0; 3];
+        for xyz in &mut xyz_tbl {
+            for (w, c) in xyz_whitepoint.iter_mut().zip(xyz.iter_mut()) {
+                *c /= illuminant_y;
+                *w += *c;
+            }
+        }
+
+        // Scale the primaries so that they add up to the white point
+        let primaries = space
+            .primaries
+            .map(|(x, y)| [x / y, 1.0, (1.0 - x - y) / y]);
+        let rgb = [0, 1, 2].map(|i| [0, 1, 2].map(|j| primaries[j][i]));
+        let scale = mul_vector(&inverse(&rgb), &xyz_whitepoint);
+        let xyz_from_rgb = [0, 1, 2].map(|i| [0, 1, 2].map(|j| rgb[i][j] * scale[j]));
+        let rgb_from_xyz = inverse(&xyz_from_rgb);
+
+        let rgb_tbl = [0, 1, 2].map(|k| {
+            xyz_tbl
+                .iter()
+                .map(|xyz| (0..3).map(|j| rgb_from_xyz[k][j] * xyz[j]).sum())
+                .collect()
+        });
+
+        Self {
+            lambda,
+            rgb_tbl,
+            xyz_whitepoint,
+            xyz_from_rgb,
+        }
+    }
+
+    /// Converts a color to CIELAB, which is roughly perceptually uniform, and gives the
+    /// derivatives of the conversion.
+    fn cie_lab(&self, rgb: &[f64; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+        let delta: f64 = 6.0 / 29.0;
+        let w = &self.xyz_whitepoint;
+        let mut f = [0.0; 3];
+        let mut df_drgb = [[0.0; 3]; 3];
+        for i in 0..3 {
+            let t = (0..3)
+                .map(|j| self.xyz_from_rgb[i][j] * rgb[j])
+                .sum::<f64>()
+                / w[i];
+            let df_dt = if t > delta.powi(3) {
+                f[i] = t.cbrt();
+                1.0 / (3.0 * f[i] * f[i])
+            } else {
+                f[i] = t / (3.0 * delta * delta) + 4.0 / 29.0;
+                1.0 / (3.0 * delta * delta)
+            };
+            df_drgb[i] = self.xyz_from_rgb[i].map(|m| df_dt * m / w[i]);
+        }
+
+        let lab = [
+            116.0 * f[1] - 16.0,
+            500.0 * (f[0] - f[1]),
+            200.0 * (f[1] - f[2]),
+        ];
+        let d = |a: usize, b: usize, s: f64| [0, 1, 2].map(|j| s * (df_drgb[a][j] - df_drgb[b][j]));
+        let dlab = [
+            df_drgb[1].map(|v| 116.0 * v),
+            d(0, 1, 500.0),
+            d(1, 2, 200.0),
+        ];
+        (lab, dlab)
+    }
+
+    /// The difference between the CIELAB color `target` and the color of the spectrum with
+    /// coefficients `c`, and its derivatives with respect to the coefficients.
+    fn residual(&self, c: &[f64; 3], target: &[f64; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+        let mut rgb = [0.0; 3];
+        let mut drgb = [[0.0; 3]; 3];
+        for (i, &lambda) in self.lambda.iter().enumerate() {
+            let x = (c[0] * lambda + c[1]) * lambda + c[2];
+            let r = 1.0 / (1.0 + x * x).sqrt();
+            let s = 0.5 * x * r + 0.5;
+            let ds = 0.5 * r * r * r;
+            let dx = [lambda * lambda, lambda, 1.0];
+            for k in 0..3 {
+                let tbl = self.rgb_tbl[k][i];
+                rgb[k] += tbl * s;
+                for j in 0..3 {
+                    drgb[k][j] += tbl * ds * dx[j];
+                }
+            }
+        }
+
+        let (lab, dlab) = self.cie_lab(&rgb);
+        let residual = [0, 1, 2].map(|j| target[j] - lab[j]);
+        let jacobian = [0, 1, 2]
+            .map(|i| [0, 1, 2].map(|j| -(0..3).map(|k| dlab[i][k] * drgb[k][j]).sum::<f64>()));
+        (residual, jacobian)
+    }
+
+    /// Refines the coefficients `c` until the spectrum matches the color `rgb`.
+    fn gauss_newton(&self, rgb: &[f64; 3], c: &mut [f64; 3]) {
+        let norm = |r: [f64; 3]| r.iter().map(|r| r * r).sum::<f64>();
+        let target = self.cie_lab(rgb).0;
+        let (mut residual, mut jacobian) = self.residual(c, &target);
+        for _ in 0..15 {
+            if norm(residual) < 1e-6 {
+                break;
+            }
+            let Some(step) = solve3(&jacobian, &residual) else {
+                break;
+            };
+
+            // Saturated colors need coefficients far from the starting point, where full steps
+            // can overshoot, so the step is shortened until it improves the fit
+            let mut scale = 1.0;
+            loop {
+                let next = [0, 1, 2].map(|i| c[i] - scale * step[i]);
+                let (next_residual, next_jacobian) = self.residual(&next, &target);
+                if norm(next_residual) < norm(residual) || scale < 1e-3 {
+                    (*c, residual, jacobian) = (next, next_residual, next_jacobian);
+                    break;
+                }
+                scale /= 2.0;
+            }
+        }
+    }
+}
+
+fn mul_vector(m: &[[f64; 3]; 3], v: &[f64; 3]) -> [f64; 3] {
+    m.map(|row| (0..3).map(|j| row[j] * v[j]).sum())
+}
+
+/// The inverse of a matrix, by its adjugate.
+fn inverse(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |i: usize, j: usize| {
+        let (i0, i1) = ((i + 1) % 3, (i + 2) % 3);
+        let (j0, j1) = ((j + 1) % 3, (j + 2) % 3);
+        m[i0][j0] * m[i1][j1] - m[i0][j1] * m[i1][j0]
+    };
+    let det: f64 = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum();
+    [0, 1, 2].map(|i| [0, 1, 2].map(|j| cofactor(j, i) / det))
+}
+
+/// Solves the linear system `a x = b` by Gaussian elimination with partial pivoting.
+fn solve3(a: &[[f64; 3]; 3], b: &[f64; 3]) -> Option<[f64; 3]> {
+    let mut m = [0, 1, 2].map(|i| [a[i][0], a[i][1], a[i][2], b[i]]);
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))?;
+        if m[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+        m.swap(col, pivot);
+        let pivot_row = m[col];
+        for row in &mut m[col + 1..] {
+            let f = row[col] / pivot_row[col];
+            for (v, p) in row.iter_mut().zip(pivot_row).skip(col) {
+                *v -= f * p;
+            }
+        }
+    }
+
+    let mut x = [0.0; 3];
+    for row in (0..3).rev() {
+        let sum: f64 = (row + 1..3).map(|k| m[row][k] * x[k]).sum();
+        x[row] = (m[row][3] - sum) / m[row][row];
+    }
+    Some(x)
+}
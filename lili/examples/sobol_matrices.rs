@@ -0,0 +1,210 @@
+//! Computes the generator matrices of the Sobol sequence, which are checked in at
+//! `lili/src/math/tables/sobolmatrices.bin` because the crate uses them as a static table.
+//!
+//! The matrices only need computing again when the direction numbers change:
+//!
+//! ```text
+//! cargo run --release --example sobol_matrices -- lili/src/math/tables/sobolmatrices.bin
+//! ```
+//!
+//! Given a file of direction numbers in the format of Joe and Kuo's `new-joe-kuo-6.21201`, with a
+//! header line followed by lines of `d s a m_1 ... m_s`, the matrices are computed from its
+//! polynomials and initial direction numbers:
+//!
+//! ```text
+//! cargo run --release --example sobol_matrices -- lili/src/math/tables/sobolmatrices.bin \
+//!     new-joe-kuo-6.21201
+//! ```
+//!
+//! Without one, each dimension after the first is given by the next primitive polynomial over
+//! GF(2), in order of degree, and initial direction numbers that are drawn at random among the
+//! valid ones with a fixed seed.
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use lili::math::{
+    rng::Rng,
+    sobolmatrices::{N_SOBOL_DIMENSIONS, SOBOL_MATRIX_SIZE},
+};
+
+fn main() {
+    let mut args = env::args_os().skip(1);
+    let Some(out_path) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: sobol_matrices <output file> [direction numbers file]");
+        process::exit(1);
+    };
+
+    let directions = match args.next().map(PathBuf::from) {
+        Some(path) => read_joe_kuo(&path).unwrap_or_else(|err| {
+            eprintln!("{}: {err}", path.display());
+            process::exit(1);
+        }),
+        None => random_directions(),
+    };
+
+    // The first dimension is the van der Corput sequence, which Joe and Kuo's files leave out
+    let mut bytes = Vec::with_capacity(4 * N_SOBOL_DIMENSIONS * SOBOL_MATRIX_SIZE);
+    let matrices = [generator_matrix(1, 0, &[])].into_iter().chain(
+        directions
+            .iter()
+            .map(|(degree, poly, m)| generator_matrix(*degree, *poly, m)),
+    );
+    for matrix in matrices.take(N_SOBOL_DIMENSIONS) {
+        bytes.extend(matrix.iter().flat_map(|column| column.to_le_bytes()));
+    }
+    if let Err(err) = fs::write(&out_path, bytes) {
+        eprintln!("{}: {err}", out_path.display());
+        process::exit(1);
+    }
+}
+
+/// The degree, polynomial and initial direction numbers of the dimensions after the first.
+type Directions = Vec<(u32, u64, Vec<u64>)>;
+
+/// Reads the polynomials and initial direction numbers of a file of Joe and Kuo.
+///
+/// The polynomials are given by their degree `s` and the coefficients `a` of their terms other
+/// than the first and last.
+fn read_joe_kuo(path: &Path) -> Result<Directions, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let directions: Directions = contents
+        .lines()
+        .skip(1)
+        .take(N_SOBOL_DIMENSIONS - 1)
+        .map(|line| {
+            let values = line
+                .split_whitespace()
+                .map(|v| v.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("{line:?}: {err}"))?;
+            let [_, s, a, m @ ..] = values.as_slice() else {
+                return Err(format!("{line:?}: expected d, s, a and m_1 to m_s"));
+            };
+            if m.len() as u64 != *s {
+                return Err(format!("{line:?}: expected {s} initial direction numbers"));
+            }
+            Ok((*s as u32, (1 << s) | (a << 1) | 1, m.to_vec()))
+        })
+        .collect::<Result<_, _>>()?;
+    if directions.len() < N_SOBOL_DIMENSIONS - 1 {
+        return Err(format!(
+            "expected {} dimensions after the first, found {}",
+            N_SOBOL_DIMENSIONS - 1,
+            directions.len()
+        ));
+    }
+    Ok(directions)
+}
+
+/// The next primitive polynomials in order, with random initial direction numbers.
+fn random_directions() -> Directions {
+    let mut rng = Rng::new(0, 0);
+    primitive_polynomials()
+        .take(N_SOBOL_DIMENSIONS - 1)
+        .map(|poly| {
+            let degree = 63 - poly.leading_zeros();
+            // The initial direction numbers m_k can be any odd numbers below 2^k
+            let m = (1..=degree)
+                .map(|k| (rng.uniform_u64() & ((1 << k) - 1)) | 1)
+                .collect();
+            (degree, poly, m)
+        })
+        .collect()
+}
+
+/// The generator matrix of the primitive polynomial `poly` of the given degree, whose bits are
+/// its coefficients, with the initial direction numbers `m`.
+///
+/// A degree of 1 with no direction numbers gives the identity matrix.
+fn generator_matrix(degree: u32, poly: u64, m: &[u64]) -> [u32; SOBOL_MATRIX_SIZE] {
+    // Compute the direction numbers v_j = m_j / 2^j in 64-bit fixed point, by Bratley and Fox's
+    // recurrence, and keep their 32 most significant bits
+    let s = degree as usize;
+    let mut v = [0_u64; SOBOL_MATRIX_SIZE];
+    for j in 0..SOBOL_MATRIX_SIZE {
+        v[j] = if m.is_empty() {
+            1 << (63 - j)
+        } else if j < s {
+            m[j] << (63 - j)
+        } else {
+            let mut vj = v[j - s] ^ (v[j - s] >> s);
+            for k in 1..s {
+                if (poly >> (s - k)) & 1 != 0 {
+                    vj ^= v[j - k];
+                }
+            }
+            vj
+        };
+    }
+    v.map(|v| (v >> 32) as u32)
+}
+
+/// The primitive polynomials over GF(2) of degree 1 and up, in order of degree and then of their
+/// coefficients, as bits.
+fn primitive_polynomials() -> impl Iterator<Item = u64> {
+    (1..32).flat_map(|degree: u32| {
+        ((1_u64 << degree) + 1..1 << (degree + 1))
+            .step_by(2)
+            .filter(move |&poly| is_primitive(poly, degree))
+    })
+}
+
+/// Whether `x` generates the multiplicative group of GF(2)[x] / `poly`, which has 2^degree - 1
+/// elements.
+fn is_primitive(poly: u64, degree: u32) -> bool {
+    let order = (1_u64 << degree) - 1;
+    if pow_x(order, poly, degree) != 1 {
+        return false;
+    }
+    prime_factors(order)
+        .into_iter()
+        .all(|q| pow_x(order / q, poly, degree) != 1)
+}
+
+/// x^e modulo `poly`.
+fn pow_x(mut e: u64, poly: u64, degree: u32) -> u64 {
+    let mul_mod = |a: u64, b: u64| {
+        let mut product = 0;
+        for i in (0..degree).rev() {
+            product <<= 1;
+            if (product >> degree) & 1 != 0 {
+                product ^= poly;
+            }
+            if (b >> i) & 1 != 0 {
+                product ^= a;
+            }
+        }
+        product
+    };
+    // x is 1 modulo x + 1
+    let (mut result, mut base) = (1, if degree == 1 { 1 } else { 2 });
+    while e > 0 {
+        if e & 1 != 0 {
+            result = mul_mod(result, base);
+        }
+        base = mul_mod(base, base);
+        e >>= 1;
+    }
+    result
+}
+
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut q = 2;
+    while q * q <= n {
+        if n.is_multiple_of(q) {
+            factors.push(q);
+            while n.is_multiple_of(q) {
+                n /= q;
+            }
+        }
+        q += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
@@ -0,0 +1,144 @@
+//! Tristimulus colors, RGB color spaces and the conversion of RGB colors to spectra.
+use std::ops::{Index, IndexMut};
+
+use auto_ops::{impl_op_ex, impl_op_ex_commutative};
+
+mod builtin;
+
+mod colorspace;
+pub use colorspace::*;
+
+mod encoding;
+pub use encoding::*;
+
+mod rgb_to_spectrum;
+pub use rgb_to_spectrum::*;
+
+use crate::{math::points::Point2f, Float};
+
+/// A color given by its red, green and blue components in some [`RgbColorSpace`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: Float,
+    pub g: Float,
+    pub b: Float,
+}
+
+impl Rgb {
+    pub fn new(r: Float, g: Float, b: Float) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn min_component_value(&self) -> Float {
+        self.r.min(self.g).min(self.b)
+    }
+
+    pub fn max_component_value(&self) -> Float {
+        self.r.max(self.g).max(self.b)
+    }
+
+    /// The index of the largest component, where red is 0.
+    pub fn max_component_index(&self) -> usize {
+        if self.r > self.g {
+            if self.r > self.b {
+                0
+            } else {
+                2
+            }
+        } else if self.g > self.b {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn average(&self) -> Float {
+        (self.r + self.g + self.b) / 3.0
+    }
+
+    pub fn clamp_zero(&self) -> Self {
+        Self::new(self.r.max(0.0), self.g.max(0.0), self.b.max(0.0))
+    }
+}
+
+impl Index<usize> for Rgb {
+    type Output = Float;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        match i {
+            0 => &self.r,
+            1 => &self.g,
+            _ => &self.b,
+        }
+    }
+}
+
+impl IndexMut<usize> for Rgb {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        match i {
+            0 => &mut self.r,
+            1 => &mut self.g,
+            _ => &mut self.b,
+        }
+    }
+}
+
+impl_op_ex!(+|a: &Rgb, b: &Rgb| -> Rgb { Rgb::new(a.r + b.r, a.g + b.g, a.b + b.b) });
+impl_op_ex!(-|a: &Rgb, b: &Rgb| -> Rgb { Rgb::new(a.r - b.r, a.g - b.g, a.b - b.b) });
+impl_op_ex!(*|a: &Rgb, b: &Rgb| -> Rgb { Rgb::new(a.r * b.r, a.g * b.g, a.b * b.b) });
+impl_op_ex!(/|a: &Rgb, b: &Rgb| -> Rgb { Rgb::new(a.r / b.r, a.g / b.g, a.b / b.b) });
+impl_op_ex_commutative!(*|a: &Rgb, b: Float| -> Rgb { Rgb::new(a.r * b, a.g * b, a.b * b) });
+impl_op_ex!(/|a: &Rgb, b: Float| -> Rgb { Rgb::new(a.r / b, a.g / b, a.b / b) });
+impl_op_ex!(+= |a: &mut Rgb, b: &Rgb| { a.r += b.r; a.g += b.g; a.b += b.b; });
+impl_op_ex!(*= |a: &mut Rgb, b: Float| { a.r *= b; a.g *= b; a.b *= b; });
+impl_op_ex!(/= |a: &mut Rgb, b: Float| { a.r /= b; a.g /= b; a.b /= b; });
+
+/// A color given by its CIE XYZ tristimulus values, where `y` is the luminance.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Xyz {
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
+}
+
+impl Xyz {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Creates the color with chromaticity `xy` and luminance `y`.
+    pub fn from_xyy(xy: Point2f, y: Float) -> Self {
+        if xy.y == 0.0 {
+            return Self::new(0.0, 0.0, 0.0);
+        }
+        Self::new(xy.x * y / xy.y, y, (1.0 - xy.x - xy.y) * y / xy.y)
+    }
+
+    /// The chromaticity of the color, which does not depend on its luminance.
+    pub fn xy(&self) -> Point2f {
+        let sum = self.x + self.y + self.z;
+        Point2f::new(self.x / sum, self.y / sum)
+    }
+
+    pub fn average(&self) -> Float {
+        (self.x + self.y + self.z) / 3.0
+    }
+}
+
+impl Index<usize> for Xyz {
+    type Output = Float;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        match i {
+            0 => &self.x,
+            1 => &self.y,
+            _ => &self.z,
+        }
+    }
+}
+
+impl_op_ex!(+|a: &Xyz, b: &Xyz| -> Xyz { Xyz::new(a.x + b.x, a.y + b.y, a.z + b.z) });
+impl_op_ex!(-|a: &Xyz, b: &Xyz| -> Xyz { Xyz::new(a.x - b.x, a.y - b.y, a.z - b.z) });
+impl_op_ex!(*|a: &Xyz, b: &Xyz| -> Xyz { Xyz::new(a.x * b.x, a.y * b.y, a.z * b.z) });
+impl_op_ex_commutative!(*|a: &Xyz, b: Float| -> Xyz { Xyz::new(a.x * b, a.y * b, a.z * b) });
+impl_op_ex!(/|a: &Xyz, b: Float| -> Xyz { Xyz::new(a.x / b, a.y / b, a.z / b) });
@@ -0,0 +1,43 @@
+//! The definitions of the built-in RGB color spaces.
+//!
+//! The `rgb2spec_opt` example also compiles this file to fit the RGB-to-spectrum tables of these
+//! color spaces, so it only depends on the `Float` type of its parent module.
+use super::Float;
+
+/// The primaries and white point of a built-in RGB color space.
+pub struct ColorSpaceDefinition {
+    pub name: &'static str,
+    /// The chromaticities of the red, green and blue primaries.
+    pub primaries: [(Float, Float); 3],
+    /// The correlated color temperature of the CIE D illuminant that gives the white point.
+    pub white_temperature: Float,
+}
+
+pub const SRGB: ColorSpaceDefinition = ColorSpaceDefinition {
+    name: "srgb",
+    primaries: [(0.64, 0.33), (0.3, 0.6), (0.15, 0.06)],
+    white_temperature: 6504.0,
+};
+
+pub const DCI_P3: ColorSpaceDefinition = ColorSpaceDefinition {
+    name: "dci-p3",
+    primaries: [(0.68, 0.32), (0.265, 0.69), (0.15, 0.06)],
+    white_temperature: 6504.0,
+};
+
+pub const REC2020: ColorSpaceDefinition = ColorSpaceDefinition {
+    name: "rec2020",
+    primaries: [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)],
+    white_temperature: 6504.0,
+};
+
+pub const ACES2065_1: ColorSpaceDefinition = ColorSpaceDefinition {
+    name: "aces2065-1",
+    primaries: [(0.7347, 0.2653), (0.0, 1.0), (0.0001, -0.077)],
+    white_temperature: 6000.0,
+};
+
+/// The color spaces that have precomputed tables.
+#[allow(dead_code)]
+pub const BUILTIN_COLOR_SPACES: [&ColorSpaceDefinition; 4] =
+    [&SRGB, &DCI_P3, &REC2020, &ACES2065_1];
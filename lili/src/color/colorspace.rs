@@ -0,0 +1,177 @@
+//! RGB color spaces.
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    color::{
+        builtin, builtin::ColorSpaceDefinition, Rgb, RgbSigmoidPolynomial, RgbToSpectrumTable, Xyz,
+    },
+    math::{matrix::SquareMatrix, points::Point2f},
+    spectrum::{named, spectrum_to_xyz, DenselySampledSpectrum, Spectrum},
+};
+
+/// An RGB color space, given by the chromaticities of its primaries and its white point
+/// illuminant.
+///
+/// # Examples
+///
+/// ```
+/// use lili::color::RgbColorSpace;
+///
+/// let srgb = RgbColorSpace::srgb();
+/// let rec2020 = RgbColorSpace::get_named("rec2020").unwrap();
+///
+/// // White is the same in color spaces with the same white point
+/// let m = RgbColorSpace::convert_rgb_color_space(srgb, rec2020);
+/// let white = m.mul_vector(&[1.0, 1.0, 1.0]);
+/// assert!(white.iter().all(|c| (c - 1.0).abs() < 1e-4));
+///
+/// // Saturated sRGB red lies inside of the Rec. 2020 gamut
+/// let red = m.mul_vector(&[1.0, 0.0, 0.0]);
+/// assert!(red.iter().all(|c| *c > 0.0 && *c < 1.0));
+/// ```
+pub struct RgbColorSpace {
+    name: &'static str,
+    pub r: Point2f,
+    pub g: Point2f,
+    pub b: Point2f,
+    /// The chromaticity of the white point.
+    pub w: Point2f,
+    pub illuminant: Arc<DenselySampledSpectrum>,
+    pub xyz_from_rgb: SquareMatrix<3>,
+    pub rgb_from_xyz: SquareMatrix<3>,
+    rgb_to_spectrum_table: &'static RgbToSpectrumTable,
+}
+
+impl RgbColorSpace {
+    /// Creates the color space with primaries at the chromaticities `r`, `g` and `b`, whose white
+    /// is the color of `illuminant`, and whose colors are converted to spectra with
+    /// `rgb_to_spectrum_table`.
+    ///
+    /// Returns `None` if the primaries are collinear.
+    pub fn new(
+        name: &'static str,
+        r: Point2f,
+        g: Point2f,
+        b: Point2f,
+        illuminant: &dyn Spectrum,
+        rgb_to_spectrum_table: &'static RgbToSpectrumTable,
+    ) -> Option<Self> {
+        let illuminant = Arc::new(DenselySampledSpectrum::from_spectrum(illuminant));
+        let white = spectrum_to_xyz(illuminant.as_ref());
+        let w = white.xy();
+        let (r_xyz, g_xyz, b_xyz) = (
+            Xyz::from_xyy(r, 1.0),
+            Xyz::from_xyy(g, 1.0),
+            Xyz::from_xyy(b, 1.0),
+        );
+        let rgb = SquareMatrix::new([
+            [r_xyz.x, g_xyz.x, b_xyz.x],
+            [r_xyz.y, g_xyz.y, b_xyz.y],
+            [r_xyz.z, g_xyz.z, b_xyz.z],
+        ]);
+
+        // Scale the primaries so that they add up to the white point
+        let c = rgb.inverse()?.mul_vector(&[white.x, white.y, white.z]);
+        let xyz_from_rgb = rgb * SquareMatrix::diag(c);
+        let rgb_from_xyz = xyz_from_rgb.inverse()?;
+
+        Some(Self {
+            name,
+            r,
+            g,
+            b,
+            w,
+            illuminant,
+            xyz_from_rgb,
+            rgb_from_xyz,
+            rgb_to_spectrum_table,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn to_xyz(&self, rgb: Rgb) -> Xyz {
+        let [x, y, z] = self.xyz_from_rgb.mul_vector(&[rgb.r, rgb.g, rgb.b]);
+        Xyz::new(x, y, z)
+    }
+
+    pub fn to_rgb(&self, xyz: Xyz) -> Rgb {
+        let [r, g, b] = self.rgb_from_xyz.mul_vector(&[xyz.x, xyz.y, xyz.z]);
+        Rgb::new(r, g, b)
+    }
+
+    /// Returns the spectrum for a color with components between 0 and 1.
+    pub fn to_rgb_coeffs(&self, rgb: Rgb) -> RgbSigmoidPolynomial {
+        self.rgb_to_spectrum_table.evaluate(rgb.clamp_zero())
+    }
+
+    /// The matrix that converts colors in `from` to colors in `to`.
+    pub fn convert_rgb_color_space(from: &RgbColorSpace, to: &RgbColorSpace) -> SquareMatrix<3> {
+        if std::ptr::eq(from, to) {
+            return SquareMatrix::identity();
+        }
+        to.rgb_from_xyz * from.xyz_from_rgb
+    }
+
+    /// The sRGB color space, which most images and displays use.
+    pub fn srgb() -> &'static RgbColorSpace {
+        static SRGB: OnceLock<RgbColorSpace> = OnceLock::new();
+        SRGB.get_or_init(|| Self::builtin(&builtin::SRGB))
+    }
+
+    /// The DCI-P3 color space, with the D65 white point of Display P3.
+    pub fn dci_p3() -> &'static RgbColorSpace {
+        static DCI_P3: OnceLock<RgbColorSpace> = OnceLock::new();
+        DCI_P3.get_or_init(|| Self::builtin(&builtin::DCI_P3))
+    }
+
+    /// The ITU-R Rec. 2020 color space of ultra high definition television.
+    pub fn rec2020() -> &'static RgbColorSpace {
+        static REC2020: OnceLock<RgbColorSpace> = OnceLock::new();
+        REC2020.get_or_init(|| Self::builtin(&builtin::REC2020))
+    }
+
+    /// The ACES2065-1 color space, whose primaries enclose all visible colors.
+    pub fn aces2065_1() -> &'static RgbColorSpace {
+        static ACES2065_1: OnceLock<RgbColorSpace> = OnceLock::new();
+        ACES2065_1.get_or_init(|| Self::builtin(&builtin::ACES2065_1))
+    }
+
+    /// Returns the built-in color space with the given name, as used by the `ColorSpace`
+    /// directive.
+    pub fn get_named(name: &str) -> Option<&'static RgbColorSpace> {
+        match name {
+            "srgb" => Some(Self::srgb()),
+            "dci-p3" => Some(Self::dci_p3()),
+            "rec2020" => Some(Self::rec2020()),
+            "aces2065-1" => Some(Self::aces2065_1()),
+            _ => None,
+        }
+    }
+
+    fn builtin(definition: &ColorSpaceDefinition) -> Self {
+        let [r, g, b] = definition.primaries.map(|(x, y)| Point2f::new(x, y));
+        let illuminant = named::std_illuminant_d(definition.white_temperature);
+        let table = RgbToSpectrumTable::get_named(definition.name)
+            .expect("the tables of the built-in color spaces are precomputed");
+        Self::new(definition.name, r, g, b, &illuminant, table)
+            .expect("the primaries are not collinear")
+    }
+}
+
+impl fmt::Debug for RgbColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RgbColorSpace")
+            .field("name", &self.name)
+            .field("r", &(self.r.x, self.r.y))
+            .field("g", &(self.g.x, self.g.y))
+            .field("b", &(self.b.x, self.b.y))
+            .field("w", &(self.w.x, self.w.y))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,51 @@
+//! Encodings of color components stored with 8 bits.
+use crate::Float;
+
+/// How the values of 8-bit color components map to linear values between 0 and 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorEncoding {
+    /// Values are proportional to the component.
+    Linear,
+    /// Values follow the sRGB transfer curve, which spends more of them on dark colors.
+    #[default]
+    Srgb,
+}
+
+impl ColorEncoding {
+    /// The linear value of the encoded component `v`.
+    pub fn to_linear(self, v: u8) -> Float {
+        let v = v as Float / 255.0;
+        match self {
+            Self::Linear => v,
+            Self::Srgb => srgb_to_linear(v),
+        }
+    }
+
+    /// Encodes the linear value `v`, which is clamped to between 0 and 1.
+    pub fn from_linear(self, v: Float) -> u8 {
+        let v = v.clamp(0.0, 1.0);
+        let v = match self {
+            Self::Linear => v,
+            Self::Srgb => linear_to_srgb(v),
+        };
+        (v * 255.0).round() as u8
+    }
+}
+
+/// Applies the sRGB transfer curve to a linear value between 0 and 1.
+pub fn linear_to_srgb(v: Float) -> Float {
+    if v <= 0.0031308 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Undoes the sRGB transfer curve, giving the linear value of `v`.
+pub fn srgb_to_linear(v: Float) -> Float {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,165 @@
+//! Conversion of RGB colors to smooth spectra, following Jakob and Hanika, "A Low-Dimensional
+//! Function Space for Efficient Spectral Upsampling" (2019).
+use std::sync::OnceLock;
+
+use fast_polynomial::poly_array;
+
+use crate::{
+    color::Rgb,
+    math::FloatExt,
+    spectrum::{LAMBDA_MAX, LAMBDA_MIN},
+    Float,
+};
+
+/// A spectrum given by a quadratic polynomial in wavelength, passed through a sigmoid so that its
+/// values lie between 0 and 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RgbSigmoidPolynomial {
+    c0: Float,
+    c1: Float,
+    c2: Float,
+}
+
+impl RgbSigmoidPolynomial {
+    /// Creates the spectrum `s(c0 λ² + c1 λ + c2)`, where `s` is the sigmoid.
+    pub fn new(c0: Float, c1: Float, c2: Float) -> Self {
+        Self { c0, c1, c2 }
+    }
+
+    pub fn evaluate(&self, lambda: Float) -> Float {
+        sigmoid(poly_array(lambda, &[self.c2, self.c1, self.c0]))
+    }
+
+    /// The largest value over the visible range, which is at one of its ends or at the extremum
+    /// of the polynomial.
+    pub fn max_value(&self) -> Float {
+        let result = self.evaluate(LAMBDA_MIN).max(self.evaluate(LAMBDA_MAX));
+        let lambda = -self.c1 / (2.0 * self.c0);
+        if (LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+            result.max(self.evaluate(lambda))
+        } else {
+            result
+        }
+    }
+}
+
+fn sigmoid(x: Float) -> Float {
+    if x.is_infinite() {
+        return if x > 0.0 { 1.0 } else { 0.0 };
+    }
+    0.5 + x / (2.0 * (1.0 + x * x).sqrt())
+}
+
+/// The number of samples along each axis of an [`RgbToSpectrumTable`].
+pub const RGB_TO_SPECTRUM_TABLE_RESOLUTION: usize = 64;
+
+const RES: usize = RGB_TO_SPECTRUM_TABLE_RESOLUTION;
+
+/// The precomputed tables of the built-in color spaces, by name, which the `rgb2spec_opt` example
+/// fits.
+const PRECOMPUTED_TABLES: [(&str, &[u8]); 4] = [
+    ("srgb", include_bytes!("tables/srgb.rgbspectrum")),
+    ("dci-p3", include_bytes!("tables/dci-p3.rgbspectrum")),
+    ("rec2020", include_bytes!("tables/rec2020.rgbspectrum")),
+    (
+        "aces2065-1",
+        include_bytes!("tables/aces2065-1.rgbspectrum"),
+    ),
+];
+
+/// The sigmoid polynomial coefficients of RGB colors with components between 0 and 1, for one
+/// color space.
+///
+/// Colors are looked up by their largest component, which selects one of three tables, and the
+/// other two components relative to it. Coefficients in between the samples are interpolated.
+///
+/// The coefficients are fitted ahead of time with Gauss-Newton iterations that minimize
+/// the difference between the color of the spectrum under the illuminant of the color space and
+/// the target color, in CIELAB.
+pub struct RgbToSpectrumTable {
+    /// The values of the largest component that the tables are sampled at, which are spaced more
+    /// densely near 0 and 1.
+    z_nodes: Vec<Float>,
+    /// The coefficients, indexed by the largest component, then by the samples of its value, of
+    /// the component after it and of the one after that.
+    coeffs: Vec<[Float; 3]>,
+}
+
+impl RgbToSpectrumTable {
+    /// Returns the precomputed table of the built-in color space with the given name.
+    pub fn get_named(name: &str) -> Option<&'static RgbToSpectrumTable> {
+        static TABLES: [OnceLock<RgbToSpectrumTable>; PRECOMPUTED_TABLES.len()] =
+            [const { OnceLock::new() }; PRECOMPUTED_TABLES.len()];
+        let i = PRECOMPUTED_TABLES.iter().position(|(n, _)| *n == name)?;
+        Some(TABLES[i].get_or_init(|| Self::from_bytes(PRECOMPUTED_TABLES[i].1)))
+    }
+
+    /// Reads a table in the format written by the `rgb2spec_opt` example.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut words = bytes
+            .chunks_exact(4)
+            .map(|b| b.try_into().expect("chunks have 4 bytes"));
+        let res = words.next().map(u32::from_le_bytes);
+        assert_eq!(res, Some(RES as u32), "the table has the wrong resolution");
+
+        let mut values = words.map(|w| f32::from_le_bytes(w) as Float);
+        let z_nodes: Vec<Float> = values.by_ref().take(RES).collect();
+        let values: Vec<Float> = values.collect();
+        let coeffs: Vec<[Float; 3]> = values.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        assert_eq!(coeffs.len(), 3 * RES * RES * RES, "the table is truncated");
+        Self { z_nodes, coeffs }
+    }
+
+    /// Returns the spectrum for a color with components between 0 and 1.
+    pub fn evaluate(&self, rgb: Rgb) -> RgbSigmoidPolynomial {
+        debug_assert!(
+            (0.0..=1.0).contains(&rgb.min_component_value())
+                && (0.0..=1.0).contains(&rgb.max_component_value()),
+            "RGB components must be between 0 and 1"
+        );
+
+        // Grays are constant spectra, for which the sigmoid can be inverted directly
+        if rgb.r == rgb.g && rgb.g == rgb.b {
+            return RgbSigmoidPolynomial::new(
+                0.0,
+                0.0,
+                (rgb.r - 0.5) / (rgb.r * (1.0 - rgb.r)).sqrt(),
+            );
+        }
+
+        // The position of the color in the table of its largest component
+        let maxc = rgb.max_component_index();
+        let z = rgb[maxc];
+        let x = rgb[(maxc + 1) % 3] * (RES - 1) as Float / z;
+        let y = rgb[(maxc + 2) % 3] * (RES - 1) as Float / z;
+
+        let xi = (x as usize).min(RES - 2);
+        let yi = (y as usize).min(RES - 2);
+        let zi = self
+            .z_nodes
+            .partition_point(|node| *node < z)
+            .clamp(1, RES - 1)
+            - 1;
+        let dx = x - xi as Float;
+        let dy = y - yi as Float;
+        let dz = (z - self.z_nodes[zi]) / (self.z_nodes[zi + 1] - self.z_nodes[zi]);
+
+        // Trilinear interpolation of the coefficients
+        let co = |dxi: usize, dyi: usize, dzi: usize, c: usize| {
+            self.coeffs[((maxc * RES + zi + dzi) * RES + yi + dyi) * RES + xi + dxi][c]
+        };
+        let c = [0, 1, 2].map(|c| {
+            dz.lerp(
+                dy.lerp(
+                    dx.lerp(co(0, 0, 0, c), co(1, 0, 0, c)),
+                    dx.lerp(co(0, 1, 0, c), co(1, 1, 0, c)),
+                ),
+                dy.lerp(
+                    dx.lerp(co(0, 0, 1, c), co(1, 0, 1, c)),
+                    dx.lerp(co(0, 1, 1, c), co(1, 1, 1, c)),
+                ),
+            )
+        });
+        RgbSigmoidPolynomial::new(c[0], c[1], c[2])
+    }
+}
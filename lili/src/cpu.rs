@@ -1 +1,3 @@
+pub mod checkpoint;
+
 pub mod integrator;
@@ -0,0 +1,159 @@
+//! Checkpoints that let an interrupted render continue where it stopped.
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{film::Film, sampler::Sampler};
+
+/// The progress of a render through its sample waves, saved along with the state of its film.
+///
+/// Samplers produce the same samples for a pixel and sample index however they are reached, so
+/// a render resumed from a checkpoint gives exactly the same image as one that ran without
+/// stopping, as long as it renders the same scene with the same sampler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderCheckpoint {
+    /// The fingerprint of the scene description.
+    pub scene_fingerprint: u64,
+    /// The name of the sampler.
+    pub sampler: String,
+    /// The seed of the sampler, which the scene may set instead of the options.
+    pub seed: i32,
+    pub samples_per_pixel: i32,
+    /// The first sample of the next wave, which is the number of samples already taken in each
+    /// pixel.
+    pub wave_start: i32,
+    /// The end of the next wave.
+    pub wave_end: i32,
+    /// The size of the wave after the next one.
+    pub next_wave_size: i32,
+}
+
+impl RenderCheckpoint {
+    const MAGIC: &'static [u8; 8] = b"LILICKPT";
+    const VERSION: u32 = 2;
+
+    /// The checkpoint of a render of the scene with the given fingerprint by `sampler`, whose
+    /// next wave is given by `wave_start`, `wave_end` and `next_wave_size`.
+    pub fn new(
+        scene_fingerprint: u64,
+        sampler: &Sampler,
+        wave_start: i32,
+        wave_end: i32,
+        next_wave_size: i32,
+    ) -> Self {
+        Self {
+            scene_fingerprint,
+            sampler: sampler.name().to_string(),
+            seed: sampler.seed(),
+            samples_per_pixel: sampler.samples_per_pixel(),
+            wave_start,
+            wave_end,
+            next_wave_size,
+        }
+    }
+
+    /// Writes the checkpoint and the state of `film` to `path`.
+    ///
+    /// The file is replaced only once the new checkpoint is complete, so an earlier checkpoint
+    /// survives if rendering stops while writing.
+    pub fn write(&self, path: impl AsRef<Path>, film: &Film) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut w = BufWriter::new(File::create(&tmp_path)?);
+        w.write_all(Self::MAGIC)?;
+        w.write_all(&Self::VERSION.to_le_bytes())?;
+        w.write_all(&self.scene_fingerprint.to_le_bytes())?;
+        w.write_all(&(self.sampler.len() as u32).to_le_bytes())?;
+        w.write_all(self.sampler.as_bytes())?;
+        for v in [
+            self.seed,
+            self.samples_per_pixel,
+            self.wave_start,
+            self.wave_end,
+            self.next_wave_size,
+        ] {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        film.write_state(&mut w)?;
+        w.into_inner()?.sync_all()?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Reads a checkpoint and restores the state of `film` from it.
+    ///
+    /// Fails without changing the film if the checkpoint is of a render of a different scene, or
+    /// with a different sampler, seed or sample count, or of a different film.
+    pub fn read(
+        path: impl AsRef<Path>,
+        film: &Film,
+        scene_fingerprint: u64,
+        sampler: &Sampler,
+    ) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(invalid("not a render checkpoint".to_string()));
+        }
+        let version = u32::from_le_bytes(read_array(&mut r)?);
+        if version != Self::VERSION {
+            return Err(invalid(format!("unsupported checkpoint version {version}")));
+        }
+        let fingerprint = u64::from_le_bytes(read_array(&mut r)?);
+        let mut name = vec![0; u32::from_le_bytes(read_array(&mut r)?) as usize];
+        r.read_exact(&mut name)?;
+        let mut read_i32 = || read_array(&mut r).map(i32::from_le_bytes);
+        let checkpoint = Self {
+            scene_fingerprint: fingerprint,
+            sampler: String::from_utf8(name)
+                .map_err(|_| invalid("the sampler name is not valid UTF-8".to_string()))?,
+            seed: read_i32()?,
+            samples_per_pixel: read_i32()?,
+            wave_start: read_i32()?,
+            wave_end: read_i32()?,
+            next_wave_size: read_i32()?,
+        };
+
+        if checkpoint.scene_fingerprint != scene_fingerprint {
+            return Err(invalid(
+                "the checkpoint is of a render of a different scene".to_string(),
+            ));
+        }
+        if checkpoint.sampler != sampler.name() {
+            return Err(invalid(format!(
+                "the checkpoint was rendered with the {} sampler, not {}",
+                checkpoint.sampler,
+                sampler.name()
+            )));
+        }
+        if checkpoint.seed != sampler.seed() {
+            return Err(invalid(format!(
+                "the checkpoint was rendered with seed {}, not {}",
+                checkpoint.seed,
+                sampler.seed()
+            )));
+        }
+        if checkpoint.samples_per_pixel != sampler.samples_per_pixel() {
+            return Err(invalid(format!(
+                "the checkpoint was rendered with {} samples per pixel, not {}",
+                checkpoint.samples_per_pixel,
+                sampler.samples_per_pixel()
+            )));
+        }
+        film.read_state(&mut r)?;
+        Ok(checkpoint)
+    }
+}
+
+/// Reads the next `N` bytes.
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut b = [0; N];
+    r.read_exact(&mut b)?;
+    Ok(b)
+}
@@ -1,12 +1,25 @@
-use std::ops::{Add, Div, Mul};
+use std::{io, time::Instant};
+
+use rayon::{prelude::*, ThreadPoolBuilder};
 
 use crate::{
+    cpu::checkpoint::RenderCheckpoint,
+    error::warning,
+    film::{Film, VisibleSurface},
+    filter::{Filter, FilterSample},
+    interaction::SurfaceInteraction,
     math::{
+        bounds::{Bounds2i, Bounds3f},
+        dot::Dot,
         points::{Point2f, Point2i},
         vectors::Vector3f,
         FloatExt,
     },
+    memory::ScratchBuffer,
+    progress::{ProgressReporter, ProgressStyle},
     rays::{Ray, RayDifferential},
+    sampler::Sampler,
+    spectrum::{SampledSpectrum, SampledWavelengths},
     Float, Options,
 };
 
@@ -15,10 +28,6 @@ fn sample_uniform_sphere(u: Point2f) -> Vector3f {
     todo!()
 }
 
-fn abs_dot(a: &Vector3f, b: &Vector3f) -> Float {
-    todo!()
-}
-
 struct Primitive {}
 
 impl Primitive {
@@ -66,6 +75,8 @@ enum LightType {
     Infinite,
 }
 
+/// The BSDF at a surface intersection, which is allocated in the scratch buffer of the sample
+/// along with its BxDF.
 struct Bsdf {}
 
 impl Bsdf {
@@ -74,13 +85,9 @@ impl Bsdf {
     }
 }
 
-struct Shading {
-    n: Vector3f,
-}
-
 struct ShapeIntersection {
     intr: SurfaceInteraction,
-    shading: Shading,
+    t_hit: Float,
 }
 
 impl ShapeIntersection {
@@ -88,51 +95,22 @@ impl ShapeIntersection {
         todo!()
     }
 
-    fn bsdf(
+    fn bsdf<'a>(
         &self,
         ray: &Ray,
         lambda: &SampledWavelengths,
         camera: &Camera,
-        scratch_buffer: &ScratchBuffer,
+        scratch_buffer: &'a ScratchBuffer,
         sampler: &Sampler,
-    ) -> Bsdf {
+    ) -> &'a Bsdf {
         todo!()
     }
 
     fn spawn_ray(&self, wp: &Vector3f) -> RayDifferential {
-        todo!()
-    }
-}
-
-struct SurfaceInteraction {}
-
-#[derive(Default)]
-struct Bounds3f {}
-
-#[derive(Clone, Copy)]
-struct Sampler {}
-
-impl Sampler {
-    fn samples_per_pixel(&self) -> i32 {
-        todo!()
-    }
-
-    fn start_pixel_sample(&self, pixel: &Point2i, sample_index: i32) {
-        todo!()
-    }
-
-    fn get_1d(&self) -> Float {
-        todo!()
-    }
-
-    fn get_2d(&self) -> Point2f {
-        todo!()
+        self.intr.spawn_ray(wp)
     }
 }
 
-#[derive(Default)]
-struct ScratchBuffer {}
-
 struct Camera {
     pub film: Film,
 }
@@ -147,80 +125,48 @@ impl Camera {
     }
 }
 
-struct Film {}
-
-impl Film {
-    fn pixel_bounds(&self) -> Bounds2i {
-        todo!()
-    }
-
-    fn sample_wavelengths(&self, sample: Float) -> SampledWavelengths {
-        todo!()
-    }
-
-    fn filter(&self) -> Filter {
-        todo!()
-    }
-
-    fn uses_visible_surface(&self) -> bool {
-        todo!()
-    }
-
-    fn add_sample(
-        &mut self,
-        pixel: Point2i,
-        l: SampledSpectrum,
-        lambda: SampledWavelengths,
-        visible_surface: &VisibleSurface,
-        filter_weight: Float,
-    ) {
-        todo!()
-    }
-}
-
 #[derive(Clone, Copy)]
 struct CameraSample {
+    pub p_film: Point2f,
+    pub p_lens: Point2f,
+    pub time: Float,
     pub filter_weight: Float,
 }
 
 impl CameraSample {
-    fn new(sampler: &Sampler, pixel: Point2i, filter: Filter) -> Self {
-        todo!()
-    }
-}
-
-struct Filter {}
-
-struct Bounds2i {}
-
-impl Bounds2i {
-    fn area(&self) -> i64 {
-        todo!()
-    }
-
-    fn x(&self) -> i32 {
-        todo!()
-    }
+    /// Samples a point in `pixel` according to the filter, along with the lens position and time
+    /// of the camera ray through it.
+    ///
+    /// With `disable_pixel_jitter`, every sample is at the center of the pixel.
+    fn new(
+        sampler: &mut Sampler,
+        pixel: Point2i,
+        filter: &Filter,
+        disable_pixel_jitter: bool,
+    ) -> Self {
+        let mut fs = filter.sample(sampler.get_pixel_2d());
+        if disable_pixel_jitter {
+            fs = FilterSample {
+                p: Point2f::new(0.0, 0.0),
+                weight: 1.0,
+            };
+        }
 
-    fn y(&self) -> i32 {
-        todo!()
+        Self {
+            p_film: Point2f::new(
+                pixel.x as Float + 0.5 + fs.p.x,
+                pixel.y as Float + 0.5 + fs.p.y,
+            ),
+            time: sampler.get_1d(),
+            p_lens: sampler.get_2d(),
+            filter_weight: fs.weight,
+        }
     }
 }
 
 trait Renderer {
-    fn render(&mut self, options: Options);
-}
-
-struct ProgressReporter {}
-
-impl ProgressReporter {
-    fn new(total: i64, message: &str, quiet: bool) -> Self {
-        todo!()
-    }
-
-    fn update(&self, progress: i32) {
-        todo!()
-    }
+    /// Renders the image and writes it.
+    fn render(&mut self, options: Options) -> io::Result<()>;
 }
 
 struct CameraRayDifferential {
@@ -228,56 +174,6 @@ struct CameraRayDifferential {
     pub weight: SampledSpectrum,
 }
 
-struct SampledWavelengths {}
-
-#[derive(Default)]
-struct SampledSpectrum {}
-
-impl SampledSpectrum {
-    fn new(wavelength: Float) -> Self {
-        todo!()
-    }
-
-    fn nonzero(&self) -> bool {
-        todo!()
-    }
-}
-
-impl Mul for SampledSpectrum {
-    type Output = SampledSpectrum;
-
-    fn mul(self, rhs: Self) -> Self::Output {
-        todo!()
-    }
-}
-
-impl Mul<Float> for SampledSpectrum {
-    type Output = SampledSpectrum;
-
-    fn mul(self, rhs: Float) -> Self::Output {
-        todo!()
-    }
-}
-
-impl Div<Float> for SampledSpectrum {
-    type Output = SampledSpectrum;
-
-    fn div(self, rhs: Float) -> Self::Output {
-        todo!()
-    }
-}
-
-impl Add for SampledSpectrum {
-    type Output = SampledSpectrum;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        todo!()
-    }
-}
-
-#[derive(Default)]
-struct VisibleSurface {}
-
 trait IntersectorTrait {
     fn intersect(&self, ray: &Ray, t_max: Float) -> Option<ShapeIntersection>;
 
@@ -336,14 +232,16 @@ impl IntersectorTrait for AggregateIntersector {
     }
 }
 
-trait PixelEvaluator {
+/// Computes the samples of a pixel, which may happen on any of the rendering threads.
+trait PixelEvaluator: Sync {
     fn evaluate_pixel_sample(
         &self,
         pixel: Point2i,
         sample_index: i32,
-        sampler: Sampler,
+        sampler: &mut Sampler,
         scratch_buffer: &ScratchBuffer,
-        camera: &mut Camera,
+        camera: &Camera,
+        options: &Options,
     );
 }
 
@@ -353,67 +251,167 @@ struct ImageTileIntegrator<E: PixelEvaluator> {
     pub sampler_prototype: Sampler,
 
     pub pixel_evaluator: E,
+
+    /// The fingerprint of the scene, which checkpoints are checked against.
+    pub scene_fingerprint: u64,
 }
 
 impl<E: PixelEvaluator> ImageTileIntegrator<E> {
-    fn new(camera: Camera, sampler: Sampler, pixel_evaluator: E) -> ImageTileIntegrator<E> {
+    fn new(
+        camera: Camera,
+        sampler: Sampler,
+        pixel_evaluator: E,
+        scene_fingerprint: u64,
+    ) -> ImageTileIntegrator<E> {
         Self {
             camera,
             sampler_prototype: sampler,
             pixel_evaluator,
+            scene_fingerprint,
         }
     }
 }
 
 impl<E: PixelEvaluator> Renderer for ImageTileIntegrator<E> {
-    fn render(&mut self, options: Options) {
-        // TODO: Thread local these, pg 25
-        let sampler = self.sampler_prototype;
-        let scratch_buffer = ScratchBuffer::default();
-
+    fn render(&mut self, options: Options) -> io::Result<()> {
         let pixel_bounds = self.camera.film.pixel_bounds();
         let spp = self.sampler_prototype.samples_per_pixel();
-        let progress =
-            ProgressReporter::new(spp as i64 * pixel_bounds.area(), "Rendering", options.quiet);
+        let mut progress = ProgressReporter::new(
+            spp as i64 * pixel_bounds.point_count() as i64,
+            "Rendering",
+            ProgressStyle::from_options(&options),
+        );
+
+        // A thread count of 0 gives one thread per core
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(options.n_threads)
+            .build()
+            .map_err(|err| {
+                io::Error::other(format!("unable to start the rendering threads: {err}"))
+            })?;
+        let tiles = tiles(pixel_bounds, Self::TILE_SIZE);
 
         let mut wave_start = 0;
         let mut wave_end = 1;
         let mut next_wave_size = 1;
+        if let Some(path) = &options.resume_file {
+            // Starting over would overwrite the checkpoint, so a render that cannot resume stops
+            let checkpoint = RenderCheckpoint::read(
+                path,
+                &self.camera.film,
+                self.scene_fingerprint,
+                &self.sampler_prototype,
+            )
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!("{path}: unable to resume render: {err}"),
+                )
+            })?;
+            wave_start = checkpoint.wave_start;
+            wave_end = checkpoint.wave_end;
+            next_wave_size = checkpoint.next_wave_size;
+
+            // The samples of the earlier waves are already in the film
+            progress.update(wave_start as i64 * pixel_bounds.point_count() as i64);
+        }
+        let mut last_write = Instant::now();
 
         while wave_start < spp {
-            // TODO: parallelize by chunking into tiles, pg 27
-            for i in 0..pixel_bounds.x() {
-                for j in 0..pixel_bounds.y() {
-                    let pixel = Point2i::new(i, j);
-                    for sample_index in wave_start..wave_end {
-                        sampler.start_pixel_sample(&pixel, sample_index);
-                        self.pixel_evaluator.evaluate_pixel_sample(
-                            pixel.clone(),
-                            sample_index,
-                            sampler,
-                            &scratch_buffer,
-                            &mut self.camera,
-                        );
-                    }
-                    progress.update(wave_end - wave_start)
-                }
-            }
+            // Every pixel lies in a single tile, so its samples are always added to the film in
+            // the same order, however many threads there are
+            pool.install(|| {
+                tiles.par_iter().for_each_init(
+                    || (self.sampler_prototype.clone(), ScratchBuffer::default()),
+                    |(sampler, scratch_buffer), tile| {
+                        for pixel in tile {
+                            for sample_index in wave_start..wave_end {
+                                sampler.start_pixel_sample(pixel, sample_index, 0);
+                                self.pixel_evaluator.evaluate_pixel_sample(
+                                    pixel,
+                                    sample_index,
+                                    sampler,
+                                    scratch_buffer,
+                                    &self.camera,
+                                    &options,
+                                );
+                                // Nothing allocated for a sample outlives it
+                                scratch_buffer.reset();
+                            }
+                        }
+                        progress.update((wave_end - wave_start) as i64 * tile.point_count() as i64);
+                    },
+                );
+            });
 
             wave_start = wave_end;
             wave_end = spp.min(wave_end + next_wave_size);
-            next_wave_size = 2 * next_wave_size.min(64);
+            next_wave_size = (2 * next_wave_size).min(64);
 
-            // TODO: write current image to disk, pg 28
+            // Write the partial image so that long renders can be previewed and are not lost if
+            // they stop early, but no more often than the checkpoint interval
+            if wave_start < spp && last_write.elapsed() >= options.checkpoint_interval {
+                if let Err(err) = self.write_image(&options) {
+                    warning(None, err.to_string());
+                }
+                if let Some(path) = &options.checkpoint_file {
+                    let checkpoint = RenderCheckpoint::new(
+                        self.scene_fingerprint,
+                        &self.sampler_prototype,
+                        wave_start,
+                        wave_end,
+                        next_wave_size,
+                    );
+                    if let Err(err) = checkpoint.write(path, &self.camera.film) {
+                        warning(None, format!("{path}: unable to write checkpoint: {err}"));
+                    }
+                }
+                last_write = Instant::now();
+            }
         }
+
+        progress.done();
+
+        self.write_image(&options)
+    }
+}
+
+/// Splits `bounds` into square tiles of `size` pixels, which are narrower at its right edge and
+/// shorter at its bottom edge.
+fn tiles(bounds: Bounds2i, size: i32) -> Vec<Bounds2i> {
+    let Bounds2i { p_min, p_max } = bounds;
+    (p_min.y..p_max.y)
+        .step_by(size as usize)
+        .flat_map(|y| {
+            (p_min.x..p_max.x).step_by(size as usize).map(move |x| {
+                Bounds2i::new(
+                    Point2i::new(x, y),
+                    Point2i::new((x + size).min(p_max.x), (y + size).min(p_max.y)),
+                )
+            })
+        })
+        .collect()
+}
+
+impl<E: PixelEvaluator> ImageTileIntegrator<E> {
+    /// The width and height of the tiles that rendering threads take turns to render.
+    const TILE_SIZE: i32 = 16;
+
+    /// Writes the image of the film to the output file.
+    fn write_image(&self, options: &Options) -> io::Result<()> {
+        let film = &self.camera.film;
+        let filename = options.image_file.as_deref().unwrap_or(film.filename());
+        film.write_image(filename)
+            .map_err(|err| io::Error::other(format!("{filename}: unable to write image: {err}")))
     }
 }
 
-trait RadianceComputer {
+trait RadianceComputer: Sync {
     fn li(
         &self,
         ray: RayDifferential,
         lambda: &SampledWavelengths,
-        sampler: Sampler,
+        sampler: &mut Sampler,
         scratch_buffer: &ScratchBuffer,
         camera: &Camera,
         visible_surface: Option<&mut VisibleSurface>,
@@ -429,10 +427,11 @@ impl<R: RadianceComputer> RayIntegrator<R> {
         camera: Camera,
         sampler: Sampler,
         radiance_computer: R,
+        scene_fingerprint: u64,
     ) -> ImageTileIntegrator<RayIntegrator<R>> {
         let e = Self { radiance_computer };
 
-        ImageTileIntegrator::new(camera, sampler, e)
+        ImageTileIntegrator::new(camera, sampler, e, scene_fingerprint)
     }
 }
 
@@ -441,15 +440,16 @@ impl<R: RadianceComputer> PixelEvaluator for RayIntegrator<R> {
         &self,
         pixel: Point2i,
         _sample_index: i32,
-        sampler: Sampler,
+        sampler: &mut Sampler,
         scratch_buffer: &ScratchBuffer,
-        camera: &mut Camera,
+        camera: &Camera,
+        options: &Options,
     ) {
         let lu = sampler.get_1d();
         let lambda = camera.film.sample_wavelengths(lu);
 
         let filter = camera.film.filter();
-        let camera_sample = CameraSample::new(&sampler, pixel.clone(), filter);
+        let camera_sample = CameraSample::new(sampler, pixel, filter, options.disable_pixel_jitter);
 
         let mut camera_ray = camera.generate_ray_differential(camera_sample, &lambda);
 
@@ -479,9 +479,9 @@ impl<R: RadianceComputer> PixelEvaluator for RayIntegrator<R> {
 
         camera.film.add_sample(
             pixel,
-            l,
-            lambda,
-            &visible_surface,
+            &l,
+            &lambda,
+            Some(&visible_surface),
             camera_sample.filter_weight,
         );
     }
@@ -499,20 +499,21 @@ impl RandomWalkIntegrator {
         camera: Camera,
         sampler: Sampler,
         intersector: AggregateIntersector,
+        scene_fingerprint: u64,
     ) -> ImageTileIntegrator<RayIntegrator<Self>> {
         let random_walk = Self {
             intersector,
             max_depth,
         };
 
-        RayIntegrator::new(camera, sampler, random_walk)
+        RayIntegrator::new(camera, sampler, random_walk, scene_fingerprint)
     }
 
     fn li_random_walk(
         &self,
         ray: RayDifferential,
         lambda: &SampledWavelengths,
-        sampler: Sampler,
+        sampler: &mut Sampler,
         scratch_buffer: &ScratchBuffer,
         camera: &Camera,
         depth: i32,
@@ -538,10 +539,10 @@ impl RandomWalkIntegrator {
                 }
 
                 // Evaluate bsdf for entering ray w_o and randomly sampled exiting ray w_p
-                let bsdf = isect.bsdf(&ray.ray, lambda, camera, scratch_buffer, &sampler);
+                let bsdf = isect.bsdf(&ray.ray, lambda, camera, scratch_buffer, sampler);
                 let u = sampler.get_2d();
                 let wp = sample_uniform_sphere(u);
-                let fcos = bsdf.f(&wo, &wp) * abs_dot(&wp, &isect.shading.n);
+                let fcos = bsdf.f(&wo, &wp) * wp.abs_dot(isect.intr.shading.n);
                 if !fcos.nonzero() {
                     return le;
                 }
@@ -561,7 +562,7 @@ impl RadianceComputer for RandomWalkIntegrator {
         &self,
         ray: RayDifferential,
         lambda: &SampledWavelengths,
-        sampler: Sampler,
+        sampler: &mut Sampler,
         scratch_buffer: &ScratchBuffer,
         camera: &Camera,
         _visible_surface: Option<&mut VisibleSurface>,
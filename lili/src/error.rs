@@ -0,0 +1,44 @@
+//! Error reporting utilities.
+use std::{fmt, sync::Arc};
+
+/// A location in a scene description file, used to report errors and warnings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLoc {
+    /// The name of the file.
+    pub filename: Arc<str>,
+    /// The line number, starting at 1.
+    pub line: i32,
+    /// The column number, starting at 1.
+    pub column: i32,
+}
+
+impl FileLoc {
+    /// Creates a location at the start of the given file.
+    pub fn new(filename: Arc<str>) -> Self {
+        Self {
+            filename,
+            line: 1,
+            column: 0,
+        }
+    }
+}
+
+impl Default for FileLoc {
+    fn default() -> Self {
+        Self::new("(unknown)".into())
+    }
+}
+
+impl fmt::Display for FileLoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.filename, self.line, self.column)
+    }
+}
+
+/// Prints a warning to standard error, prefixed by its location if one is given.
+pub fn warning(loc: Option<&FileLoc>, message: impl fmt::Display) {
+    match loc {
+        Some(loc) => eprintln!("{loc}: Warning: {message}"),
+        None => eprintln!("Warning: {message}"),
+    }
+}
@@ -0,0 +1,445 @@
+//! Films, which accumulate the radiance that reaches each pixel of the image.
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+mod rgb;
+pub use rgb::*;
+
+mod gbuffer;
+pub use gbuffer::*;
+
+mod spectral;
+pub use spectral::*;
+
+mod sensor;
+pub use sensor::*;
+
+use crate::{
+    color::{ColorEncoding, Rgb},
+    filter::Filter,
+    image::{Image, ImageError, ImageMetadata, PixelFormat},
+    interaction::SurfaceInteraction,
+    math::{
+        bounds::Bounds2i,
+        normals::Normal3f,
+        points::{Point2f, Point2i, Point3f},
+        transform::Transform,
+        Float,
+    },
+    scene::{parser::ParseError, SceneEntity},
+    spectrum::{SampledSpectrum, SampledWavelengths},
+};
+
+/// The geometry of the first surface that a camera ray hits, for films that store more than
+/// radiance.
+#[derive(Default, Clone, Copy)]
+pub struct VisibleSurface {
+    pub p: Point3f,
+    pub n: Normal3f,
+    /// The shading normal.
+    pub ns: Normal3f,
+    pub uv: Point2f,
+    pub time: Float,
+    /// The reflectance of the surface, for the wavelengths of the camera ray.
+    pub albedo: SampledSpectrum,
+    set: bool,
+}
+
+impl VisibleSurface {
+    pub fn new(si: &SurfaceInteraction, albedo: SampledSpectrum) -> Self {
+        Self {
+            p: si.p(),
+            n: si.n,
+            ns: si.shading.n,
+            uv: si.uv,
+            time: si.time,
+            albedo,
+            set: true,
+        }
+    }
+
+    /// Returns `false` if the camera ray did not hit anything.
+    pub fn is_set(&self) -> bool {
+        self.set
+    }
+}
+
+/// The properties that all films share.
+#[derive(Clone)]
+pub struct FilmBase {
+    /// The resolution of the whole image, which may be larger than the part being rendered.
+    pub full_resolution: Point2i,
+    /// The pixels that are rendered.
+    pub pixel_bounds: Bounds2i,
+    pub filter: Filter,
+    /// The length of the diagonal of the film, in meters.
+    pub diagonal: Float,
+    /// The file the image is written to.
+    pub filename: String,
+    pub sensor: PixelSensor,
+}
+
+impl FilmBase {
+    /// Reads the resolution, crop window, diagonal, output filename and sensor from the parameters
+    /// of a `Film` directive, for a camera whose shutter is open for `exposure_time`.
+    pub fn create(
+        entity: &SceneEntity,
+        filter: Filter,
+        exposure_time: Float,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        let error = |message: &str| ParseError::new(&entity.loc, message);
+
+        let full_resolution = Point2i::new(
+            params.get_one_int("xresolution", 1280)?,
+            params.get_one_int("yresolution", 720)?,
+        );
+        if full_resolution.x <= 0 || full_resolution.y <= 0 {
+            return Err(error("film resolution must be positive"));
+        }
+        let full_bounds = Bounds2i::new(Point2i::new(0, 0), full_resolution);
+
+        // The pixel bounds are either given directly or as a fraction of the image
+        let crop_window = params.get_float_array("cropwindow")?;
+        let pixel_bounds = params.get_int_array("pixelbounds")?;
+        let pixel_bounds = match (crop_window.as_slice(), pixel_bounds.as_slice()) {
+            ([], []) => full_bounds,
+            ([_, _, _, _], []) => {
+                let to_pixel =
+                    |v: Float, res: i32| (v.clamp(0.0, 1.0) * res as Float).ceil() as i32;
+                Bounds2i::new(
+                    Point2i::new(
+                        to_pixel(crop_window[0], full_resolution.x),
+                        to_pixel(crop_window[2], full_resolution.y),
+                    ),
+                    Point2i::new(
+                        to_pixel(crop_window[1], full_resolution.x),
+                        to_pixel(crop_window[3], full_resolution.y),
+                    ),
+                )
+            }
+            ([], [x0, x1, y0, y1]) => Bounds2i::new(Point2i::new(*x0, *y0), Point2i::new(*x1, *y1))
+                .intersect(&full_bounds),
+            ([], _) => return Err(error("\"pixelbounds\" must have four values")),
+            (_, []) => return Err(error("\"cropwindow\" must have four values")),
+            _ => {
+                return Err(error(
+                    "\"cropwindow\" and \"pixelbounds\" cannot both be given",
+                ))
+            }
+        };
+        if pixel_bounds.is_empty() {
+            return Err(error("film pixel bounds are empty"));
+        }
+
+        Ok(Self {
+            full_resolution,
+            pixel_bounds,
+            filter,
+            diagonal: params.get_one_float("diagonal", 35.0)? * 0.001,
+            filename: params.get_one_string("filename", "lili.exr")?,
+            sensor: PixelSensor::create(entity, params.color_space(), exposure_time)?,
+        })
+    }
+
+    /// The offset of a pixel in row-major arrays covering the pixel bounds.
+    fn pixel_offset(&self, p: Point2i) -> usize {
+        debug_assert!(self.pixel_bounds.inside_exclusive(p));
+        let width = (self.pixel_bounds.p_max.x - self.pixel_bounds.p_min.x) as usize;
+        (p.y - self.pixel_bounds.p_min.y) as usize * width
+            + (p.x - self.pixel_bounds.p_min.x) as usize
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.pixel_bounds.point_count()
+    }
+
+    /// An image with the given channels covering the pixel bounds.
+    fn image(&self, format: PixelFormat, channel_names: &[&str]) -> Image {
+        let Bounds2i { p_min, p_max } = self.pixel_bounds;
+        let resolution = Point2i::new(p_max.x - p_min.x, p_max.y - p_min.y);
+        Image::new(format, resolution, channel_names, ColorEncoding::Linear)
+    }
+
+    /// The pixel of images from [`image`](Self::image) that corresponds to the film pixel `p`.
+    fn image_pixel(&self, p: Point2i) -> Point2i {
+        Point2i::new(
+            p.x - self.pixel_bounds.p_min.x,
+            p.y - self.pixel_bounds.p_min.y,
+        )
+    }
+}
+
+/// The film that records the image, chosen with the `Film` directive.
+///
+/// # Examples
+///
+/// ```
+/// use lili::{
+///     film::Film,
+///     filter::Filter,
+///     math::{points::Point2i, transform::Transform},
+///     scene::BasicSceneBuilder,
+///     spectrum::{SampledSpectrum, SampledWavelengths},
+/// };
+///
+/// let scene = BasicSceneBuilder::parse_string(
+///     r#"Film "rgb" "integer xresolution" 4 "integer yresolution" 2 WorldBegin"#,
+/// )
+/// .unwrap();
+/// let film = Film::create(&scene.film, Filter::default(), 1.0, Transform::default()).unwrap();
+/// assert_eq!(film.pixel_bounds().area(), 8);
+///
+/// // Pixels hold the weighted average of their samples
+/// let lambda = film.sample_wavelengths(0.5);
+/// film.add_sample(Point2i::new(0, 0), &SampledSpectrum::new(1.0), &lambda, None, 1.0);
+/// film.add_sample(Point2i::new(0, 0), &SampledSpectrum::new(4.0), &lambda, None, 2.0);
+/// film.add_sample(Point2i::new(1, 0), &SampledSpectrum::new(3.0), &lambda, None, 1.0);
+///
+/// let (a, b) = (film.get_pixel_rgb(Point2i::new(0, 0)), film.get_pixel_rgb(Point2i::new(1, 0)));
+/// assert!((a.g - b.g).abs() < 1e-4 * b.g);
+/// ```
+pub enum Film {
+    Rgb(RgbFilm),
+    GBuffer(GBufferFilm),
+    Spectral(SpectralFilm),
+}
+
+impl Film {
+    /// Creates the film described by a `Film` directive.
+    ///
+    /// The geometry that a [`GBufferFilm`] stores is transformed by `output_from_render`, which
+    /// should take it to camera space. The exposure of the image is proportional to
+    /// `exposure_time`, the time that the shutter of the camera is open.
+    pub fn create(
+        entity: &SceneEntity,
+        filter: Filter,
+        exposure_time: Float,
+        output_from_render: Transform,
+    ) -> Result<Self, ParseError> {
+        let film = match entity.name.as_str() {
+            "rgb" => Self::Rgb(RgbFilm::create(entity, filter, exposure_time)?),
+            "gbuffer" => Self::GBuffer(GBufferFilm::create(
+                entity,
+                filter,
+                exposure_time,
+                output_from_render,
+            )?),
+            "spectral" => Self::Spectral(SpectralFilm::create(entity, filter, exposure_time)?),
+            name => {
+                return Err(ParseError::new(
+                    &entity.loc,
+                    format!("\"{name}\": unknown film type"),
+                ))
+            }
+        };
+        entity.parameters.report_unused()?;
+        Ok(film)
+    }
+
+    pub fn base(&self) -> &FilmBase {
+        match self {
+            Self::Rgb(film) => &film.base,
+            Self::GBuffer(film) => &film.base,
+            Self::Spectral(film) => &film.base,
+        }
+    }
+
+    pub fn full_resolution(&self) -> Point2i {
+        self.base().full_resolution
+    }
+
+    pub fn pixel_bounds(&self) -> Bounds2i {
+        self.base().pixel_bounds
+    }
+
+    pub fn diagonal(&self) -> Float {
+        self.base().diagonal
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.base().filter
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.base().filename
+    }
+
+    /// Samples the wavelengths that a camera ray carries.
+    pub fn sample_wavelengths(&self, u: Float) -> SampledWavelengths {
+        match self {
+            Self::Rgb(_) | Self::GBuffer(_) => SampledWavelengths::sample_visible(u),
+            Self::Spectral(film) => film.sample_wavelengths(u),
+        }
+    }
+
+    /// Returns `true` if the film needs the [`VisibleSurface`] of each camera ray.
+    pub fn uses_visible_surface(&self) -> bool {
+        matches!(self, Self::GBuffer(_))
+    }
+
+    /// Adds the radiance `l` arriving at pixel `p_film` to the film, weighted by the filter.
+    ///
+    /// This may be called from multiple threads at once.
+    pub fn add_sample(
+        &self,
+        p_film: Point2i,
+        l: &SampledSpectrum,
+        lambda: &SampledWavelengths,
+        visible_surface: Option<&VisibleSurface>,
+        weight: Float,
+    ) {
+        match self {
+            Self::Rgb(film) => film.add_sample(p_film, l, lambda, weight),
+            Self::GBuffer(film) => film.add_sample(p_film, l, lambda, visible_surface, weight),
+            Self::Spectral(film) => film.add_sample(p_film, l, lambda, weight),
+        }
+    }
+
+    /// The final color of a pixel in the output color space.
+    pub fn get_pixel_rgb(&self, p: Point2i) -> Rgb {
+        match self {
+            Self::Rgb(film) => film.get_pixel_rgb(p),
+            Self::GBuffer(film) => film.get_pixel_rgb(p),
+            Self::Spectral(film) => film.get_pixel_rgb(p),
+        }
+    }
+
+    /// The image of the pixel bounds, with the color of each pixel in the `R`, `G` and `B`
+    /// channels followed by any other channels that the film records.
+    pub fn get_image(&self) -> Image {
+        match self {
+            Self::Rgb(film) => film.get_image(),
+            Self::GBuffer(film) => film.get_image(),
+            Self::Spectral(film) => film.get_image(),
+        }
+    }
+
+    /// Writes the sums that the pixels have accumulated, so that
+    /// [`read_state`](Self::read_state) can restore them exactly.
+    pub fn write_state(&self, w: &mut impl Write) -> io::Result<()> {
+        let sums: Vec<&AtomicDouble> = self.sums().collect();
+        w.write_all(&(sums.len() as u64).to_le_bytes())?;
+        for sum in sums {
+            w.write_all(&sum.bits().to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Restores the sums written by [`write_state`](Self::write_state) for a film of the same
+    /// type and size.
+    ///
+    /// The film is left unchanged if the state cannot be read.
+    pub fn read_state(&self, r: &mut impl Read) -> io::Result<()> {
+        let sums: Vec<&AtomicDouble> = self.sums().collect();
+        let mut len = [0; 8];
+        r.read_exact(&mut len)?;
+        if u64::from_le_bytes(len) != sums.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the saved film state does not match the film",
+            ));
+        }
+
+        let mut bytes = vec![0; 8 * sums.len()];
+        r.read_exact(&mut bytes)?;
+        for (sum, b) in sums.into_iter().zip(bytes.chunks_exact(8)) {
+            sum.set_bits(u64::from_le_bytes(b.try_into().unwrap()));
+        }
+        Ok(())
+    }
+
+    /// All of the sums that the pixels accumulate.
+    fn sums(&self) -> Box<dyn Iterator<Item = &AtomicDouble> + '_> {
+        match self {
+            Self::Rgb(film) => Box::new(film.sums()),
+            Self::GBuffer(film) => Box::new(film.sums()),
+            Self::Spectral(film) => Box::new(film.sums()),
+        }
+    }
+
+    /// Writes the image to `filename`, in the format given by its extension.
+    ///
+    /// Only OpenEXR files store the channels beyond the color of the pixels, and where the pixel
+    /// bounds lie within the full resolution of the film.
+    pub fn write_image(&self, filename: impl AsRef<Path>) -> Result<(), ImageError> {
+        let metadata = ImageMetadata {
+            pixel_bounds: Some(self.pixel_bounds()),
+            full_resolution: Some(self.full_resolution()),
+        };
+        self.get_image().write(filename, &metadata)
+    }
+}
+
+/// Scales `rgb` down so that none of its components exceed `max_component_value`.
+fn clamp_rgb(rgb: Rgb, max_component_value: Float) -> Rgb {
+    let m = rgb.max_component_value();
+    if m > max_component_value {
+        rgb * (max_component_value / m)
+    } else {
+        rgb
+    }
+}
+
+/// A floating-point value that can be added to from multiple threads.
+#[derive(Debug, Default)]
+struct AtomicDouble(AtomicU64);
+
+impl AtomicDouble {
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn bits(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set_bits(&self, bits: u64) {
+        self.0.store(bits, Ordering::Relaxed)
+    }
+
+    fn add(&self, v: f64) {
+        // The closure always returns `Some`, so the update cannot fail
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + v).to_bits())
+            });
+    }
+}
+
+/// The sums of the weighted colors of the samples in a pixel, and of their weights.
+#[derive(Debug, Default)]
+struct RgbPixel {
+    rgb_sum: [AtomicDouble; 3],
+    weight_sum: AtomicDouble,
+}
+
+impl RgbPixel {
+    fn sums(&self) -> impl Iterator<Item = &AtomicDouble> {
+        self.rgb_sum.iter().chain([&self.weight_sum])
+    }
+
+    fn add(&self, rgb: Rgb, weight: Float) {
+        for c in 0..3 {
+            self.rgb_sum[c].add((weight * rgb[c]) as f64);
+        }
+        self.weight_sum.add(weight as f64);
+    }
+
+    /// The weighted average of the colors of the samples.
+    fn rgb(&self) -> Rgb {
+        let weight_sum = self.weight_sum.get();
+        if weight_sum == 0.0 {
+            return Rgb::default();
+        }
+        let [r, g, b] = self
+            .rgb_sum
+            .each_ref()
+            .map(|c| (c.get() / weight_sum) as Float);
+        Rgb::new(r, g, b)
+    }
+}
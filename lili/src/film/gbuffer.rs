@@ -0,0 +1,219 @@
+//! A film that records the geometry of the visible surfaces along with the color of each pixel.
+use crate::{
+    color::{Rgb, RgbColorSpace},
+    film::{clamp_rgb, AtomicDouble, FilmBase, RgbPixel, VisibleSurface},
+    filter::Filter,
+    image::{Image, PixelFormat},
+    math::{
+        matrix::SquareMatrix,
+        normalize::Normalize,
+        normals::Normal3f,
+        points::{Point2f, Point2i, Point3f},
+        transform::Transform,
+        Float,
+    },
+    scene::{parser::ParseError, SceneEntity},
+    spectrum::{SampledSpectrum, SampledWavelengths},
+};
+
+/// A film that stores the position, normals, texture coordinates and albedo of the first surface
+/// visible in each pixel, as needed for denoising.
+///
+/// The geometry is given in the output space of the film, which is usually camera space, so that
+/// the depth of a pixel is the `z` coordinate of its position.
+pub struct GBufferFilm {
+    pub base: FilmBase,
+    color_space: &'static RgbColorSpace,
+    max_component_value: Float,
+    output_rgb_from_sensor_rgb: SquareMatrix<3>,
+    output_from_render: Transform,
+    pixels: Vec<GBufferPixel>,
+}
+
+/// The averages of the geometry of the surfaces visible in a pixel.
+#[derive(Default, Clone, Copy)]
+pub struct GBufferSample {
+    pub p: Point3f,
+    /// The distance from the camera along the viewing direction.
+    pub depth: Float,
+    pub n: Normal3f,
+    /// The shading normal.
+    pub ns: Normal3f,
+    pub uv: Point2f,
+    /// The reflectance of the surfaces, in the output color space.
+    pub albedo: Rgb,
+}
+
+impl GBufferFilm {
+    /// Creates a film whose pixels are in `color_space` and whose geometry is transformed by
+    /// `output_from_render`.
+    pub fn new(
+        base: FilmBase,
+        color_space: &'static RgbColorSpace,
+        max_component_value: Float,
+        output_from_render: Transform,
+    ) -> Self {
+        let pixels = (0..base.pixel_count())
+            .map(|_| GBufferPixel::default())
+            .collect();
+        // The sensor's colors are converted to XYZ, and from there to the output color space
+        let output_rgb_from_sensor_rgb = color_space.rgb_from_xyz * base.sensor.xyz_from_sensor_rgb;
+        Self {
+            base,
+            color_space,
+            max_component_value,
+            output_rgb_from_sensor_rgb,
+            output_from_render,
+            pixels,
+        }
+    }
+
+    /// Creates the film described by a `Film "gbuffer"` directive.
+    pub fn create(
+        entity: &SceneEntity,
+        filter: Filter,
+        exposure_time: Float,
+        output_from_render: Transform,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        Ok(Self::new(
+            FilmBase::create(entity, filter, exposure_time)?,
+            params.color_space(),
+            params.get_one_float("maxcomponentvalue", Float::INFINITY)?,
+            output_from_render,
+        ))
+    }
+
+    /// The color space of the output image.
+    pub fn color_space(&self) -> &'static RgbColorSpace {
+        self.color_space
+    }
+
+    pub fn add_sample(
+        &self,
+        p_film: Point2i,
+        l: &SampledSpectrum,
+        lambda: &SampledWavelengths,
+        visible_surface: Option<&VisibleSurface>,
+        weight: Float,
+    ) {
+        let pixel = &self.pixels[self.base.pixel_offset(p_film)];
+        let rgb = clamp_rgb(
+            self.base.sensor.to_sensor_rgb(l, lambda),
+            self.max_component_value,
+        );
+        pixel.rgb.add(rgb, weight);
+
+        let Some(vs) = visible_surface.filter(|vs| vs.is_set()) else {
+            return;
+        };
+        let p = self.output_from_render.apply(&vs.p);
+        let n = self.output_from_render.apply(&vs.n);
+        let ns = self.output_from_render.apply(&vs.ns);
+        let albedo = vs.albedo.to_rgb(lambda, self.color_space);
+
+        let weight = weight as f64;
+        let add = |sums: &[AtomicDouble], values: &[Float]| {
+            for (sum, v) in sums.iter().zip(values) {
+                sum.add(weight * *v as f64);
+            }
+        };
+        pixel.g_buffer_weight_sum.add(weight);
+        add(&pixel.p_sum, &[p.x, p.y, p.z]);
+        add(&pixel.n_sum, &[n.x, n.y, n.z]);
+        add(&pixel.ns_sum, &[ns.x, ns.y, ns.z]);
+        add(&pixel.uv_sum, &[vs.uv.x, vs.uv.y]);
+        add(&pixel.albedo_sum, &[albedo.r, albedo.g, albedo.b]);
+    }
+
+    pub fn get_pixel_rgb(&self, p: Point2i) -> Rgb {
+        let rgb = self.pixels[self.base.pixel_offset(p)].rgb.rgb();
+        let [r, g, b] = self
+            .output_rgb_from_sensor_rgb
+            .mul_vector(&[rgb.r, rgb.g, rgb.b]);
+        Rgb::new(r, g, b)
+    }
+
+    /// The average geometry of the surfaces visible in a pixel, or `None` if no camera ray in it
+    /// hit a surface.
+    pub fn get_pixel_surface(&self, p: Point2i) -> Option<GBufferSample> {
+        let pixel = &self.pixels[self.base.pixel_offset(p)];
+        let weight_sum = pixel.g_buffer_weight_sum.get();
+        if weight_sum == 0.0 {
+            return None;
+        }
+        let average = |sums: &[AtomicDouble]| -> Vec<Float> {
+            sums.iter()
+                .map(|sum| (sum.get() / weight_sum) as Float)
+                .collect()
+        };
+
+        let p = average(&pixel.p_sum);
+        let n = average(&pixel.n_sum);
+        let ns = average(&pixel.ns_sum);
+        let uv = average(&pixel.uv_sum);
+        let albedo = average(&pixel.albedo_sum);
+        Some(GBufferSample {
+            p: Point3f::new(p[0], p[1], p[2]),
+            depth: p[2],
+            n: Normal3f::new(n[0], n[1], n[2]).normalize(),
+            ns: Normal3f::new(ns[0], ns[1], ns[2]).normalize(),
+            uv: Point2f::new(uv[0], uv[1]),
+            albedo: Rgb::new(albedo[0], albedo[1], albedo[2]),
+        })
+    }
+
+    pub(super) fn sums(&self) -> impl Iterator<Item = &AtomicDouble> {
+        self.pixels.iter().flat_map(|pixel| {
+            pixel
+                .rgb
+                .sums()
+                .chain([&pixel.g_buffer_weight_sum])
+                .chain(&pixel.p_sum)
+                .chain(&pixel.n_sum)
+                .chain(&pixel.ns_sum)
+                .chain(&pixel.uv_sum)
+                .chain(&pixel.albedo_sum)
+        })
+    }
+
+    /// The image of the film, with the color of each pixel followed by the geometry in the
+    /// `Albedo`, `P`, `N` and `Ns` layers and the `u` and `v` channels.
+    ///
+    /// The geometry of pixels that no camera ray hit a surface in is 0.
+    pub fn get_image(&self) -> Image {
+        let mut image = self.base.image(
+            PixelFormat::Float,
+            &[
+                "R", "G", "B", "Albedo.R", "Albedo.G", "Albedo.B", "P.X", "P.Y", "P.Z", "N.X",
+                "N.Y", "N.Z", "Ns.X", "Ns.Y", "Ns.Z", "u", "v",
+            ],
+        );
+        let all = image.all_channels_desc();
+        for p in self.base.pixel_bounds {
+            let rgb = self.get_pixel_rgb(p);
+            let s = self.get_pixel_surface(p).unwrap_or_default();
+            image.set_channels(
+                self.base.image_pixel(p),
+                &all,
+                &[
+                    rgb.r, rgb.g, rgb.b, s.albedo.r, s.albedo.g, s.albedo.b, s.p.x, s.p.y, s.p.z,
+                    s.n.x, s.n.y, s.n.z, s.ns.x, s.ns.y, s.ns.z, s.uv.x, s.uv.y,
+                ],
+            );
+        }
+        image
+    }
+}
+
+#[derive(Debug, Default)]
+struct GBufferPixel {
+    rgb: RgbPixel,
+    /// The sum of the weights of the samples that hit a surface.
+    g_buffer_weight_sum: AtomicDouble,
+    p_sum: [AtomicDouble; 3],
+    n_sum: [AtomicDouble; 3],
+    ns_sum: [AtomicDouble; 3],
+    uv_sum: [AtomicDouble; 2],
+    albedo_sum: [AtomicDouble; 3],
+}
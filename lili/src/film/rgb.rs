@@ -0,0 +1,99 @@
+//! A film that records the color of each pixel.
+use crate::{
+    color::{Rgb, RgbColorSpace},
+    film::{clamp_rgb, AtomicDouble, FilmBase, RgbPixel},
+    filter::Filter,
+    image::{Image, PixelFormat},
+    math::{matrix::SquareMatrix, points::Point2i, Float},
+    scene::{parser::ParseError, SceneEntity},
+    spectrum::{SampledSpectrum, SampledWavelengths},
+};
+
+/// A film that stores the average color of the samples in each pixel, in an RGB color space.
+pub struct RgbFilm {
+    pub base: FilmBase,
+    color_space: &'static RgbColorSpace,
+    /// Samples whose largest component exceeds this are scaled down to it, which trades bias
+    /// for less noise from rare bright samples.
+    max_component_value: Float,
+    output_rgb_from_sensor_rgb: SquareMatrix<3>,
+    pixels: Vec<RgbPixel>,
+}
+
+impl RgbFilm {
+    /// Creates a film whose pixels are in `color_space`.
+    pub fn new(
+        base: FilmBase,
+        color_space: &'static RgbColorSpace,
+        max_component_value: Float,
+    ) -> Self {
+        let pixels = (0..base.pixel_count())
+            .map(|_| RgbPixel::default())
+            .collect();
+        // The sensor's colors are converted to XYZ, and from there to the output color space
+        let output_rgb_from_sensor_rgb = color_space.rgb_from_xyz * base.sensor.xyz_from_sensor_rgb;
+        Self {
+            base,
+            color_space,
+            max_component_value,
+            output_rgb_from_sensor_rgb,
+            pixels,
+        }
+    }
+
+    /// Creates the film described by a `Film "rgb"` directive.
+    pub fn create(
+        entity: &SceneEntity,
+        filter: Filter,
+        exposure_time: Float,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        Ok(Self::new(
+            FilmBase::create(entity, filter, exposure_time)?,
+            params.color_space(),
+            params.get_one_float("maxcomponentvalue", Float::INFINITY)?,
+        ))
+    }
+
+    /// The color space of the output image.
+    pub fn color_space(&self) -> &'static RgbColorSpace {
+        self.color_space
+    }
+
+    pub fn add_sample(
+        &self,
+        p_film: Point2i,
+        l: &SampledSpectrum,
+        lambda: &SampledWavelengths,
+        weight: Float,
+    ) {
+        let rgb = clamp_rgb(
+            self.base.sensor.to_sensor_rgb(l, lambda),
+            self.max_component_value,
+        );
+        self.pixels[self.base.pixel_offset(p_film)].add(rgb, weight);
+    }
+
+    pub fn get_pixel_rgb(&self, p: Point2i) -> Rgb {
+        let rgb = self.pixels[self.base.pixel_offset(p)].rgb();
+        let [r, g, b] = self
+            .output_rgb_from_sensor_rgb
+            .mul_vector(&[rgb.r, rgb.g, rgb.b]);
+        Rgb::new(r, g, b)
+    }
+
+    pub(super) fn sums(&self) -> impl Iterator<Item = &AtomicDouble> {
+        self.pixels.iter().flat_map(RgbPixel::sums)
+    }
+
+    pub fn get_image(&self) -> Image {
+        let mut image = self.base.image(PixelFormat::Half, &["R", "G", "B"]);
+        for p in self.base.pixel_bounds {
+            let rgb = self.get_pixel_rgb(p);
+            for c in 0..3 {
+                image.set_channel(self.base.image_pixel(p), c, rgb[c]);
+            }
+        }
+        image
+    }
+}
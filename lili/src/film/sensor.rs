@@ -0,0 +1,321 @@
+//! The response of a camera sensor to the radiance that reaches it.
+use crate::{
+    color::{ColorEncoding, Rgb, RgbColorSpace, Xyz},
+    math::{matrix::SquareMatrix, points::Point2f, Float},
+    scene::{parser::ParseError, SceneEntity},
+    spectrum::{
+        inner_product, named, spectrum_to_xyz, DenselySampledSpectrum, RgbAlbedoSpectrum,
+        SampledSpectrum, SampledWavelengths, Spectrum, SpectrumType, LAMBDA_MAX, LAMBDA_MIN,
+    },
+};
+
+/// A camera sensor, given by the spectral response of its red, green and blue pixels.
+///
+/// The sensor also applies the exposure of the camera, as the product of its exposure time and
+/// ISO sensitivity, and optionally white balances the image.
+///
+/// # Examples
+///
+/// ```
+/// use lili::{
+///     color::{Rgb, RgbColorSpace},
+///     film::PixelSensor,
+///     math::Float,
+///     spectrum::{named, SampledWavelengths, Spectrum},
+/// };
+///
+/// // White balancing for a warm light makes it appear white in the output color space
+/// let srgb = RgbColorSpace::srgb();
+/// let illuminant = named::std_illuminant_d(4000.0);
+/// let sensor = PixelSensor::xyz(srgb, Some(&illuminant), 1.0);
+///
+/// let n = 64;
+/// let mut rgb = Rgb::new(0.0, 0.0, 0.0);
+/// for i in 0..n {
+///     let lambda = SampledWavelengths::sample_visible((i as Float + 0.5) / n as Float);
+///     rgb = rgb + sensor.to_sensor_rgb(&illuminant.sample(&lambda), &lambda);
+/// }
+/// let output_rgb_from_sensor_rgb = srgb.rgb_from_xyz * sensor.xyz_from_sensor_rgb;
+/// let [r, g, b] = output_rgb_from_sensor_rgb.mul_vector(&[rgb.r, rgb.g, rgb.b]);
+/// assert!((r / g - 1.0).abs() < 0.01 && (b / g - 1.0).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PixelSensor {
+    /// Converts the colors that the sensor records to XYZ.
+    pub xyz_from_sensor_rgb: SquareMatrix<3>,
+    r_bar: DenselySampledSpectrum,
+    g_bar: DenselySampledSpectrum,
+    b_bar: DenselySampledSpectrum,
+    /// The factor that the recorded radiance is scaled by.
+    imaging_ratio: Float,
+}
+
+impl PixelSensor {
+    /// Creates a sensor whose responses are the CIE XYZ matching functions.
+    ///
+    /// If `sensor_illum` is given, colors are white balanced so that it appears as the white of
+    /// `output_color_space`.
+    pub fn xyz(
+        output_color_space: &RgbColorSpace,
+        sensor_illum: Option<&dyn Spectrum>,
+        imaging_ratio: Float,
+    ) -> Self {
+        let xyz_from_sensor_rgb = match sensor_illum {
+            Some(illum) => white_balance(spectrum_to_xyz(illum).xy(), output_color_space.w),
+            None => SquareMatrix::identity(),
+        };
+        Self {
+            xyz_from_sensor_rgb,
+            r_bar: named::x().clone(),
+            g_bar: named::y().clone(),
+            b_bar: named::z().clone(),
+            imaging_ratio,
+        }
+    }
+
+    /// Creates a sensor with the spectral responses `r`, `g` and `b`, as lit by `sensor_illum`.
+    ///
+    /// The conversion of its colors to those of `output_color_space` is the one that best
+    /// reproduces the colors of a color checker chart, white balanced from `sensor_illum` to the
+    /// white point of the color space.
+    pub fn new(
+        r: &dyn Spectrum,
+        g: &dyn Spectrum,
+        b: &dyn Spectrum,
+        output_color_space: &RgbColorSpace,
+        sensor_illum: &dyn Spectrum,
+        imaging_ratio: Float,
+    ) -> Self {
+        let (r_bar, g_bar, b_bar) = (
+            DenselySampledSpectrum::from_spectrum(r),
+            DenselySampledSpectrum::from_spectrum(g),
+            DenselySampledSpectrum::from_spectrum(b),
+        );
+
+        // The colors of the swatches as the sensor records them, and as they should appear in
+        // the output
+        let swatches = swatch_reflectances();
+        let sensor_white_g = inner_product(sensor_illum, &g_bar);
+        let sensor_white_y = inner_product(sensor_illum, named::y());
+        let rgb_camera: Vec<[Float; 3]> = swatches
+            .iter()
+            .map(|s| project_reflectance(s, sensor_illum, [&r_bar, &g_bar, &b_bar]))
+            .collect();
+        let xyz_output: Vec<[Float; 3]> = swatches
+            .iter()
+            .map(|s| {
+                project_reflectance(
+                    s,
+                    output_color_space.illuminant.as_ref(),
+                    [named::x(), named::y(), named::z()],
+                )
+                .map(|c| c * sensor_white_y / sensor_white_g)
+            })
+            .collect();
+
+        Self {
+            xyz_from_sensor_rgb: linear_least_squares(&rgb_camera, &xyz_output)
+                .unwrap_or_else(SquareMatrix::identity),
+            r_bar,
+            g_bar,
+            b_bar,
+            imaging_ratio,
+        }
+    }
+
+    /// Creates the sensor described by the parameters of a `Film` directive, for a camera whose
+    /// shutter is open for `exposure_time`.
+    ///
+    /// The sensor is either the built-in `"cie1931"` one, or the one whose spectral responses are
+    /// given by the `"r"`, `"g"` and `"b"` spectrum parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::{color::RgbColorSpace, film::PixelSensor, scene::BasicSceneBuilder};
+    ///
+    /// // A sensor whose pixels each respond to a third of the visible spectrum
+    /// let scene = BasicSceneBuilder::parse_string(
+    ///     r#"Film "rgb" "spectrum r" [600 1 700 1] "spectrum g" [500 1 600 1]
+    ///                   "spectrum b" [400 1 500 1] "float whitebalance" 5000"#,
+    /// )
+    /// .unwrap();
+    /// assert!(PixelSensor::create(&scene.film, RgbColorSpace::srgb(), 1.0).is_ok());
+    ///
+    /// // All three responses are needed
+    /// let scene = BasicSceneBuilder::parse_string(r#"Film "rgb" "spectrum r" [600 1 700 1]"#)
+    ///     .unwrap();
+    /// assert!(PixelSensor::create(&scene.film, RgbColorSpace::srgb(), 1.0).is_err());
+    /// ```
+    pub fn create(
+        entity: &SceneEntity,
+        output_color_space: &RgbColorSpace,
+        exposure_time: Float,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        let iso = params.get_one_float("iso", 100.0)?;
+        let sensor_name = params.get_one_string("sensor", "cie1931")?;
+        let imaging_ratio = exposure_time * iso / 100.0;
+
+        let white_balance_temp = params.get_one_float("whitebalance", 0.0)?;
+
+        let r = params.get_one_spectrum("r", SpectrumType::Unbounded)?;
+        let g = params.get_one_spectrum("g", SpectrumType::Unbounded)?;
+        let b = params.get_one_spectrum("b", SpectrumType::Unbounded)?;
+        match (r, g, b) {
+            (Some(r), Some(g), Some(b)) => {
+                // Sensors other than the XYZ one are always white balanced, for daylight unless
+                // told otherwise
+                let white_balance_temp = if white_balance_temp == 0.0 {
+                    6500.0
+                } else {
+                    white_balance_temp
+                };
+                let sensor_illum = named::std_illuminant_d(white_balance_temp);
+                Ok(Self::new(
+                    r.as_ref(),
+                    g.as_ref(),
+                    b.as_ref(),
+                    output_color_space,
+                    &sensor_illum,
+                    imaging_ratio,
+                ))
+            }
+            (None, None, None) => {
+                // The spectral responses of real cameras are not built in, so only the XYZ
+                // sensor can be named
+                if sensor_name != "cie1931" {
+                    return Err(ParseError::new(
+                        &entity.loc,
+                        format!(
+                            "\"{sensor_name}\": unsupported sensor, the only built-in sensor is \
+                             \"cie1931\", and others need their \"r\", \"g\" and \"b\" responses"
+                        ),
+                    ));
+                }
+
+                let sensor_illum = (white_balance_temp != 0.0)
+                    .then(|| named::std_illuminant_d(white_balance_temp));
+                Ok(Self::xyz(
+                    output_color_space,
+                    sensor_illum.as_ref().map(|s| s as &dyn Spectrum),
+                    imaging_ratio,
+                ))
+            }
+            _ => Err(ParseError::new(
+                &entity.loc,
+                "the \"r\", \"g\" and \"b\" responses of a sensor must be given together",
+            )),
+        }
+    }
+
+    /// The color that the sensor records for the radiance `l`.
+    pub fn to_sensor_rgb(&self, l: &SampledSpectrum, lambda: &SampledWavelengths) -> Rgb {
+        let l = l.safe_div(&lambda.pdf());
+        let response = |bar: &DenselySampledSpectrum| (bar.sample(lambda) * l).average();
+        Rgb::new(
+            response(&self.r_bar),
+            response(&self.g_bar),
+            response(&self.b_bar),
+        ) * (self.imaging_ratio / named::CIE_Y_INTEGRAL)
+    }
+}
+
+/// Bradford's transformation from XYZ to the responses of the cones of the eye.
+const LMS_FROM_XYZ: [[Float; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+/// The von Kries transformation that maps the white with chromaticity `src_white` to
+/// `target_white`, by scaling the cone responses independently.
+pub fn white_balance(src_white: Point2f, target_white: Point2f) -> SquareMatrix<3> {
+    let lms_from_xyz = SquareMatrix::<3>::new(LMS_FROM_XYZ);
+    let xyz_from_lms = lms_from_xyz
+        .inverse()
+        .expect("the Bradford matrix is invertible");
+
+    let to_lms = |xy: Point2f| {
+        let xyz = Xyz::from_xyy(xy, 1.0);
+        lms_from_xyz.mul_vector(&[xyz.x, xyz.y, xyz.z])
+    };
+    let (src_lms, dst_lms) = (to_lms(src_white), to_lms(target_white));
+    let lms_correct = SquareMatrix::diag([0, 1, 2].map(|i| dst_lms[i] / src_lms[i]));
+    xyz_from_lms * lms_correct * lms_from_xyz
+}
+
+/// The sum of the product of three spectra over the visible wavelengths, at 1nm intervals.
+fn inner_product3(a: &dyn Spectrum, b: &dyn Spectrum, c: &dyn Spectrum) -> Float {
+    (LAMBDA_MIN as i32..=LAMBDA_MAX as i32)
+        .map(|lambda| {
+            let lambda = lambda as Float;
+            a.evaluate(lambda) * b.evaluate(lambda) * c.evaluate(lambda)
+        })
+        .sum()
+}
+
+/// The color of the reflectance `r` lit by `illum` as seen through the matching functions `bars`,
+/// relative to the second component of the color of the illuminant itself.
+fn project_reflectance(
+    r: &dyn Spectrum,
+    illum: &dyn Spectrum,
+    bars: [&DenselySampledSpectrum; 3],
+) -> [Float; 3] {
+    let g_integral = inner_product(illum, bars[1]);
+    bars.map(|bar| inner_product3(r, illum, bar) / g_integral)
+}
+
+/// The matrix `m` that minimizes the squared error of `m a[i] = b[i]`.
+fn linear_least_squares(a: &[[Float; 3]], b: &[[Float; 3]]) -> Option<SquareMatrix<3>> {
+    let mut at_a = SquareMatrix::<3>::zero();
+    let mut at_b = SquareMatrix::zero();
+    for (a, b) in a.iter().zip(b) {
+        for i in 0..3 {
+            for j in 0..3 {
+                at_a[i][j] += a[i] * a[j];
+                at_b[i][j] += a[i] * b[j];
+            }
+        }
+    }
+    Some((at_a.inverse()? * at_b).transpose())
+}
+
+/// The reflectances of the 24 patches of the X-Rite ColorChecker chart, reconstructed from their
+/// 8-bit sRGB colors.
+fn swatch_reflectances() -> Vec<RgbAlbedoSpectrum> {
+    const SWATCHES: [[u8; 3]; 24] = [
+        [115, 82, 68],
+        [194, 150, 130],
+        [98, 122, 157],
+        [87, 108, 67],
+        [133, 128, 177],
+        [103, 189, 170],
+        [214, 126, 44],
+        [80, 91, 166],
+        [193, 90, 99],
+        [94, 60, 108],
+        [157, 188, 64],
+        [224, 163, 46],
+        [56, 61, 150],
+        [70, 148, 73],
+        [175, 54, 60],
+        [231, 199, 31],
+        [187, 86, 149],
+        [8, 133, 161],
+        [243, 243, 242],
+        [200, 200, 200],
+        [160, 160, 160],
+        [122, 122, 121],
+        [85, 85, 85],
+        [52, 52, 52],
+    ];
+
+    SWATCHES
+        .iter()
+        .map(|rgb| {
+            let [r, g, b] = rgb.map(|v| ColorEncoding::Srgb.to_linear(v));
+            RgbAlbedoSpectrum::new(RgbColorSpace::srgb(), Rgb::new(r, g, b))
+        })
+        .collect()
+}
@@ -0,0 +1,209 @@
+//! A film that records the spectral distribution of the radiance in each pixel.
+use crate::{
+    color::{Rgb, RgbColorSpace},
+    film::{clamp_rgb, AtomicDouble, FilmBase, RgbPixel},
+    filter::Filter,
+    image::{Image, PixelFormat},
+    math::{matrix::SquareMatrix, points::Point2i, Float},
+    scene::{parser::ParseError, SceneEntity},
+    spectrum::{SampledSpectrum, SampledWavelengths, LAMBDA_MAX, LAMBDA_MIN, N_SPECTRUM_SAMPLES},
+};
+
+/// A film that stores the average radiance in each of a number of equally wide wavelength
+/// ranges, for measurements that need more than a color.
+///
+/// The color of each pixel is stored as well, so that the image can be viewed.
+pub struct SpectralFilm {
+    pub base: FilmBase,
+    color_space: &'static RgbColorSpace,
+    max_component_value: Float,
+    output_rgb_from_sensor_rgb: SquareMatrix<3>,
+    lambda_min: Float,
+    lambda_max: Float,
+    n_buckets: usize,
+    pixels: Vec<SpectralPixel>,
+}
+
+impl SpectralFilm {
+    /// Creates a film that divides the wavelengths between `lambda_min` and `lambda_max` into
+    /// `n_buckets` ranges.
+    pub fn new(
+        base: FilmBase,
+        color_space: &'static RgbColorSpace,
+        max_component_value: Float,
+        lambda_min: Float,
+        lambda_max: Float,
+        n_buckets: usize,
+    ) -> Self {
+        let pixels = (0..base.pixel_count())
+            .map(|_| SpectralPixel {
+                rgb: RgbPixel::default(),
+                bucket_sums: (0..n_buckets).map(|_| AtomicDouble::default()).collect(),
+                weight_sums: (0..n_buckets).map(|_| AtomicDouble::default()).collect(),
+            })
+            .collect();
+        // The sensor's colors are converted to XYZ, and from there to the output color space
+        let output_rgb_from_sensor_rgb = color_space.rgb_from_xyz * base.sensor.xyz_from_sensor_rgb;
+        Self {
+            base,
+            color_space,
+            max_component_value,
+            output_rgb_from_sensor_rgb,
+            lambda_min,
+            lambda_max,
+            n_buckets,
+            pixels,
+        }
+    }
+
+    /// Creates the film described by a `Film "spectral"` directive.
+    pub fn create(
+        entity: &SceneEntity,
+        filter: Filter,
+        exposure_time: Float,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        let error = |message: &str| ParseError::new(&entity.loc, message);
+
+        let n_buckets = params.get_one_int("nbuckets", 16)?;
+        if n_buckets <= 0 {
+            return Err(error("\"nbuckets\" must be positive"));
+        }
+        let lambda_min = params.get_one_float("lambdamin", LAMBDA_MIN)?;
+        let lambda_max = params.get_one_float("lambdamax", LAMBDA_MAX)?;
+        if lambda_min < LAMBDA_MIN || lambda_max > LAMBDA_MAX || lambda_min >= lambda_max {
+            return Err(error(&format!(
+                "the wavelength range must be increasing and between {LAMBDA_MIN} and \
+                 {LAMBDA_MAX}nm"
+            )));
+        }
+
+        Ok(Self::new(
+            FilmBase::create(entity, filter, exposure_time)?,
+            params.color_space(),
+            params.get_one_float("maxcomponentvalue", Float::INFINITY)?,
+            lambda_min,
+            lambda_max,
+            n_buckets as usize,
+        ))
+    }
+
+    /// The color space of the colors of the pixels.
+    pub fn color_space(&self) -> &'static RgbColorSpace {
+        self.color_space
+    }
+
+    /// The number of wavelength ranges.
+    pub fn n_buckets(&self) -> usize {
+        self.n_buckets
+    }
+
+    /// The wavelengths in the middle of each wavelength range.
+    pub fn bucket_wavelengths(&self) -> impl Iterator<Item = Float> + '_ {
+        let width = (self.lambda_max - self.lambda_min) / self.n_buckets as Float;
+        (0..self.n_buckets).map(move |b| self.lambda_min + (b as Float + 0.5) * width)
+    }
+
+    /// Samples wavelengths uniformly over the range of the film, so that every bucket receives
+    /// samples.
+    pub fn sample_wavelengths(&self, u: Float) -> SampledWavelengths {
+        SampledWavelengths::sample_uniform(u, self.lambda_min, self.lambda_max)
+    }
+
+    pub fn add_sample(
+        &self,
+        p_film: Point2i,
+        l: &SampledSpectrum,
+        lambda: &SampledWavelengths,
+        weight: Float,
+    ) {
+        let pixel = &self.pixels[self.base.pixel_offset(p_film)];
+        let rgb = clamp_rgb(
+            self.base.sensor.to_sensor_rgb(l, lambda),
+            self.max_component_value,
+        );
+        pixel.rgb.add(rgb, weight);
+
+        for i in 0..N_SPECTRUM_SAMPLES {
+            // Secondary wavelengths that were terminated carry no radiance
+            if lambda.pdf()[i] == 0.0 {
+                continue;
+            }
+            let b = self.lambda_to_bucket(lambda[i]);
+            pixel.bucket_sums[b].add((weight * l[i]) as f64);
+            pixel.weight_sums[b].add(weight as f64);
+        }
+    }
+
+    pub fn get_pixel_rgb(&self, p: Point2i) -> Rgb {
+        let rgb = self.pixels[self.base.pixel_offset(p)].rgb.rgb();
+        let [r, g, b] = self
+            .output_rgb_from_sensor_rgb
+            .mul_vector(&[rgb.r, rgb.g, rgb.b]);
+        Rgb::new(r, g, b)
+    }
+
+    /// The average radiance in each wavelength range of a pixel, which is 0 for ranges that no
+    /// sample fell in.
+    pub fn get_pixel_spectrum(&self, p: Point2i) -> Vec<Float> {
+        let pixel = &self.pixels[self.base.pixel_offset(p)];
+        pixel
+            .bucket_sums
+            .iter()
+            .zip(&pixel.weight_sums)
+            .map(|(sum, weight_sum)| match weight_sum.get() {
+                0.0 => 0.0,
+                weight_sum => (sum.get() / weight_sum) as Float,
+            })
+            .collect()
+    }
+
+    pub(super) fn sums(&self) -> impl Iterator<Item = &AtomicDouble> {
+        self.pixels.iter().flat_map(|pixel| {
+            pixel
+                .rgb
+                .sums()
+                .chain(&pixel.bucket_sums)
+                .chain(&pixel.weight_sums)
+        })
+    }
+
+    /// The image of the film, with the color of each pixel followed by one channel per
+    /// wavelength range, named after the wavelength in its middle, as in `S0.375nm`.
+    pub fn get_image(&self) -> Image {
+        let names: Vec<String> = ["R", "G", "B"]
+            .into_iter()
+            .map(String::from)
+            .chain(
+                self.bucket_wavelengths()
+                    .map(|lambda| format!("S0.{lambda:.0}nm")),
+            )
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let mut image = self.base.image(PixelFormat::Float, &names);
+        let all = image.all_channels_desc();
+        for p in self.base.pixel_bounds {
+            let rgb = self.get_pixel_rgb(p);
+            let values: Vec<Float> = [rgb.r, rgb.g, rgb.b]
+                .into_iter()
+                .chain(self.get_pixel_spectrum(p))
+                .collect();
+            image.set_channels(self.base.image_pixel(p), &all, &values);
+        }
+        image
+    }
+
+    fn lambda_to_bucket(&self, lambda: Float) -> usize {
+        let b = (lambda - self.lambda_min) / (self.lambda_max - self.lambda_min)
+            * self.n_buckets as Float;
+        (b as usize).min(self.n_buckets - 1)
+    }
+}
+
+#[derive(Debug)]
+struct SpectralPixel {
+    rgb: RgbPixel,
+    bucket_sums: Vec<AtomicDouble>,
+    weight_sums: Vec<AtomicDouble>,
+}
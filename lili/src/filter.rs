@@ -0,0 +1,207 @@
+//! Filters that reconstruct the image from its samples.
+mod filters;
+pub use filters::*;
+
+use crate::{
+    math::{
+        bounds::Bounds2f, points::Point2f, sampling::PiecewiseConstant2D, vectors::Vector2f, Float,
+    },
+    scene::{parser::ParseError, SceneEntity},
+};
+
+/// A sampled offset from the center of a pixel, and the weight that the radiance found along the
+/// camera ray through it is multiplied by.
+#[derive(Default, Clone, Copy)]
+pub struct FilterSample {
+    pub p: Point2f,
+    pub weight: Float,
+}
+
+/// A pixel reconstruction filter, chosen with the `PixelFilter` directive.
+///
+/// Rather than weighting samples by the filter when adding them to the film, camera rays are
+/// distributed according to the filter, which gives every sample the same weight for filters
+/// that are never negative.
+///
+/// # Examples
+///
+/// ```
+/// use lili::{
+///     filter::{Filter, GaussianFilter},
+///     math::{points::Point2f, vectors::Vector2f, Float},
+/// };
+///
+/// let filter = Filter::Gaussian(GaussianFilter::new(Vector2f::new(1.5, 1.5), 0.5));
+/// assert!(filter.evaluate(Point2f::new(0.0, 0.0)) > filter.evaluate(Point2f::new(0.5, 0.0)));
+/// assert_eq!(filter.evaluate(Point2f::new(2.0, 0.0)), 0.0);
+///
+/// // The weights of samples from the tabulated Gaussian average to its integral
+/// let n = 64;
+/// let mut sum = 0.0;
+/// for i in 0..n {
+///     for j in 0..n {
+///         let u = Point2f::new((i as Float + 0.5) / n as Float, (j as Float + 0.5) / n as Float);
+///         sum += filter.sample(u).weight;
+///     }
+/// }
+/// assert!((sum / (n * n) as Float - filter.integral()).abs() < 1e-3);
+/// ```
+#[derive(Clone)]
+pub enum Filter {
+    Box(BoxFilter),
+    Gaussian(GaussianFilter),
+    Mitchell(MitchellFilter),
+    LanczosSinc(LanczosSincFilter),
+    Triangle(TriangleFilter),
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::Gaussian(GaussianFilter::new(Vector2f::new(1.5, 1.5), 0.5))
+    }
+}
+
+impl Filter {
+    /// Creates the filter described by a `PixelFilter` directive.
+    pub fn create(entity: &SceneEntity) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        let radius = |default: Float| -> Result<Vector2f, ParseError> {
+            let radius = Vector2f::new(
+                params.get_one_float("xradius", default)?,
+                params.get_one_float("yradius", default)?,
+            );
+            if radius.x <= 0.0 || radius.y <= 0.0 {
+                return Err(ParseError::new(
+                    &entity.loc,
+                    "filter radius must be positive",
+                ));
+            }
+            Ok(radius)
+        };
+
+        let filter = match entity.name.as_str() {
+            "box" => Self::Box(BoxFilter::new(radius(0.5)?)),
+            "gaussian" => Self::Gaussian(GaussianFilter::new(
+                radius(1.5)?,
+                params.get_one_float("sigma", 0.5)?,
+            )),
+            "mitchell" => Self::Mitchell(MitchellFilter::new(
+                radius(2.0)?,
+                params.get_one_float("B", 1.0 / 3.0)?,
+                params.get_one_float("C", 1.0 / 3.0)?,
+            )),
+            "sinc" => Self::LanczosSinc(LanczosSincFilter::new(
+                radius(4.0)?,
+                params.get_one_float("tau", 3.0)?,
+            )),
+            "triangle" => Self::Triangle(TriangleFilter::new(radius(2.0)?)),
+            name => {
+                return Err(ParseError::new(
+                    &entity.loc,
+                    format!("\"{name}\": unknown filter type"),
+                ))
+            }
+        };
+        params.report_unused()?;
+        Ok(filter)
+    }
+
+    /// The distance from the center of the filter beyond which it is 0, along each axis.
+    pub fn radius(&self) -> Vector2f {
+        match self {
+            Self::Box(f) => f.radius,
+            Self::Gaussian(f) => f.radius,
+            Self::Mitchell(f) => f.radius,
+            Self::LanczosSinc(f) => f.radius,
+            Self::Triangle(f) => f.radius,
+        }
+    }
+
+    /// The value of the filter at an offset `p` from its center.
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        match self {
+            Self::Box(f) => f.evaluate(p),
+            Self::Gaussian(f) => f.evaluate(p),
+            Self::Mitchell(f) => f.evaluate(p),
+            Self::LanczosSinc(f) => f.evaluate(p),
+            Self::Triangle(f) => f.evaluate(p),
+        }
+    }
+
+    /// The integral of the filter over its support.
+    pub fn integral(&self) -> Float {
+        match self {
+            Self::Box(f) => f.integral(),
+            Self::Gaussian(f) => f.integral(),
+            Self::Mitchell(f) => f.integral(),
+            Self::LanczosSinc(f) => f.integral(),
+            Self::Triangle(f) => f.integral(),
+        }
+    }
+
+    /// Samples an offset from the center of the filter, with a density proportional to the
+    /// absolute value of the filter.
+    pub fn sample(&self, u: Point2f) -> FilterSample {
+        match self {
+            Self::Box(f) => f.sample(u),
+            Self::Gaussian(f) => f.sampler.sample(u),
+            Self::Mitchell(f) => f.sampler.sample(u),
+            Self::LanczosSinc(f) => f.sampler.sample(u),
+            Self::Triangle(f) => f.sample(u),
+        }
+    }
+}
+
+/// Samples a filter by tabulating it, for filters that cannot be sampled exactly.
+///
+/// The filter is treated as constant over each cell of the table, so the weights of the samples
+/// only approximate the ratio of the filter to its density.
+#[derive(Clone)]
+pub struct FilterSampler {
+    /// The values of the filter at the centers of the cells, row by row.
+    f: Vec<Float>,
+    /// The distribution of the absolute values of the filter.
+    distrib: PiecewiseConstant2D,
+}
+
+impl FilterSampler {
+    /// The number of cells per unit of filter radius.
+    const CELLS_PER_UNIT: Float = 32.0;
+
+    /// Tabulates the filter `f` with the given radius.
+    pub fn new(radius: Vector2f, f: impl Fn(Point2f) -> Float) -> Self {
+        let domain = Bounds2f::new(
+            Point2f::new(-radius.x, -radius.y),
+            Point2f::new(radius.x, radius.y),
+        );
+        let nx = ((Self::CELLS_PER_UNIT * radius.x) as usize).max(1);
+        let ny = ((Self::CELLS_PER_UNIT * radius.y) as usize).max(1);
+
+        let mut values = Vec::with_capacity(nx * ny);
+        for y in 0..ny {
+            for x in 0..nx {
+                let t = Point2f::new(
+                    (x as Float + 0.5) / nx as Float,
+                    (y as Float + 0.5) / ny as Float,
+                );
+                values.push(f(domain.lerp(t)));
+            }
+        }
+
+        Self {
+            distrib: PiecewiseConstant2D::new(&values, nx, ny, domain),
+            f: values,
+        }
+    }
+
+    /// Samples an offset from the center of the filter.
+    pub fn sample(&self, u: Point2f) -> FilterSample {
+        let (p, pdf, cell) = self.distrib.sample(u);
+
+        // The density is proportional to the absolute value of the cell, so the weight is the
+        // integral with the sign of the cell
+        let f = self.f[(cell.y * self.distrib.resolution().x + cell.x) as usize];
+        let weight = if pdf == 0.0 { 0.0 } else { f / pdf };
+        FilterSample { p, weight }
+    }
+}
@@ -0,0 +1,202 @@
+//! The reconstruction filters that scenes can choose from.
+use crate::{
+    filter::{FilterSample, FilterSampler},
+    math::{
+        gaussian, gaussian_integral, points::Point2f, sampling::sample_tent, vectors::Vector2f,
+        windowed_sinc, Float, FloatExt,
+    },
+};
+
+/// A filter that weights all samples within its radius equally.
+#[derive(Clone)]
+pub struct BoxFilter {
+    pub radius: Vector2f,
+}
+
+impl BoxFilter {
+    pub fn new(radius: Vector2f) -> Self {
+        Self { radius }
+    }
+
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        if p.x.abs() <= self.radius.x && p.y.abs() <= self.radius.y {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn integral(&self) -> Float {
+        4.0 * self.radius.x * self.radius.y
+    }
+
+    pub fn sample(&self, u: Point2f) -> FilterSample {
+        let p = Point2f::new(
+            u.x.lerp(-self.radius.x, self.radius.x),
+            u.y.lerp(-self.radius.y, self.radius.y),
+        );
+        FilterSample { p, weight: 1.0 }
+    }
+}
+
+/// A Gaussian filter, shifted down so that it reaches 0 at its radius.
+#[derive(Clone)]
+pub struct GaussianFilter {
+    pub radius: Vector2f,
+    sigma: Float,
+    exp_x: Float,
+    exp_y: Float,
+    pub(super) sampler: FilterSampler,
+}
+
+impl GaussianFilter {
+    /// Creates the filter with standard deviation `sigma`.
+    pub fn new(radius: Vector2f, sigma: Float) -> Self {
+        let exp_x = gaussian(radius.x, 0.0, sigma);
+        let exp_y = gaussian(radius.y, 0.0, sigma);
+        Self {
+            radius,
+            sigma,
+            exp_x,
+            exp_y,
+            sampler: FilterSampler::new(radius, |p| shifted_gaussian(p, sigma, exp_x, exp_y)),
+        }
+    }
+
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        shifted_gaussian(p, self.sigma, self.exp_x, self.exp_y)
+    }
+
+    pub fn integral(&self) -> Float {
+        (gaussian_integral(-self.radius.x, self.radius.x, 0.0, self.sigma)
+            - 2.0 * self.radius.x * self.exp_x)
+            * (gaussian_integral(-self.radius.y, self.radius.y, 0.0, self.sigma)
+                - 2.0 * self.radius.y * self.exp_y)
+    }
+}
+
+fn shifted_gaussian(p: Point2f, sigma: Float, exp_x: Float, exp_y: Float) -> Float {
+    (gaussian(p.x, 0.0, sigma) - exp_x).max(0.0) * (gaussian(p.y, 0.0, sigma) - exp_y).max(0.0)
+}
+
+/// The Mitchell-Netravali filter, a cubic that trades blurring against ringing with its `b` and
+/// `c` parameters.
+#[derive(Clone)]
+pub struct MitchellFilter {
+    pub radius: Vector2f,
+    b: Float,
+    c: Float,
+    pub(super) sampler: FilterSampler,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: Vector2f, b: Float, c: Float) -> Self {
+        Self {
+            radius,
+            b,
+            c,
+            sampler: FilterSampler::new(radius, |p| mitchell(p, radius, b, c)),
+        }
+    }
+
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        mitchell(p, self.radius, self.b, self.c)
+    }
+
+    pub fn integral(&self) -> Float {
+        self.radius.x * self.radius.y / 4.0
+    }
+}
+
+fn mitchell(p: Point2f, radius: Vector2f, b: Float, c: Float) -> Float {
+    mitchell_1d(2.0 * p.x / radius.x, b, c) * mitchell_1d(2.0 * p.y / radius.y, b, c)
+}
+
+/// The Mitchell-Netravali cubic, which is nonzero between -2 and 2.
+fn mitchell_1d(x: Float, b: Float, c: Float) -> Float {
+    let x = x.abs();
+    if x <= 1.0 {
+        ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+            + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+            + (6.0 - 2.0 * b))
+            / 6.0
+    } else if x <= 2.0 {
+        ((-b - 6.0 * c) * x * x * x
+            + (6.0 * b + 30.0 * c) * x * x
+            + (-12.0 * b - 48.0 * c) * x
+            + (8.0 * b + 24.0 * c))
+            / 6.0
+    } else {
+        0.0
+    }
+}
+
+/// A sinc filter windowed by a Lanczos window with `tau` lobes.
+#[derive(Clone)]
+pub struct LanczosSincFilter {
+    pub radius: Vector2f,
+    tau: Float,
+    integral: Float,
+    pub(super) sampler: FilterSampler,
+}
+
+impl LanczosSincFilter {
+    pub fn new(radius: Vector2f, tau: Float) -> Self {
+        // The filter has no closed-form integral, but it is separable, so each axis is integrated
+        // numerically with the midpoint rule
+        let integral_1d = |r: Float| {
+            let n = 1024;
+            let dx = 2.0 * r / n as Float;
+            (0..n)
+                .map(|i| windowed_sinc(-r + (i as Float + 0.5) * dx, r, tau) * dx)
+                .sum::<Float>()
+        };
+
+        Self {
+            radius,
+            tau,
+            integral: integral_1d(radius.x) * integral_1d(radius.y),
+            sampler: FilterSampler::new(radius, |p| lanczos_sinc(p, radius, tau)),
+        }
+    }
+
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        lanczos_sinc(p, self.radius, self.tau)
+    }
+
+    pub fn integral(&self) -> Float {
+        self.integral
+    }
+}
+
+fn lanczos_sinc(p: Point2f, radius: Vector2f, tau: Float) -> Float {
+    windowed_sinc(p.x, radius.x, tau) * windowed_sinc(p.y, radius.y, tau)
+}
+
+/// A filter that falls off linearly from its center to its radius.
+#[derive(Clone)]
+pub struct TriangleFilter {
+    pub radius: Vector2f,
+}
+
+impl TriangleFilter {
+    pub fn new(radius: Vector2f) -> Self {
+        Self { radius }
+    }
+
+    pub fn evaluate(&self, p: Point2f) -> Float {
+        (self.radius.x - p.x.abs()).max(0.0) * (self.radius.y - p.y.abs()).max(0.0)
+    }
+
+    pub fn integral(&self) -> Float {
+        self.radius.x * self.radius.x * self.radius.y * self.radius.y
+    }
+
+    pub fn sample(&self, u: Point2f) -> FilterSample {
+        let p = Point2f::new(
+            sample_tent(u.x, self.radius.x),
+            sample_tent(u.y, self.radius.y),
+        );
+        FilterSample { p, weight: 1.0 }
+    }
+}
@@ -0,0 +1,359 @@
+//! Images with any number of named channels, and reading and writing them in common file formats.
+use std::{error::Error, fmt, io, path::Path};
+
+use half::f16;
+
+mod exr;
+mod pfm;
+mod png;
+mod qoi;
+
+use crate::{
+    color::ColorEncoding,
+    math::{bounds::Bounds2i, points::Point2i},
+    Float,
+};
+
+/// How the values of the channels of an image are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8-bit values, interpreted according to the [`ColorEncoding`] of the image.
+    U8,
+    /// 16-bit floating-point values.
+    Half,
+    /// 32-bit floating-point values.
+    Float,
+}
+
+#[derive(Clone)]
+enum PixelData {
+    U8(Vec<u8>),
+    Half(Vec<f16>),
+    Float(Vec<f32>),
+}
+
+/// Information about an image that some formats store along with its pixels.
+#[derive(Clone, Copy, Default)]
+pub struct ImageMetadata {
+    /// The pixels of a larger image that the image covers, if it is a crop of it.
+    pub pixel_bounds: Option<Bounds2i>,
+    /// The resolution of the larger image that the image is a crop of.
+    pub full_resolution: Option<Point2i>,
+}
+
+/// An error encountered while reading or writing an image.
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// The file is not a valid image in its format.
+    Invalid(String),
+    /// The image cannot be stored in the requested format.
+    Unsupported(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => err.fmt(f),
+            Self::Invalid(message) | Self::Unsupported(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// A selection of the channels of an image, in a given order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageChannelDesc {
+    offset: Vec<usize>,
+}
+
+impl ImageChannelDesc {
+    /// The number of selected channels.
+    pub fn size(&self) -> usize {
+        self.offset.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset.is_empty()
+    }
+}
+
+/// An image whose pixels all have the same channels, which are identified by name.
+///
+/// Color channels are named `R`, `G` and `B`, and other channels may be grouped into layers by
+/// prefixing them with the name of the layer, as in `Albedo.R`. All formats but OpenEXR only
+/// store the color of the image, or its only channel for single-channel images.
+///
+/// # Examples
+///
+/// ```
+/// use lili::{
+///     color::ColorEncoding,
+///     image::{Image, ImageMetadata, PixelFormat},
+///     math::points::Point2i,
+/// };
+///
+/// let mut image = Image::new(
+///     PixelFormat::Float,
+///     Point2i::new(4, 2),
+///     &["R", "G", "B", "Albedo.R", "Albedo.G", "Albedo.B"],
+///     ColorEncoding::Linear,
+/// );
+/// let albedo = image.channel_desc(&["Albedo.R", "Albedo.G", "Albedo.B"]).unwrap();
+/// image.set_channels(Point2i::new(3, 1), &albedo, &[0.25, 0.5, 0.75]);
+///
+/// // OpenEXR files keep every channel
+/// let path = std::env::temp_dir().join("lili-image-example.exr");
+/// image.write(&path, &ImageMetadata::default()).unwrap();
+/// let read = Image::read(&path, ColorEncoding::Srgb).unwrap();
+/// let albedo = read.channel_desc(&["Albedo.R", "Albedo.G", "Albedo.B"]).unwrap();
+/// assert_eq!(read.get_channels(Point2i::new(3, 1), &albedo), [0.25, 0.5, 0.75]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Image {
+    resolution: Point2i,
+    channel_names: Vec<String>,
+    encoding: ColorEncoding,
+    data: PixelData,
+}
+
+impl Image {
+    /// Creates an image whose channels are all 0.
+    ///
+    /// The encoding only applies to [`PixelFormat::U8`] images.
+    pub fn new(
+        format: PixelFormat,
+        resolution: Point2i,
+        channel_names: &[&str],
+        encoding: ColorEncoding,
+    ) -> Self {
+        assert!(resolution.x >= 0 && resolution.y >= 0);
+        let size = resolution.x as usize * resolution.y as usize * channel_names.len();
+        let data = match format {
+            PixelFormat::U8 => PixelData::U8(vec![0; size]),
+            PixelFormat::Half => PixelData::Half(vec![f16::ZERO; size]),
+            PixelFormat::Float => PixelData::Float(vec![0.0; size]),
+        };
+        Self {
+            resolution,
+            channel_names: channel_names.iter().map(|name| name.to_string()).collect(),
+            encoding,
+            data,
+        }
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        match self.data {
+            PixelData::U8(_) => PixelFormat::U8,
+            PixelData::Half(_) => PixelFormat::Half,
+            PixelData::Float(_) => PixelFormat::Float,
+        }
+    }
+
+    pub fn resolution(&self) -> Point2i {
+        self.resolution
+    }
+
+    pub fn n_channels(&self) -> usize {
+        self.channel_names.len()
+    }
+
+    pub fn channel_names(&self) -> &[String] {
+        &self.channel_names
+    }
+
+    pub fn encoding(&self) -> ColorEncoding {
+        self.encoding
+    }
+
+    /// Selects the channels with the given names, or returns `None` if one of them is missing.
+    pub fn channel_desc(&self, names: &[&str]) -> Option<ImageChannelDesc> {
+        let offset = names
+            .iter()
+            .map(|name| self.channel_names.iter().position(|n| n == name))
+            .collect::<Option<_>>()?;
+        Some(ImageChannelDesc { offset })
+    }
+
+    /// Selects all of the channels of the image.
+    pub fn all_channels_desc(&self) -> ImageChannelDesc {
+        ImageChannelDesc {
+            offset: (0..self.n_channels()).collect(),
+        }
+    }
+
+    /// The linear value of channel `c` of the pixel `p`.
+    pub fn get_channel(&self, p: Point2i, c: usize) -> Float {
+        let i = self.pixel_offset(p) + c;
+        match &self.data {
+            PixelData::U8(data) => self.encoding.to_linear(data[i]),
+            PixelData::Half(data) => data[i].to_f32() as Float,
+            PixelData::Float(data) => data[i] as Float,
+        }
+    }
+
+    /// Sets channel `c` of the pixel `p` to the linear value `v`, which is rounded to the
+    /// precision of the image.
+    // `Float` is only `f32` without the `f64` feature
+    #[allow(clippy::unnecessary_cast)]
+    pub fn set_channel(&mut self, p: Point2i, c: usize, v: Float) {
+        let i = self.pixel_offset(p) + c;
+        match &mut self.data {
+            PixelData::U8(data) => data[i] = self.encoding.from_linear(v),
+            PixelData::Half(data) => data[i] = f16::from_f32(v as f32),
+            PixelData::Float(data) => data[i] = v as f32,
+        }
+    }
+
+    /// The values of the selected channels of the pixel `p`.
+    pub fn get_channels(&self, p: Point2i, desc: &ImageChannelDesc) -> Vec<Float> {
+        desc.offset
+            .iter()
+            .map(|&c| self.get_channel(p, c))
+            .collect()
+    }
+
+    /// Sets the selected channels of the pixel `p` to `values`.
+    pub fn set_channels(&mut self, p: Point2i, desc: &ImageChannelDesc, values: &[Float]) {
+        assert_eq!(desc.size(), values.len());
+        for (&c, &v) in desc.offset.iter().zip(values) {
+            self.set_channel(p, c, v);
+        }
+    }
+
+    /// A copy of the image with only the selected channels.
+    pub fn select_channels(&self, desc: &ImageChannelDesc) -> Self {
+        let names: Vec<&str> = desc
+            .offset
+            .iter()
+            .map(|&c| self.channel_names[c].as_str())
+            .collect();
+        let mut image = Self::new(self.format(), self.resolution, &names, self.encoding);
+        let all = image.all_channels_desc();
+        for p in self.pixels() {
+            image.set_channels(p, &all, &self.get_channels(p, desc));
+        }
+        image
+    }
+
+    /// A copy of the image with its values stored in `format`.
+    pub fn convert_to_format(&self, format: PixelFormat) -> Self {
+        if format == self.format() {
+            return self.clone();
+        }
+        let names: Vec<&str> = self.channel_names.iter().map(String::as_str).collect();
+        let mut image = Self::new(format, self.resolution, &names, self.encoding);
+        let all = self.all_channels_desc();
+        for p in self.pixels() {
+            image.set_channels(p, &all, &self.get_channels(p, &all));
+        }
+        image
+    }
+
+    /// The mean squared difference between the selected channels of this image and the same
+    /// channels of `reference`, for each channel.
+    pub fn mse(&self, desc: &ImageChannelDesc, reference: &Image) -> Vec<Float> {
+        assert!(
+            self.resolution.x == reference.resolution.x
+                && self.resolution.y == reference.resolution.y
+        );
+        let mut sum = vec![0.0_f64; desc.size()];
+        for p in self.pixels() {
+            let values = self.get_channels(p, desc);
+            let reference = reference.get_channels(p, desc);
+            for ((sum, v), r) in sum.iter_mut().zip(values).zip(reference) {
+                *sum += ((v - r) as f64).powi(2);
+            }
+        }
+        let n = self.resolution.x as f64 * self.resolution.y as f64;
+        sum.into_iter().map(|s| (s / n) as Float).collect()
+    }
+
+    /// Reads an image, choosing the format from the extension of the file name: `exr`, `pfm`,
+    /// `png` or `qoi`.
+    ///
+    /// 8-bit images whose file does not say how they are encoded are assumed to use `encoding`.
+    pub fn read(path: impl AsRef<Path>, encoding: ColorEncoding) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        match extension(path).as_str() {
+            "exr" => exr::read(path),
+            "pfm" => pfm::read(path),
+            "png" => png::read(path, encoding),
+            "qoi" => qoi::read(path),
+            _ => Err(unsupported_extension(path)),
+        }
+    }
+
+    /// Writes the image, choosing the format from the extension of the file name as for
+    /// [`read`](Self::read).
+    ///
+    /// PNG files are 8-bit or 16-bit and QOI files are 8-bit, both sRGB encoded, so values outside
+    /// of 0 to 1 are clamped. Only OpenEXR files store the `metadata`.
+    pub fn write(
+        &self,
+        path: impl AsRef<Path>,
+        metadata: &ImageMetadata,
+    ) -> Result<(), ImageError> {
+        let path = path.as_ref();
+        match extension(path).as_str() {
+            "exr" => exr::write(self, path, metadata),
+            "pfm" => pfm::write(self, path),
+            "png" => png::write(self, path),
+            "qoi" => qoi::write(self, path),
+            _ => Err(unsupported_extension(path)),
+        }
+    }
+
+    /// The color channels that formats other than OpenEXR store: `R`, `G`, `B` and optionally
+    /// `A`, or the only channel of single-channel images.
+    fn color_channels_desc(&self, alpha: bool) -> Result<ImageChannelDesc, ImageError> {
+        if self.n_channels() == 1 {
+            return Ok(self.all_channels_desc());
+        }
+        let rgba = alpha
+            .then(|| self.channel_desc(&["R", "G", "B", "A"]))
+            .flatten();
+        rgba.or_else(|| self.channel_desc(&["R", "G", "B"]))
+            .ok_or_else(|| {
+                ImageError::Unsupported(format!(
+                    "image with channels {:?} has no RGB channels",
+                    self.channel_names
+                ))
+            })
+    }
+
+    /// The pixels of the image, row by row.
+    fn pixels(&self) -> impl Iterator<Item = Point2i> {
+        let Point2i {
+            x: width,
+            y: height,
+        } = self.resolution;
+        (0..height).flat_map(move |y| (0..width).map(move |x| Point2i::new(x, y)))
+    }
+
+    fn pixel_offset(&self, p: Point2i) -> usize {
+        debug_assert!(p.x >= 0 && p.x < self.resolution.x && p.y >= 0 && p.y < self.resolution.y);
+        (p.y as usize * self.resolution.x as usize + p.x as usize) * self.n_channels()
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn unsupported_extension(path: &Path) -> ImageError {
+    ImageError::Unsupported(format!(
+        "{}: unsupported image format; use .exr, .pfm, .png or .qoi",
+        path.display()
+    ))
+}
@@ -0,0 +1,124 @@
+//! OpenEXR images, which store any number of 16-bit or 32-bit floating-point channels.
+use std::path::Path;
+
+use crate::{
+    color::ColorEncoding,
+    image::{Image, ImageError, ImageMetadata, PixelData, PixelFormat},
+    math::points::Point2i,
+};
+use ::exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, ReadChannels, ReadLayers, SmallVec, Vec2, WritableImage,
+};
+
+impl From<::exr::error::Error> for ImageError {
+    fn from(err: ::exr::error::Error) -> Self {
+        match err {
+            ::exr::error::Error::Io(err) => Self::Io(err),
+            err => Self::Invalid(err.to_string()),
+        }
+    }
+}
+
+pub(super) fn read(path: &Path) -> Result<Image, ImageError> {
+    let exr = ::exr::prelude::read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .all_channels()
+        .first_valid_layer()
+        .all_attributes()
+        .from_file(path)?;
+    let layer = exr.layer_data;
+    let resolution = Point2i::new(layer.size.x() as i32, layer.size.y() as i32);
+    let channels = &layer.channel_data.list;
+
+    // Half images stay half, and anything else is widened to float
+    let all_half = channels
+        .iter()
+        .all(|c| matches!(c.sample_data, FlatSamples::F16(_)));
+    let format = if all_half {
+        PixelFormat::Half
+    } else {
+        PixelFormat::Float
+    };
+    let names: Vec<String> = channels.iter().map(|c| c.name.to_string()).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut image = Image::new(format, resolution, &names, ColorEncoding::Linear);
+
+    // The samples of each channel are stored separately, while the image interleaves them
+    let n = names.len();
+    for (c, channel) in channels.iter().enumerate() {
+        match (&mut image.data, &channel.sample_data) {
+            (PixelData::Half(data), FlatSamples::F16(samples)) => {
+                for (i, s) in samples.iter().enumerate() {
+                    data[i * n + c] = *s;
+                }
+            }
+            (PixelData::Float(data), samples) => {
+                for (i, s) in samples.values_as_f32().enumerate() {
+                    data[i * n + c] = s;
+                }
+            }
+            _ => unreachable!("half images only have half channels"),
+        }
+    }
+    Ok(image)
+}
+
+/// Writes the image as the data window of the file, placed at the pixel bounds of the metadata
+/// within a display window of its full resolution.
+pub(super) fn write(
+    image: &Image,
+    path: &Path,
+    metadata: &ImageMetadata,
+) -> Result<(), ImageError> {
+    // OpenEXR has no 8-bit channels
+    let half;
+    let image = if image.format() == PixelFormat::U8 {
+        half = image.convert_to_format(PixelFormat::Half);
+        &half
+    } else {
+        image
+    };
+
+    let n = image.n_channels();
+    let channel = |c: usize| -> FlatSamples {
+        match &image.data {
+            PixelData::Half(data) => {
+                FlatSamples::F16(data.iter().skip(c).step_by(n).copied().collect())
+            }
+            PixelData::Float(data) => {
+                FlatSamples::F32(data.iter().skip(c).step_by(n).copied().collect())
+            }
+            PixelData::U8(_) => unreachable!("8-bit images are converted to half"),
+        }
+    };
+    let channels: SmallVec<_> = image
+        .channel_names
+        .iter()
+        .enumerate()
+        .map(|(c, name)| AnyChannel::new(name.as_str(), channel(c)))
+        .collect();
+
+    let resolution = (image.resolution.x as usize, image.resolution.y as usize);
+    let position = metadata
+        .pixel_bounds
+        .map_or(Vec2(0, 0), |b| Vec2(b.p_min.x, b.p_min.y));
+    let display_size = metadata
+        .full_resolution
+        .map_or(resolution, |r| (r.x as usize, r.y as usize));
+    let layer = Layer::new(
+        resolution,
+        LayerAttributes {
+            layer_position: position,
+            ..LayerAttributes::default()
+        },
+        Encoding::SMALL_LOSSLESS,
+        AnyChannels::sort(channels),
+    );
+    let attributes = ImageAttributes::new(IntegerBounds::new((0, 0), display_size));
+    ::exr::image::Image::new(attributes, layer)
+        .write()
+        .to_file(path)?;
+    Ok(())
+}
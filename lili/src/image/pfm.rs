@@ -0,0 +1,101 @@
+//! Portable float maps, a minimal format for 32-bit floating-point grayscale or RGB images.
+use std::{fs, io::Write, path::Path};
+
+use crate::{
+    color::ColorEncoding,
+    image::{Image, ImageError, PixelFormat},
+    math::points::Point2i,
+};
+
+pub(super) fn read(path: &Path) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    let invalid = |message: &str| ImageError::Invalid(format!("{}: {message}", path.display()));
+
+    // The header is four whitespace-separated words, followed by a single whitespace character
+    let mut words = Vec::with_capacity(4);
+    let mut pos = 0;
+    while words.len() < 4 {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("truncated PFM header"));
+        }
+        words.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    let data = bytes
+        .get(pos + 1..)
+        .ok_or_else(|| invalid("truncated PFM header"))?;
+
+    let names: &[&str] = match words[0].as_str() {
+        "PF" => &["R", "G", "B"],
+        "Pf" => &["Y"],
+        _ => return Err(invalid("not a PFM file")),
+    };
+    let (width, height, scale) = match (
+        words[1].parse::<i32>(),
+        words[2].parse::<i32>(),
+        words[3].parse::<f32>(),
+    ) {
+        (Ok(w), Ok(h), Ok(s)) if w > 0 && h > 0 && s != 0.0 => (w, h, s),
+        _ => return Err(invalid("invalid PFM header")),
+    };
+
+    let n = names.len();
+    let count = width as usize * height as usize * n;
+    if data.len() < 4 * count {
+        return Err(invalid("truncated PFM data"));
+    }
+    // A negative scale means that the values are little-endian
+    let little_endian = scale < 0.0;
+    let values = data[..4 * count].chunks_exact(4).map(|b| {
+        let b = [b[0], b[1], b[2], b[3]];
+        let v = if little_endian {
+            f32::from_le_bytes(b)
+        } else {
+            f32::from_be_bytes(b)
+        };
+        v * scale.abs()
+    });
+
+    // Rows are stored from the bottom of the image to the top
+    let mut image = Image::new(
+        PixelFormat::Float,
+        Point2i::new(width, height),
+        names,
+        ColorEncoding::Linear,
+    );
+    for (i, v) in values.enumerate() {
+        let x = (i / n) % width as usize;
+        let y = height as usize - 1 - i / n / width as usize;
+        image.set_channel(Point2i::new(x as i32, y as i32), i % n, v as _);
+    }
+    Ok(image)
+}
+
+// `Float` is only `f32` without the `f64` feature
+#[allow(clippy::unnecessary_cast)]
+pub(super) fn write(image: &Image, path: &Path) -> Result<(), ImageError> {
+    let desc = image.color_channels_desc(false)?;
+    let magic = if desc.size() == 1 { "Pf" } else { "PF" };
+    let Point2i {
+        x: width,
+        y: height,
+    } = image.resolution;
+
+    let mut out = Vec::with_capacity(32 + 4 * desc.size() * (width * height) as usize);
+    write!(out, "{magic}\n{width} {height}\n-1\n")?;
+    for y in (0..height).rev() {
+        for x in 0..width {
+            for v in image.get_channels(Point2i::new(x, y), &desc) {
+                out.extend_from_slice(&(v as f32).to_le_bytes());
+            }
+        }
+    }
+    fs::write(path, out)?;
+    Ok(())
+}
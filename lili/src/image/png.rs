@@ -0,0 +1,146 @@
+//! PNG images, which store 8-bit or 16-bit integer channels.
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use ::png::{
+    BitDepth, ColorType, Decoder, DecodingError, Encoder, EncodingError, Info, ScaledFloat,
+    SrgbRenderingIntent, Transformations,
+};
+
+use crate::{
+    color::{linear_to_srgb, srgb_to_linear, ColorEncoding},
+    image::{Image, ImageError, PixelData, PixelFormat},
+    math::points::Point2i,
+    Float,
+};
+
+impl From<DecodingError> for ImageError {
+    fn from(err: DecodingError) -> Self {
+        match err {
+            DecodingError::IoError(err) => Self::Io(err),
+            err => Self::Invalid(err.to_string()),
+        }
+    }
+}
+
+impl From<EncodingError> for ImageError {
+    fn from(err: EncodingError) -> Self {
+        match err {
+            EncodingError::IoError(err) => Self::Io(err),
+            err => Self::Invalid(err.to_string()),
+        }
+    }
+}
+
+/// Reads 8-bit images as they are and 16-bit images as half-precision linear values.
+///
+/// The encoding of the values is taken from the sRGB or gAMA chunk of the file, and is
+/// `encoding` if it has neither.
+pub(super) fn read(path: &Path, encoding: ColorEncoding) -> Result<Image, ImageError> {
+    let mut decoder = Decoder::new(BufReader::new(File::open(path)?));
+    // Palettes and bit depths below 8 are expanded to plain 8-bit channels
+    decoder.set_transformations(Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let encoding = file_encoding(reader.info()).unwrap_or(encoding);
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    let names: &[&str] = match info.color_type {
+        ColorType::Grayscale => &["Y"],
+        ColorType::GrayscaleAlpha => &["Y", "A"],
+        ColorType::Rgb => &["R", "G", "B"],
+        ColorType::Rgba => &["R", "G", "B", "A"],
+        ColorType::Indexed => unreachable!("palettes are expanded"),
+    };
+    let resolution = Point2i::new(info.width as i32, info.height as i32);
+
+    if info.bit_depth == BitDepth::Sixteen {
+        let mut image = Image::new(PixelFormat::Half, resolution, names, encoding);
+        let PixelData::Half(data) = &mut image.data else {
+            unreachable!()
+        };
+        for (v, b) in data.iter_mut().zip(buf.chunks_exact(2)) {
+            let v16 = u16::from_be_bytes([b[0], b[1]]) as Float / 65535.0;
+            let linear = match encoding {
+                ColorEncoding::Linear => v16,
+                ColorEncoding::Srgb => srgb_to_linear(v16),
+            };
+            *v = half::f16::from_f32(linear as f32);
+        }
+        Ok(image)
+    } else {
+        let mut image = Image::new(PixelFormat::U8, resolution, names, encoding);
+        image.data = PixelData::U8(buf);
+        Ok(image)
+    }
+}
+
+/// The encoding that the chunks of a file declare, if any.
+///
+/// Only linear values and the sRGB curve are supported, so other gamma curves are read as the
+/// latter, which is the closest to the usual gamma of 2.2.
+fn file_encoding(info: &Info) -> Option<ColorEncoding> {
+    if info.srgb.is_some() {
+        return Some(ColorEncoding::Srgb);
+    }
+    let gamma = info.source_gamma?.into_value();
+    if (gamma - 1.0).abs() < 0.01 {
+        Some(ColorEncoding::Linear)
+    } else {
+        Some(ColorEncoding::Srgb)
+    }
+}
+
+/// Writes 8-bit images as they are, and others as 16-bit sRGB-encoded values.
+pub(super) fn write(image: &Image, path: &Path) -> Result<(), ImageError> {
+    let desc = image.color_channels_desc(true)?;
+    let color_type = match desc.size() {
+        1 => ColorType::Grayscale,
+        3 => ColorType::Rgb,
+        _ => ColorType::Rgba,
+    };
+    let Point2i {
+        x: width,
+        y: height,
+    } = image.resolution;
+
+    let mut encoder = Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        height as u32,
+    );
+    encoder.set_color(color_type);
+
+    let mut data = Vec::new();
+    if let PixelData::U8(values) = &image.data {
+        encoder.set_depth(BitDepth::Eight);
+        match image.encoding {
+            ColorEncoding::Srgb => encoder.set_source_srgb(SrgbRenderingIntent::Perceptual),
+            ColorEncoding::Linear => encoder.set_source_gamma(ScaledFloat::new(1.0)),
+        }
+        let n = image.n_channels();
+        for pixel in values.chunks_exact(n) {
+            data.extend(desc.offset.iter().map(|&c| pixel[c]));
+        }
+    } else {
+        encoder.set_depth(BitDepth::Sixteen);
+        encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+        for y in 0..height {
+            for x in 0..width {
+                for v in image.get_channels(Point2i::new(x, y), &desc) {
+                    let v = (linear_to_srgb(v.clamp(0.0, 1.0)) * 65535.0).round() as u16;
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
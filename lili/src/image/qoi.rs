@@ -0,0 +1,74 @@
+//! QOI images, a fast lossless format for 8-bit RGB and RGBA images.
+use std::{fs, path::Path};
+
+use ::qoi::{ColorSpace, Decoder, Encoder};
+
+use crate::{
+    color::ColorEncoding,
+    image::{Image, ImageError, PixelData, PixelFormat},
+    math::points::Point2i,
+};
+
+impl From<::qoi::Error> for ImageError {
+    fn from(err: ::qoi::Error) -> Self {
+        match err {
+            ::qoi::Error::IoError(err) => Self::Io(err),
+            err => Self::Invalid(err.to_string()),
+        }
+    }
+}
+
+pub(super) fn read(path: &Path) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    let mut decoder = Decoder::new(&bytes)?;
+    let header = *decoder.header();
+    let data = decoder.decode_to_vec()?;
+
+    let names: &[&str] = if header.channels.is_rgba() {
+        &["R", "G", "B", "A"]
+    } else {
+        &["R", "G", "B"]
+    };
+    let encoding = match header.colorspace {
+        ColorSpace::Srgb => ColorEncoding::Srgb,
+        ColorSpace::Linear => ColorEncoding::Linear,
+    };
+    let resolution = Point2i::new(header.width as i32, header.height as i32);
+    let mut image = Image::new(PixelFormat::U8, resolution, names, encoding);
+    image.data = PixelData::U8(data);
+    Ok(image)
+}
+
+/// Writes the image with sRGB-encoded 8-bit values, repeating the channel of single-channel
+/// images as red, green and blue.
+pub(super) fn write(image: &Image, path: &Path) -> Result<(), ImageError> {
+    let desc = image.color_channels_desc(true)?;
+    let Point2i {
+        x: width,
+        y: height,
+    } = image.resolution;
+
+    let mut data = Vec::with_capacity((width * height) as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let p = Point2i::new(x, y);
+            let values = image.get_channels(p, &desc);
+            let values = if values.len() == 1 {
+                vec![values[0]; 3]
+            } else {
+                values
+            };
+            data.extend(
+                values
+                    .into_iter()
+                    .map(|v| ColorEncoding::Srgb.from_linear(v)),
+            );
+        }
+    }
+
+    let encoded = Encoder::new(&data, width as u32, height as u32)?
+        .with_colorspace(ColorSpace::Srgb)
+        .encode_to_vec()?;
+    fs::write(path, encoded)?;
+    Ok(())
+}
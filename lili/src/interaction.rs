@@ -0,0 +1,158 @@
+//! Local geometry of points where rays intersect surfaces.
+use crate::{
+    math::{
+        dot::Dot,
+        face_forward::FaceForward,
+        interval::Point3fi,
+        normalize::Normalize,
+        normals::Normal3f,
+        points::{Point2f, Point3f},
+        vectors::{Cross, Vector3f},
+        Float,
+    },
+    media::{Medium, MediumInterface},
+    rays::{self, Ray, RayDifferential},
+};
+
+/// The shading geometry at a surface point, which may differ from the true geometry, for example
+/// with interpolated normals or bump mapping.
+#[derive(Default, Clone, Copy)]
+pub struct Shading {
+    pub n: Normal3f,
+    pub dpdu: Vector3f,
+    pub dpdv: Vector3f,
+    pub dndu: Normal3f,
+    pub dndv: Normal3f,
+}
+
+#[derive(Default, Clone)]
+pub struct SurfaceInteraction {
+    /// The intersection point, along with bounds on its floating-point error.
+    pub pi: Point3fi,
+    pub time: Float,
+    /// The outgoing direction, pointing back along the ray that found the intersection.
+    pub wo: Vector3f,
+    pub n: Normal3f,
+    pub uv: Point2f,
+    pub dpdu: Vector3f,
+    pub dpdv: Vector3f,
+    pub dndu: Normal3f,
+    pub dndv: Normal3f,
+    pub shading: Shading,
+    /// The media on either side of the surface, if it separates two media.
+    pub medium_interface: Option<MediumInterface>,
+    /// The medium the point lies in, if the surface does not separate media.
+    pub medium: Option<Medium>,
+}
+
+impl SurfaceInteraction {
+    /// Creates an interaction from the partial derivatives of a surface at `pi`.
+    ///
+    /// The normal is computed from `dpdu` and `dpdv`, and flipped if `flip_normal` is set, such as
+    /// when the shape has reversed orientation or its transformation swaps handedness.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        pi: Point3fi,
+        uv: Point2f,
+        wo: Vector3f,
+        dpdu: Vector3f,
+        dpdv: Vector3f,
+        dndu: Normal3f,
+        dndv: Normal3f,
+        time: Float,
+        flip_normal: bool,
+    ) -> Self {
+        let cross = dpdu.cross(&dpdv).normalize();
+        let mut n = Normal3f::new(cross.x, cross.y, cross.z);
+        if flip_normal {
+            n = -n;
+        }
+
+        Self {
+            pi,
+            time,
+            wo: wo.normalize(),
+            n,
+            uv,
+            dpdu,
+            dpdv,
+            dndu,
+            dndv,
+            shading: Shading {
+                n,
+                dpdu,
+                dpdv,
+                dndu,
+                dndv,
+            },
+            medium_interface: None,
+            medium: None,
+        }
+    }
+
+    /// The intersection point, without its error bounds.
+    pub fn p(&self) -> Point3f {
+        self.pi.midpoint()
+    }
+
+    /// Sets the shading geometry, flipping either the true or the shading normal so that both lie
+    /// in the same hemisphere.
+    ///
+    /// If `orientation_is_authoritative` is set, the shading normal determines the side of the
+    /// surface, otherwise the true normal does.
+    pub fn set_shading_geometry(
+        &mut self,
+        ns: Normal3f,
+        dpdus: Vector3f,
+        dpdvs: Vector3f,
+        dndus: Normal3f,
+        dndvs: Normal3f,
+        orientation_is_authoritative: bool,
+    ) {
+        self.shading.n = ns;
+        if orientation_is_authoritative {
+            self.n = self.n.face_forward(self.shading.n);
+        } else {
+            self.shading.n = self.shading.n.face_forward(self.n);
+        }
+        self.shading.dpdu = dpdus;
+        self.shading.dpdv = dpdvs;
+        self.shading.dndu = dndus;
+        self.shading.dndv = dndvs;
+    }
+
+    /// The medium that a ray leaving the point in direction `w` travels through.
+    pub fn get_medium(&self, w: &Vector3f) -> Option<Medium> {
+        match &self.medium_interface {
+            Some(mi) if w.dot(self.n) > 0.0 => mi.outside.clone(),
+            Some(mi) => mi.inside.clone(),
+            None => self.medium.clone(),
+        }
+    }
+
+    /// The point offset past its error bounds, to the side of the surface that `w` points to.
+    pub fn offset_ray_origin(&self, w: &Vector3f) -> Point3f {
+        rays::offset_ray_origin(&self.pi, &self.n, w)
+    }
+
+    /// Spawns a ray leaving the surface in direction `d`.
+    pub fn spawn_ray(&self, d: &Vector3f) -> RayDifferential {
+        let mut ray = rays::spawn_ray(&self.pi, &self.n, self.time, *d);
+        ray.medium = Box::new(self.get_medium(d));
+        ray.into()
+    }
+
+    /// Spawns a ray towards `p`, which it reaches at `t = 1`.
+    pub fn spawn_ray_to(&self, p: Point3f) -> Ray {
+        let mut ray = rays::spawn_ray_to(&self.pi, &self.n, self.time, p);
+        ray.medium = Box::new(self.get_medium(&ray.d));
+        ray
+    }
+
+    /// Spawns a ray towards a point on another surface, which it reaches at `t = 1`.
+    pub fn spawn_ray_to_interaction(&self, other: &SurfaceInteraction) -> Ray {
+        let mut ray = rays::spawn_ray_between(&self.pi, &self.n, self.time, &other.pi, &other.n);
+        ray.medium = Box::new(self.get_medium(&ray.d));
+        ray
+    }
+}
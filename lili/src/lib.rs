@@ -1,24 +1,47 @@
+pub mod color;
+
 pub mod cpu;
 
+pub mod error;
+
+pub mod film;
+
+pub mod filter;
+
+pub mod image;
+
+pub mod interaction;
+
 pub mod math;
 type Float = math::Float;
 
 pub mod scene;
 
-use std::ffi::OsString;
+use std::{ffi::OsString, time::Duration};
 
 use crate::scene::BasicScene;
 
 pub mod media;
 
+pub mod memory;
+
+pub mod progress;
+
 pub mod rays;
 
+pub mod sampler;
+
+pub mod spectrum;
+
 #[derive(Debug, Default)]
 pub struct Options {
     pub seed: u32,
 
     pub quiet: bool,
 
+    /// Reports progress as JSON lines rather than progress bars.
+    pub progress_json: bool,
+
     pub disable_pixel_jitter: bool,
 
     pub disable_wavelength_jitter: bool,
@@ -31,8 +54,23 @@ pub struct Options {
 
     pub wavefront: bool,
 
+    /// The number of threads to render with, or 0 for one per core.
+    pub n_threads: usize,
+
     pub rendering_space: RenderingCoordinateSystem,
 
+    /// Overrides the file that the film writes the image to.
+    pub image_file: Option<String>,
+
+    /// The shortest time between writes of the partial image and checkpoint while rendering.
+    pub checkpoint_interval: Duration,
+
+    /// The file that checkpoints are written to, so that the render can be resumed.
+    pub checkpoint_file: Option<String>,
+
+    /// A checkpoint to resume the render from.
+    pub resume_file: Option<String>,
+
     pub scenes: Vec<OsString>,
 }
 
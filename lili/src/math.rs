@@ -3,16 +3,26 @@ use std::ops::{Add, Mul, Neg};
 
 pub mod sampling;
 
+pub mod hash;
+
+pub mod rng;
+
+pub mod lowdiscrepancy;
+
+pub mod sobolmatrices;
+
+pub mod pmj02tables;
+
 mod float;
 pub use float::*;
 
 mod primes;
 use num_traits::MulAdd;
-pub use primes::next_prime;
+pub use primes::{next_prime, PRIMES, PRIME_TABLE_SIZE};
 
 pub mod tuples;
 
-mod num_traits;
+pub mod num_traits;
 
 pub mod length;
 
@@ -28,6 +38,14 @@ pub mod dot;
 
 pub mod face_forward;
 
+pub mod matrix;
+
+pub mod bounds;
+
+pub mod interval;
+
+pub mod transform;
+
 pub fn sqr<T>(v: T) -> T
 where
     T: Mul<Output = T> + Copy,
@@ -59,3 +77,58 @@ where
     let error = (-c).mul_add(d, cd);
     difference_of_products + error
 }
+
+/// The error function, with an absolute error of less than 1.2e-7.
+#[allow(clippy::excessive_precision)]
+pub fn erf(x: Float) -> Float {
+    // Numerical Recipes' Chebyshev fit of the complementary error function
+    let z = x.abs() as f64;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let p = fast_polynomial::poly_array(
+        t,
+        &[
+            -1.26551223,
+            1.00002368,
+            0.37409196,
+            0.09678418,
+            -0.18628806,
+            0.27886807,
+            -1.13520398,
+            1.48851587,
+            -0.82215223,
+            0.17087277,
+        ],
+    );
+    let erfc = t * (-z * z + p).exp();
+    (1.0 - erfc).copysign(x as f64) as Float
+}
+
+/// The normal distribution with mean `mu` and standard deviation `sigma`.
+pub fn gaussian(x: Float, mu: Float, sigma: Float) -> Float {
+    1.0 / (2.0 * Float::PI * sigma * sigma).sqrt() * (-sqr(x - mu) / (2.0 * sigma * sigma)).exp()
+}
+
+/// The integral of [`gaussian`] between `x0` and `x1`.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::gaussian_integral;
+///
+/// assert!((gaussian_integral(-1.0, 1.0, 0.0, 1.0) - 0.6827).abs() < 1e-4);
+/// assert!((gaussian_integral(-10.0, 10.0, 3.0, 0.5) - 1.0).abs() < 1e-6);
+/// ```
+pub fn gaussian_integral(x0: Float, x1: Float, mu: Float, sigma: Float) -> Float {
+    let sigma_root2 = sigma * std::f64::consts::SQRT_2 as Float;
+    0.5 * (erf((mu - x0) / sigma_root2) - erf((mu - x1) / sigma_root2))
+}
+
+/// The normalized sinc function `sin(πx) / (πx)`, windowed by a wider sinc function with `tau`
+/// lobes and cut off at `radius`.
+pub fn windowed_sinc(x: Float, radius: Float, tau: Float) -> Float {
+    if x.abs() > radius {
+        return 0.0;
+    }
+    let sinc = |x: Float| (Float::PI * x).sin_x_over_x();
+    sinc(x) * sinc(x / tau)
+}
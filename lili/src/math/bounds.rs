@@ -0,0 +1,537 @@
+//! Axis-aligned bounding boxes.
+use std::ops::Index;
+
+use super::{
+    gamma,
+    length::Length,
+    matrix::SquareMatrix,
+    points::{Point2, Point2f, Point2i, Point3, Point3f},
+    transform::Transformable,
+    tuples::{Tuple, TupleElement},
+    vectors::{Vector2, Vector2f, Vector3, Vector3f},
+    Float, FloatExt,
+};
+
+/// A 2-dimensional axis-aligned box, given by its minimum and maximum corners.
+#[derive(Clone, Copy)]
+pub struct Bounds2<T> {
+    pub p_min: Point2<T>,
+    pub p_max: Point2<T>,
+}
+
+/// A 3-dimensional axis-aligned box, given by its minimum and maximum corners.
+#[derive(Clone, Copy)]
+pub struct Bounds3<T> {
+    pub p_min: Point3<T>,
+    pub p_max: Point3<T>,
+}
+
+// Type aliases
+
+pub type Bounds2f = Bounds2<Float>;
+pub type Bounds2i = Bounds2<i32>;
+pub type Bounds3f = Bounds3<Float>;
+pub type Bounds3i = Bounds3<i32>;
+
+impl<T> Default for Bounds2<T>
+where
+    T: TupleElement,
+{
+    /// Returns an empty box, which any union with a point or box replaces.
+    fn default() -> Self {
+        Self {
+            p_min: Point2 {
+                x: T::MAX,
+                y: T::MAX,
+            },
+            p_max: Point2 {
+                x: T::MIN,
+                y: T::MIN,
+            },
+        }
+    }
+}
+
+impl<T> Default for Bounds3<T>
+where
+    T: TupleElement,
+{
+    /// Returns an empty box, which any union with a point or box replaces.
+    fn default() -> Self {
+        Self {
+            p_min: Point3 {
+                x: T::MAX,
+                y: T::MAX,
+                z: T::MAX,
+            },
+            p_max: Point3 {
+                x: T::MIN,
+                y: T::MIN,
+                z: T::MIN,
+            },
+        }
+    }
+}
+
+impl<T> Bounds2<T>
+where
+    T: TupleElement,
+{
+    /// Creates the box with `p1` and `p2` at opposite corners.
+    pub fn new(p1: Point2<T>, p2: Point2<T>) -> Self {
+        Self {
+            p_min: p1.min(p2),
+            p_max: p1.max(p2),
+        }
+    }
+
+    /// Creates a box that contains only `p`.
+    pub fn from_point(p: Point2<T>) -> Self {
+        Self { p_min: p, p_max: p }
+    }
+
+    /// Returns one of the four corners; bit 0 of `corner` selects `x` and bit 1 selects `y`.
+    pub fn corner(&self, corner: usize) -> Point2<T> {
+        Point2::new(self[corner & 1].x, self[(corner & 2) >> 1].y)
+    }
+
+    /// The vector from the minimum to the maximum corner.
+    pub fn diagonal(&self) -> Vector2<T> {
+        Vector2::new(self.p_max.x - self.p_min.x, self.p_max.y - self.p_min.y)
+    }
+
+    pub fn area(&self) -> T {
+        self.diagonal().hprod()
+    }
+
+    /// Returns the index of the axis along which the box is widest.
+    pub fn max_dimension(&self) -> usize {
+        self.diagonal().max_component_index()
+    }
+
+    /// Returns `true` if the box encloses no area.
+    pub fn is_empty(&self) -> bool {
+        self.p_min.x >= self.p_max.x || self.p_min.y >= self.p_max.y
+    }
+
+    /// Returns `true` if the minimum corner is beyond the maximum corner along some axis.
+    pub fn is_degenerate(&self) -> bool {
+        self.p_min.x > self.p_max.x || self.p_min.y > self.p_max.y
+    }
+
+    pub fn union_point(&self, p: Point2<T>) -> Self {
+        Self {
+            p_min: self.p_min.min(p),
+            p_max: self.p_max.max(p),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            p_min: self.p_min.min(other.p_min),
+            p_max: self.p_max.max(other.p_max),
+        }
+    }
+
+    /// Returns the overlap of the two boxes, which is degenerate if they do not overlap.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            p_min: self.p_min.max(other.p_min),
+            p_max: self.p_max.min(other.p_max),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let x = self.p_max.x >= other.p_min.x && self.p_min.x <= other.p_max.x;
+        let y = self.p_max.y >= other.p_min.y && self.p_min.y <= other.p_max.y;
+        x && y
+    }
+
+    pub fn inside(&self, p: Point2<T>) -> bool {
+        p.x >= self.p_min.x && p.x <= self.p_max.x && p.y >= self.p_min.y && p.y <= self.p_max.y
+    }
+
+    /// Like [`Bounds2::inside`], but points on the upper boundary are not considered inside.
+    pub fn inside_exclusive(&self, p: Point2<T>) -> bool {
+        p.x >= self.p_min.x && p.x < self.p_max.x && p.y >= self.p_min.y && p.y < self.p_max.y
+    }
+
+    /// Grows the box by `delta` in every direction.
+    pub fn expand(&self, delta: T) -> Self {
+        Self {
+            p_min: Point2::new(self.p_min.x - delta, self.p_min.y - delta),
+            p_max: Point2::new(self.p_max.x + delta, self.p_max.y + delta),
+        }
+    }
+}
+
+impl<T> Bounds3<T>
+where
+    T: TupleElement,
+{
+    /// Creates the box with `p1` and `p2` at opposite corners.
+    pub fn new(p1: Point3<T>, p2: Point3<T>) -> Self {
+        Self {
+            p_min: p1.min(p2),
+            p_max: p1.max(p2),
+        }
+    }
+
+    /// Creates a box that contains only `p`.
+    pub fn from_point(p: Point3<T>) -> Self {
+        Self { p_min: p, p_max: p }
+    }
+
+    /// Returns one of the eight corners; bits 0, 1 and 2 of `corner` select `x`, `y` and `z`.
+    pub fn corner(&self, corner: usize) -> Point3<T> {
+        Point3::new(
+            self[corner & 1].x,
+            self[(corner & 2) >> 1].y,
+            self[(corner & 4) >> 2].z,
+        )
+    }
+
+    /// The vector from the minimum to the maximum corner.
+    pub fn diagonal(&self) -> Vector3<T> {
+        Vector3::new(
+            self.p_max.x - self.p_min.x,
+            self.p_max.y - self.p_min.y,
+            self.p_max.z - self.p_min.z,
+        )
+    }
+
+    pub fn surface_area(&self) -> T {
+        let d = self.diagonal();
+        let half_area = d.x * d.y + d.x * d.z + d.y * d.z;
+        half_area + half_area
+    }
+
+    pub fn volume(&self) -> T {
+        self.diagonal().hprod()
+    }
+
+    /// Returns the index of the axis along which the box is widest.
+    pub fn max_dimension(&self) -> usize {
+        self.diagonal().max_component_index()
+    }
+
+    /// Returns `true` if the box encloses no volume.
+    pub fn is_empty(&self) -> bool {
+        self.p_min.x >= self.p_max.x || self.p_min.y >= self.p_max.y || self.p_min.z >= self.p_max.z
+    }
+
+    /// Returns `true` if the minimum corner is beyond the maximum corner along some axis.
+    pub fn is_degenerate(&self) -> bool {
+        self.p_min.x > self.p_max.x || self.p_min.y > self.p_max.y || self.p_min.z > self.p_max.z
+    }
+
+    pub fn union_point(&self, p: Point3<T>) -> Self {
+        Self {
+            p_min: self.p_min.min(p),
+            p_max: self.p_max.max(p),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            p_min: self.p_min.min(other.p_min),
+            p_max: self.p_max.max(other.p_max),
+        }
+    }
+
+    /// Returns the overlap of the two boxes, which is degenerate if they do not overlap.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            p_min: self.p_min.max(other.p_min),
+            p_max: self.p_max.min(other.p_max),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let x = self.p_max.x >= other.p_min.x && self.p_min.x <= other.p_max.x;
+        let y = self.p_max.y >= other.p_min.y && self.p_min.y <= other.p_max.y;
+        let z = self.p_max.z >= other.p_min.z && self.p_min.z <= other.p_max.z;
+        x && y && z
+    }
+
+    pub fn inside(&self, p: Point3<T>) -> bool {
+        p.x >= self.p_min.x
+            && p.x <= self.p_max.x
+            && p.y >= self.p_min.y
+            && p.y <= self.p_max.y
+            && p.z >= self.p_min.z
+            && p.z <= self.p_max.z
+    }
+
+    /// Like [`Bounds3::inside`], but points on the upper boundary are not considered inside.
+    pub fn inside_exclusive(&self, p: Point3<T>) -> bool {
+        p.x >= self.p_min.x
+            && p.x < self.p_max.x
+            && p.y >= self.p_min.y
+            && p.y < self.p_max.y
+            && p.z >= self.p_min.z
+            && p.z < self.p_max.z
+    }
+
+    /// Grows the box by `delta` in every direction.
+    pub fn expand(&self, delta: T) -> Self {
+        Self {
+            p_min: Point3::new(
+                self.p_min.x - delta,
+                self.p_min.y - delta,
+                self.p_min.z - delta,
+            ),
+            p_max: Point3::new(
+                self.p_max.x + delta,
+                self.p_max.y + delta,
+                self.p_max.z + delta,
+            ),
+        }
+    }
+}
+
+impl Bounds2i {
+    /// Returns the number of integer points inside the box, excluding its upper boundary.
+    ///
+    /// Unlike [`Bounds2::area`], this does not overflow for boxes of more than `i32::MAX`
+    /// points.
+    pub fn point_count(&self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            let d = self.diagonal();
+            d.x as usize * d.y as usize
+        }
+    }
+}
+
+impl Bounds2f {
+    /// Returns the point at the relative position `t` in the box, where `(0, 0)` is the minimum
+    /// corner and `(1, 1)` the maximum one.
+    pub fn lerp(&self, t: Point2f) -> Point2f {
+        Point2f::new(
+            t.x.lerp(self.p_min.x, self.p_max.x),
+            t.y.lerp(self.p_min.y, self.p_max.y),
+        )
+    }
+
+    /// Returns the position of `p` relative to the box; the inverse of [`Bounds2f::lerp`].
+    pub fn offset(&self, p: Point2f) -> Vector2f {
+        let mut o = p - self.p_min;
+        if self.p_max.x > self.p_min.x {
+            o.x /= self.p_max.x - self.p_min.x;
+        }
+        if self.p_max.y > self.p_min.y {
+            o.y /= self.p_max.y - self.p_min.y;
+        }
+        o
+    }
+}
+
+impl Bounds3f {
+    /// Returns the point at the relative position `t` in the box, where `(0, 0, 0)` is the
+    /// minimum corner and `(1, 1, 1)` the maximum one.
+    pub fn lerp(&self, t: Point3f) -> Point3f {
+        Point3f::new(
+            t.x.lerp(self.p_min.x, self.p_max.x),
+            t.y.lerp(self.p_min.y, self.p_max.y),
+            t.z.lerp(self.p_min.z, self.p_max.z),
+        )
+    }
+
+    /// Returns the position of `p` relative to the box; the inverse of [`Bounds3f::lerp`].
+    pub fn offset(&self, p: Point3f) -> Vector3f {
+        let mut o = p - self.p_min;
+        for axis in 0..3 {
+            if self.p_max[axis] > self.p_min[axis] {
+                o[axis] /= self.p_max[axis] - self.p_min[axis];
+            }
+        }
+        o
+    }
+
+    /// Returns the center and radius of a sphere that contains the box.
+    pub fn bounding_sphere(&self) -> (Point3f, Float) {
+        let center = (self.p_min + self.p_max) / 2.0;
+        let radius = if self.inside(center) {
+            (self.p_max - center).length()
+        } else {
+            0.0
+        };
+        (center, radius)
+    }
+
+    /// Finds the parametric range along the ray `o + t * d` that lies inside the box, limited to
+    /// `0..t_max`.
+    ///
+    /// The far end of each slab is pushed outwards by a bound on the rounding error, so that rays
+    /// which pass through the box are never reported as missing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::math::{bounds::Bounds3f, points::Point3f, vectors::Vector3f, Float};
+    ///
+    /// let b = Bounds3f::new(Point3f::new(-1.0, -1.0, -1.0), Point3f::new(1.0, 1.0, 1.0));
+    /// let o = Point3f::new(-3.0, 0.0, 0.0);
+    ///
+    /// let (t0, t1) = b.intersect_p(o, Vector3f::new(1.0, 0.0, 0.0), Float::INFINITY).unwrap();
+    /// assert_eq!(t0, 2.0);
+    /// assert!(t1 >= 4.0);
+    ///
+    /// assert!(b.intersect_p(o, Vector3f::new(0.0, 1.0, 0.0), Float::INFINITY).is_none());
+    /// ```
+    pub fn intersect_p(&self, o: Point3f, d: Vector3f, t_max: Float) -> Option<(Float, Float)> {
+        let mut t0 = 0.0;
+        let mut t1 = t_max;
+        for axis in 0..3 {
+            let inv_ray_dir = 1.0 / d[axis];
+            let mut t_near = (self.p_min[axis] - o[axis]) * inv_ray_dir;
+            let mut t_far = (self.p_max[axis] - o[axis]) * inv_ray_dir;
+            if t_near > t_far {
+                std::mem::swap(&mut t_near, &mut t_far);
+            }
+            t_far *= 1.0 + 2.0 * gamma(3);
+
+            // Written so that NaN values, from rays lying in a slab's plane, are ignored
+            t0 = if t_near > t0 { t_near } else { t0 };
+            t1 = if t_far < t1 { t_far } else { t1 };
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+
+    /// Like [`Bounds3f::intersect_p`], but faster when testing one ray against many boxes.
+    ///
+    /// `inv_dir` holds the reciprocals of the ray direction's components and `dir_is_neg` is 1
+    /// for each component that is negative and 0 otherwise.
+    pub fn intersect_p_inv_dir(
+        &self,
+        o: Point3f,
+        ray_t_max: Float,
+        inv_dir: Vector3f,
+        dir_is_neg: [usize; 3],
+    ) -> bool {
+        let error_scale = 1.0 + 2.0 * gamma(3);
+
+        let mut t_min = (self[dir_is_neg[0]].x - o.x) * inv_dir.x;
+        let mut t_max = (self[1 - dir_is_neg[0]].x - o.x) * inv_dir.x * error_scale;
+        let ty_min = (self[dir_is_neg[1]].y - o.y) * inv_dir.y;
+        let ty_max = (self[1 - dir_is_neg[1]].y - o.y) * inv_dir.y * error_scale;
+        if t_min > ty_max || ty_min > t_max {
+            return false;
+        }
+        if ty_min > t_min {
+            t_min = ty_min;
+        }
+        if ty_max < t_max {
+            t_max = ty_max;
+        }
+
+        let tz_min = (self[dir_is_neg[2]].z - o.z) * inv_dir.z;
+        let tz_max = (self[1 - dir_is_neg[2]].z - o.z) * inv_dir.z * error_scale;
+        if t_min > tz_max || tz_min > t_max {
+            return false;
+        }
+        if tz_min > t_min {
+            t_min = tz_min;
+        }
+        if tz_max < t_max {
+            t_max = tz_max;
+        }
+
+        t_min < ray_t_max && t_max > 0.0
+    }
+}
+
+impl Transformable for Bounds3f {
+    /// Returns the box that bounds the transformed corners of the box.
+    fn transformed(&self, m: &SquareMatrix<4>, m_inv: &SquareMatrix<4>) -> Self {
+        (0..8).fold(Bounds3f::default(), |bounds, corner| {
+            bounds.union_point(self.corner(corner).transformed(m, m_inv))
+        })
+    }
+}
+
+impl<T> Index<usize> for Bounds2<T> {
+    type Output = Point2<T>;
+
+    /// Returns the minimum corner for 0 and the maximum corner for 1.
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.p_min,
+            _ => &self.p_max,
+        }
+    }
+}
+
+impl<T> Index<usize> for Bounds3<T> {
+    type Output = Point3<T>;
+
+    /// Returns the minimum corner for 0 and the maximum corner for 1.
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.p_min,
+            _ => &self.p_max,
+        }
+    }
+}
+
+/// Iterates over the integer points inside a [`Bounds2i`], excluding its upper boundary, in
+/// scanline order.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::{bounds::Bounds2i, points::Point2i};
+///
+/// let bounds = Bounds2i::new(Point2i::new(1, 1), Point2i::new(3, 2));
+/// let pixels = bounds.into_iter().map(|p| (p.x, p.y)).collect::<Vec<_>>();
+/// assert_eq!(pixels, [(1, 1), (2, 1)]);
+/// ```
+#[derive(Clone)]
+pub struct Bounds2iIterator {
+    bounds: Bounds2i,
+    p: Point2i,
+}
+
+impl Iterator for Bounds2iIterator {
+    type Item = Point2i;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bounds.is_empty() || self.p.y >= self.bounds.p_max.y {
+            return None;
+        }
+
+        let p = self.p;
+        self.p.x += 1;
+        if self.p.x == self.bounds.p_max.x {
+            self.p.x = self.bounds.p_min.x;
+            self.p.y += 1;
+        }
+        Some(p)
+    }
+}
+
+impl IntoIterator for Bounds2i {
+    type Item = Point2i;
+    type IntoIter = Bounds2iIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Bounds2iIterator {
+            bounds: self,
+            p: self.p_min,
+        }
+    }
+}
+
+impl IntoIterator for &Bounds2i {
+    type Item = Point2i;
+    type IntoIter = Bounds2iIterator;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (*self).into_iter()
+    }
+}
@@ -1,12 +1,18 @@
 //! Floating-point number utilities.
 
-/// The type used for floating-point numbers. It is either `f32` or `f64` depending on the configuration.
-#[cfg(not(f64))]
+/// The type used for floating-point numbers. It is `f32`, or `f64` when the `f64` feature is enabled.
+#[cfg(not(feature = "f64"))]
 pub type Float = f32;
 
-#[cfg(f64)]
+#[cfg(feature = "f64")]
 pub type Float = f64;
 
+#[cfg(not(feature = "f64"))]
+use std::f32::consts;
+
+#[cfg(feature = "f64")]
+use std::f64::consts;
+
 /// Extension trait for floating-point numbers.
 pub trait FloatExt {
     /// Calculates the square root of the number, ensuring that the result is non-negative.
@@ -41,10 +47,10 @@ pub trait FloatExt {
     /// # Examples
     ///
     /// ```
-    /// use math::float::lerp;
+    /// use lili::math::{Float, FloatExt};
     ///
-    /// let result = lerp(0.5, 0.0, 1.0);
-    /// assert_eq!(result, 0.5);
+    /// let t: Float = 0.25;
+    /// assert_eq!(t.lerp(2.0, 4.0), 2.5);
     /// ```
     fn lerp(self, a: Self, b: Self) -> Self;
 
@@ -57,18 +63,15 @@ pub trait FloatExt {
     /// # Examples
     ///
     /// ```
-    /// use math::float::smooth_step;
+    /// use lili::math::{Float, FloatExt};
     ///
-    /// assert_eq!(smooth_step(0.5, 0.0, 1.0), 0.5);
-    /// assert_eq!(smooth_step(-2.0, 0.0, 1.0), 0.0);
-    /// assert_eq!(smooth_step(2.0, 0.0, 1.0), 1.0);
+    /// assert_eq!((0.5 as Float).smooth_step(0.0, 1.0), 0.5);
+    /// assert_eq!((-2.0 as Float).smooth_step(0.0, 1.0), 0.0);
+    /// assert_eq!((2.0 as Float).smooth_step(0.0, 1.0), 1.0);
     /// ```
     fn smooth_step(self, a: Self, b: Self) -> Self;
 
     /// The largest representable value less than 1.0.
-    ///
-    /// f32::from_bits in const context is not yet stable: <https://github.com/rust-lang/rust/issues/72447>
-    /// However, transmute is stable in const context, so we use that instead.
     const ONE_MINUS_EPSILON: Self;
 
     /// The mathematical constant π (pi).
@@ -181,22 +184,23 @@ impl FloatExt for Float {
         t * t * (3.0 - 2.0 * t)
     }
 
-    #[cfg(not(f64))]
-    const ONE_MINUS_EPSILON: Float = unsafe { std::mem::transmute(0x3f7fffffu32) };
-
-    #[cfg(f64)]
-    const ONE_MINUS_EPSILON: Float = unsafe { std::mem::transmute(0x3fefffffffffffffu64) };
-
-    #[allow(clippy::excessive_precision)]
-    const PI: Float = 3.14159265358979323846;
-    #[allow(clippy::excessive_precision)]
-    const INV_PI: Float = 0.31830988618379067154;
-    #[allow(clippy::excessive_precision)]
-    const INV_2PI: Float = 0.15915494309189533577;
-    #[allow(clippy::excessive_precision)]
-    const INV_4PI: Float = 0.07957747154594766788;
-    #[allow(clippy::excessive_precision)]
-    const PI_OVER_2: Float = 1.57079632679489661923;
-    #[allow(clippy::excessive_precision)]
-    const PI_OVER_4: Float = 0.78539816339744830961;
+    // The spacing of representable values just below 1 is half of that just above it
+    const ONE_MINUS_EPSILON: Float = 1.0 - Float::EPSILON / 2.0;
+
+    const PI: Float = consts::PI;
+    const INV_PI: Float = consts::FRAC_1_PI;
+    const INV_2PI: Float = consts::FRAC_1_PI / 2.0;
+    const INV_4PI: Float = consts::FRAC_1_PI / 4.0;
+    const PI_OVER_2: Float = consts::FRAC_PI_2;
+    const PI_OVER_4: Float = consts::FRAC_PI_4;
+}
+
+/// Half the distance between 1 and the next representable number, which bounds the relative
+/// error of a single rounded operation.
+pub const MACHINE_EPSILON: Float = Float::EPSILON * 0.5;
+
+/// A conservative bound on the relative error accumulated by `n` rounded operations.
+#[inline]
+pub fn gamma(n: i32) -> Float {
+    (n as Float * MACHINE_EPSILON) / (1.0 - n as Float * MACHINE_EPSILON)
 }
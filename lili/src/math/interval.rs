@@ -0,0 +1,324 @@
+//! Interval arithmetic for tracking floating-point rounding error.
+use auto_ops::{impl_op_ex, impl_op_ex_commutative};
+
+use super::{
+    gamma, matrix::SquareMatrix, points::Point3f, transform::Transformable, vectors::Vector3f,
+    Float, FloatExt,
+};
+
+/// A range of values that is guaranteed to contain the exact result of a computation.
+///
+/// Every arithmetic operation rounds the lower bound down and the upper bound up, so the interval
+/// stays conservative no matter how the floating-point results were rounded.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::interval::Interval;
+///
+/// let a = Interval::new(0.1);
+/// let sum = a + a + a;
+/// assert!(sum.lower_bound() <= 0.3 && sum.upper_bound() >= 0.3);
+/// assert!(sum.width() > 0.0);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Interval {
+    low: Float,
+    high: Float,
+}
+
+impl Interval {
+    /// Creates an interval that holds exactly `v`.
+    pub fn new(v: Float) -> Self {
+        Self { low: v, high: v }
+    }
+
+    /// Creates the interval between `a` and `b`, in either order.
+    pub fn from_bounds(a: Float, b: Float) -> Self {
+        Self {
+            low: a.min(b),
+            high: a.max(b),
+        }
+    }
+
+    /// Creates the interval `v ± err`.
+    pub fn from_value_and_error(v: Float, err: Float) -> Self {
+        if err == 0.0 {
+            return Self::new(v);
+        }
+        Self {
+            low: sub_round_down(v, err),
+            high: add_round_up(v, err),
+        }
+    }
+
+    pub fn lower_bound(&self) -> Float {
+        self.low
+    }
+
+    pub fn upper_bound(&self) -> Float {
+        self.high
+    }
+
+    pub fn midpoint(&self) -> Float {
+        (self.low + self.high) / 2.0
+    }
+
+    pub fn width(&self) -> Float {
+        self.high - self.low
+    }
+
+    /// Returns `true` if the interval holds a single value.
+    pub fn is_exact(&self) -> bool {
+        self.low == self.high
+    }
+
+    pub fn contains(&self, v: Float) -> bool {
+        v >= self.low && v <= self.high
+    }
+
+    pub fn sqr(&self) -> Self {
+        let (mut a_low, mut a_high) = (self.low.abs(), self.high.abs());
+        if a_low > a_high {
+            std::mem::swap(&mut a_low, &mut a_high);
+        }
+        if self.contains(0.0) {
+            return Self {
+                low: 0.0,
+                high: mul_round_up(a_high, a_high),
+            };
+        }
+        Self {
+            low: mul_round_down(a_low, a_low),
+            high: mul_round_up(a_high, a_high),
+        }
+    }
+
+    pub fn sqrt(&self) -> Self {
+        Self {
+            low: self.low.sqrt().next_float_down().max(0.0),
+            high: self.high.sqrt().next_float_up(),
+        }
+    }
+}
+
+impl From<Float> for Interval {
+    fn from(v: Float) -> Self {
+        Self::new(v)
+    }
+}
+
+impl From<Interval> for Float {
+    fn from(i: Interval) -> Self {
+        i.midpoint()
+    }
+}
+
+#[inline]
+fn add_round_up(a: Float, b: Float) -> Float {
+    (a + b).next_float_up()
+}
+
+#[inline]
+fn add_round_down(a: Float, b: Float) -> Float {
+    (a + b).next_float_down()
+}
+
+#[inline]
+fn sub_round_up(a: Float, b: Float) -> Float {
+    add_round_up(a, -b)
+}
+
+#[inline]
+fn sub_round_down(a: Float, b: Float) -> Float {
+    add_round_down(a, -b)
+}
+
+#[inline]
+fn mul_round_up(a: Float, b: Float) -> Float {
+    (a * b).next_float_up()
+}
+
+#[inline]
+fn mul_round_down(a: Float, b: Float) -> Float {
+    (a * b).next_float_down()
+}
+
+#[inline]
+fn div_round_up(a: Float, b: Float) -> Float {
+    (a / b).next_float_up()
+}
+
+#[inline]
+fn div_round_down(a: Float, b: Float) -> Float {
+    (a / b).next_float_down()
+}
+
+fn min4(v: [Float; 4]) -> Float {
+    v[0].min(v[1]).min(v[2].min(v[3]))
+}
+
+fn max4(v: [Float; 4]) -> Float {
+    v[0].max(v[1]).max(v[2].max(v[3]))
+}
+
+// Arithmetic operations
+
+impl_op_ex!(+|a: &Interval, b: &Interval| -> Interval {
+    Interval { low: add_round_down(a.low, b.low), high: add_round_up(a.high, b.high) }
+});
+
+impl_op_ex!(-|a: &Interval, b: &Interval| -> Interval {
+    Interval {
+        low: sub_round_down(a.low, b.high),
+        high: sub_round_up(a.high, b.low),
+    }
+});
+
+impl_op_ex!(*|a: &Interval, b: &Interval| -> Interval {
+    let low = [
+        mul_round_down(a.low, b.low),
+        mul_round_down(a.high, b.low),
+        mul_round_down(a.low, b.high),
+        mul_round_down(a.high, b.high),
+    ];
+    let high = [
+        mul_round_up(a.low, b.low),
+        mul_round_up(a.high, b.low),
+        mul_round_up(a.low, b.high),
+        mul_round_up(a.high, b.high),
+    ];
+    Interval {
+        low: min4(low),
+        high: max4(high),
+    }
+});
+
+impl_op_ex!(/|a: &Interval, b: &Interval| -> Interval {
+    // The quotient is unbounded if the divisor may be zero
+    if b.contains(0.0) {
+        return Interval { low: Float::NEG_INFINITY, high: Float::INFINITY };
+    }
+    let low = [
+        div_round_down(a.low, b.low),
+        div_round_down(a.high, b.low),
+        div_round_down(a.low, b.high),
+        div_round_down(a.high, b.high),
+    ];
+    let high = [
+        div_round_up(a.low, b.low),
+        div_round_up(a.high, b.low),
+        div_round_up(a.low, b.high),
+        div_round_up(a.high, b.high),
+    ];
+    Interval { low: min4(low), high: max4(high) }
+});
+
+impl_op_ex_commutative!(+|a: &Interval, b: Float| -> Interval { a + Interval::new(b) });
+impl_op_ex_commutative!(*|a: &Interval, b: Float| -> Interval { a * Interval::new(b) });
+impl_op_ex!(-|a: &Interval, b: Float| -> Interval { a - Interval::new(b) });
+impl_op_ex!(-|a: Float, b: &Interval| -> Interval { Interval::new(a) - b });
+impl_op_ex!(/|a: &Interval, b: Float| -> Interval { a / Interval::new(b) });
+impl_op_ex!(/|a: Float, b: &Interval| -> Interval { Interval::new(a) / b });
+
+impl_op_ex!(-|a: &Interval| -> Interval {
+    Interval {
+        low: -a.high,
+        high: -a.low,
+    }
+});
+
+/// A point whose coordinates are intervals, such as a ray intersection point along with the
+/// rounding error of its computation.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Point3fi {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Point3fi {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Creates the point `p` with an absolute error of at most `e` in each coordinate.
+    pub fn from_point_and_error(p: Point3f, e: Vector3f) -> Self {
+        Self {
+            x: Interval::from_value_and_error(p.x, e.x),
+            y: Interval::from_value_and_error(p.y, e.y),
+            z: Interval::from_value_and_error(p.z, e.z),
+        }
+    }
+
+    /// The half-widths of the coordinate intervals.
+    pub fn error(&self) -> Vector3f {
+        Vector3f::new(
+            self.x.width() / 2.0,
+            self.y.width() / 2.0,
+            self.z.width() / 2.0,
+        )
+    }
+
+    /// Returns `true` if every coordinate is known exactly.
+    pub fn is_exact(&self) -> bool {
+        self.x.is_exact() && self.y.is_exact() && self.z.is_exact()
+    }
+
+    /// The point at the middle of the coordinate intervals.
+    pub fn midpoint(&self) -> Point3f {
+        Point3f::new(self.x.midpoint(), self.y.midpoint(), self.z.midpoint())
+    }
+}
+
+impl From<Point3f> for Point3fi {
+    fn from(p: Point3f) -> Self {
+        Self::new(p.x.into(), p.y.into(), p.z.into())
+    }
+}
+
+impl From<Point3fi> for Point3f {
+    fn from(p: Point3fi) -> Self {
+        p.midpoint()
+    }
+}
+
+impl_op_ex!(+|a: &Point3fi, b: &Vector3f| -> Point3fi { Point3fi::new(a.x + b.x, a.y + b.y, a.z + b.z) });
+impl_op_ex!(-|a: &Point3fi, b: &Vector3f| -> Point3fi {
+    Point3fi::new(a.x - b.x, a.y - b.y, a.z - b.z)
+});
+impl_op_ex!(/|a: &Point3fi, b: Float| -> Point3fi { Point3fi::new(a.x / b, a.y / b, a.z / b) });
+
+impl Transformable for Point3fi {
+    /// Transforms the point, growing its error bounds by the rounding error of the matrix
+    /// multiplication.
+    fn transformed(&self, m: &SquareMatrix<4>, _m_inv: &SquareMatrix<4>) -> Self {
+        let p = self.midpoint();
+        let (x, y, z) = (p.x, p.y, p.z);
+        let row = |i: usize| (m[i][0] * x + m[i][1] * y) + (m[i][2] * z + m[i][3]);
+        let (xp, yp, zp, wp) = (row(0), row(1), row(2), row(3));
+
+        // The error of rounding the transformed point, plus that of the original point carried
+        // through the transformation
+        let p_in_error = self.error();
+        let error = |i: usize| {
+            let rounding = gamma(3)
+                * ((m[i][0] * x).abs() + (m[i][1] * y).abs() + (m[i][2] * z).abs() + m[i][3].abs());
+            if self.is_exact() {
+                return rounding;
+            }
+            let propagated = m[i][0].abs() * p_in_error.x
+                + m[i][1].abs() * p_in_error.y
+                + m[i][2].abs() * p_in_error.z;
+            (gamma(3) + 1.0) * propagated + rounding
+        };
+        let p_error = Vector3f::new(error(0), error(1), error(2));
+
+        let result = Point3fi::from_point_and_error(Point3f::new(xp, yp, zp), p_error);
+        if wp == 1.0 {
+            result
+        } else {
+            result / wp
+        }
+    }
+}
@@ -16,7 +16,7 @@ pub trait Length {
     /// # Examples
     ///
     /// ```
-    /// use lili::math::{Length, Vector2f};
+    /// use lili::math::{length::Length, vectors::Vector2f};
     ///
     /// let vector = Vector2f::new(3.0, 4.0);
     /// let length_squared = vector.length_squared();
@@ -29,7 +29,7 @@ pub trait Length {
     /// # Examples
     ///
     /// ```
-    /// use lili::math::{Length, Vector2f};
+    /// use lili::math::{length::Length, vectors::Vector2f};
     ///
     /// let vector = Vector2f::new(3.0, 4.0);
     /// let length = vector.length();
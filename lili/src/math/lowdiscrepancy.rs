@@ -0,0 +1,329 @@
+//! Low-discrepancy sequences, whose points cover their domain more evenly than random points do.
+use super::{
+    hash::{hash, mix_bits, permutation_element},
+    points::Point2i,
+    sobolmatrices::{SOBOL_MATRICES, SOBOL_MATRIX_SIZE},
+    Float, FloatExt, PRIMES,
+};
+
+/// The radical inverse of `a` in the base `PRIMES[base_index]`, which mirrors its digits about
+/// the decimal point.
+///
+/// The radical inverses of 0, 1, 2... in a prime base form a van der Corput sequence, and those
+/// in the first `n` primes together form the points of the `n`-dimensional Halton sequence.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::lowdiscrepancy::radical_inverse;
+///
+/// // 6 is 110 in base 2, and 0.011 is 3/8
+/// assert_eq!(radical_inverse(0, 6), 0.375);
+/// // 5 is 12 in base 3, and 0.21 is 7/9
+/// assert!((radical_inverse(1, 5) - 7.0 / 9.0).abs() < 1e-6);
+/// ```
+pub fn radical_inverse(base_index: usize, mut a: u64) -> Float {
+    let base = PRIMES[base_index] as u64;
+    // Stop before the reversed digits overflow, since later digits are below the precision of
+    // the result anyway
+    let limit = u64::MAX / base - base;
+    let inv_base = 1.0 / base as Float;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits = 0;
+    while a != 0 && reversed_digits < limit {
+        let next = a / base;
+        let digit = a - next * base;
+        reversed_digits = reversed_digits * base + digit;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (reversed_digits as Float * inv_base_m).min(Float::ONE_MINUS_EPSILON)
+}
+
+/// The integer whose `n_digits` lowest digits in `base` are those of `inverse` in reverse order,
+/// which inverts [`radical_inverse`] for values with no more digits.
+pub fn inverse_radical_inverse(mut inverse: u64, base: u64, n_digits: u32) -> u64 {
+    let mut index = 0;
+    for _ in 0..n_digits {
+        let digit = inverse % base;
+        inverse /= base;
+        index = index * base + digit;
+    }
+    index
+}
+
+/// Random permutations of the digits of a base, one for each digit that [`Float`] can resolve.
+#[derive(Debug, Clone)]
+pub struct DigitPermutation {
+    base: u32,
+    permutations: Vec<u16>,
+}
+
+impl DigitPermutation {
+    pub fn new(base: u32, seed: u64) -> Self {
+        assert!(base < 65536, "digits must fit in 16 bits");
+        let n_digits = n_digits(base);
+        let mut permutations = Vec::with_capacity(n_digits * base as usize);
+        for digit_index in 0..n_digits {
+            let digit_seed = hash(&[base as i64, digit_index as i64, seed as i64]);
+            permutations
+                .extend((0..base).map(|d| permutation_element(d, base, digit_seed as u32) as u16));
+        }
+        Self { base, permutations }
+    }
+
+    /// The value that the digit `digit_value` becomes at position `digit_index`.
+    pub fn permute(&self, digit_index: usize, digit_value: u64) -> u64 {
+        self.permutations[digit_index * self.base as usize + digit_value as usize] as u64
+    }
+}
+
+/// Digit permutations for every prime base in [`PRIMES`], as used by
+/// [`scrambled_radical_inverse`].
+pub fn compute_radical_inverse_permutations(seed: u64) -> Vec<DigitPermutation> {
+    PRIMES
+        .iter()
+        .map(|&p| DigitPermutation::new(p as u32, seed))
+        .collect()
+}
+
+/// The radical inverse of `a` in the base `PRIMES[base_index]`, with its digits permuted by
+/// `perm`.
+///
+/// Unlike [`radical_inverse`], the infinitely many zero digits that follow those of `a` are
+/// permuted too, as far as the precision of [`Float`] allows.
+pub fn scrambled_radical_inverse(base_index: usize, a: u64, perm: &DigitPermutation) -> Float {
+    scrambled(base_index, a, |digit_index, digit_value, _| {
+        perm.permute(digit_index, digit_value)
+    })
+}
+
+/// The radical inverse of `a` in the base `PRIMES[base_index]`, Owen scrambled with the seed
+/// `hash`.
+///
+/// Each digit is permuted according to the digits before it, so that the points of the sequence
+/// stay well distributed while being decorrelated from those of other seeds.
+pub fn owen_scrambled_radical_inverse(base_index: usize, a: u64, hash: u32) -> Float {
+    let base = PRIMES[base_index] as u32;
+    scrambled(base_index, a, |_, digit_value, reversed_digits| {
+        let digit_hash = mix_bits(hash as u64 ^ reversed_digits);
+        permutation_element(digit_value as u32, base, digit_hash as u32) as u64
+    })
+}
+
+/// The radical inverse of `a` with each digit replaced by `permute(digit_index, digit_value,
+/// reversed_digits)`, where `reversed_digits` are the digits before it.
+fn scrambled(base_index: usize, mut a: u64, permute: impl Fn(usize, u64, u64) -> u64) -> Float {
+    let base = PRIMES[base_index] as u64;
+    let limit = u64::MAX / base - base;
+    let inv_base = 1.0 / base as Float;
+    let mut inv_base_m: Float = 1.0;
+    let mut reversed_digits = 0;
+    let mut digit_index = 0;
+    while 1.0 - (base - 1) as Float * inv_base_m < 1.0 && reversed_digits < limit {
+        let next = a / base;
+        let digit_value = a - next * base;
+        reversed_digits =
+            reversed_digits * base + permute(digit_index, digit_value, reversed_digits);
+        inv_base_m *= inv_base;
+        digit_index += 1;
+        a = next;
+    }
+    (inv_base_m * reversed_digits as Float).min(Float::ONE_MINUS_EPSILON)
+}
+
+/// The number of digits in `base` that [`Float`] can resolve.
+fn n_digits(base: u32) -> usize {
+    let inv_base = 1.0 / base as Float;
+    let mut inv_base_m: Float = 1.0;
+    let mut n_digits = 0;
+    while 1.0 - (base - 1) as Float * inv_base_m < 1.0 {
+        n_digits += 1;
+        inv_base_m *= inv_base;
+    }
+    n_digits
+}
+
+/// A randomization of the points of the Sobol sequence, applied to the 32 bits of their values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SobolScrambler {
+    None,
+    /// Flips the bits that are set in the seed, which permutes the digits in base 2.
+    BinaryPermute(u32),
+    /// An approximation of Owen scrambling by a hash function, computed for all bits at once.
+    FastOwen(u32),
+    /// Flips each bit according to a hash of the bits before it.
+    Owen(u32),
+}
+
+impl SobolScrambler {
+    pub fn scramble(self, mut v: u32) -> u32 {
+        match self {
+            Self::None => v,
+            Self::BinaryPermute(perm) => v ^ perm,
+            Self::FastOwen(seed) => {
+                // Laine and Karras' hash, which only lets each bit depend on the bits before it
+                // once the bits are reversed
+                v = v.reverse_bits();
+                v ^= v.wrapping_mul(0x3d20adea);
+                v = v.wrapping_add(seed);
+                v = v.wrapping_mul((seed >> 16) | 1);
+                v ^= v.wrapping_mul(0x05526c56);
+                v ^= v.wrapping_mul(0x53a22864);
+                v.reverse_bits()
+            }
+            Self::Owen(seed) => {
+                if seed & 1 != 0 {
+                    v ^= 1 << 31;
+                }
+                for b in 1..32 {
+                    let mask = u32::MAX << (32 - b);
+                    if (mix_bits(((v & mask) ^ seed) as u64) as u32) & (1 << b) != 0 {
+                        v ^= 1 << (31 - b);
+                    }
+                }
+                v
+            }
+        }
+    }
+}
+
+/// Dimension `dimension` of point `a` of the Sobol sequence, randomized by `scrambler`.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::lowdiscrepancy::{sobol_sample, SobolScrambler};
+///
+/// // The first dimension is the van der Corput sequence
+/// assert_eq!(sobol_sample(6, 0, SobolScrambler::None), 0.375);
+///
+/// // Each of the first 2^k points falls in a different interval of size 2^-k, in every
+/// // dimension and however it is scrambled
+/// for dimension in [1, 17, 1000] {
+///     let mut intervals: Vec<u32> = (0..16)
+///         .map(|a| (sobol_sample(a, dimension, SobolScrambler::Owen(42)) * 16.0) as u32)
+///         .collect();
+///     intervals.sort();
+///     assert_eq!(intervals, (0..16).collect::<Vec<_>>());
+/// }
+/// ```
+pub fn sobol_sample(mut a: u64, dimension: usize, scrambler: SobolScrambler) -> Float {
+    let matrix = &SOBOL_MATRICES[dimension];
+    let mut v = 0;
+    let mut i = 0;
+    while a != 0 {
+        if a & 1 != 0 {
+            v ^= matrix[i];
+        }
+        a >>= 1;
+        i += 1;
+    }
+    let v = scrambler.scramble(v);
+    (v as Float * (2.0 as Float).powi(-32)).min(Float::ONE_MINUS_EPSILON)
+}
+
+/// Finds the indices of the points of the Sobol sequence whose first two dimensions, scaled by
+/// `2^log2_scale`, fall in a given pixel.
+///
+/// The points of every block of `4^log2_scale` consecutive indices fall in each pixel once, so
+/// the samples of a pixel are the points with that pixel in each block.
+#[derive(Debug, Clone)]
+pub struct SobolIntervalToIndex {
+    log2_scale: u32,
+    /// The pixel coordinates that each bit of the block index moves the points by.
+    frame_offsets: Vec<u64>,
+    /// The index bits that each bit of the pixel coordinates corresponds to.
+    pixel_inverse: Vec<u64>,
+}
+
+impl SobolIntervalToIndex {
+    pub fn new(log2_scale: u32) -> Self {
+        let m = log2_scale as usize;
+        assert!(
+            2 * m < SOBOL_MATRIX_SIZE,
+            "image is too large for the Sobol sampler"
+        );
+        // The pixel coordinates, as (x << m) | y, that bit j of the index contributes to
+        let pixel_bits = |j: usize| {
+            if m == 0 {
+                return 0;
+            }
+            let [x, y] = [0, 1].map(|d| (SOBOL_MATRICES[d][j] >> (32 - m)) as u64);
+            (x << m) | y
+        };
+        let frame_offsets = (2 * m..SOBOL_MATRIX_SIZE).map(pixel_bits).collect();
+
+        // Invert the map from the lowest 2m bits of the index to pixel coordinates by Gaussian
+        // elimination over GF(2)
+        let mut images: Vec<u64> = (0..2 * m).map(pixel_bits).collect();
+        let mut preimages: Vec<u64> = (0..2 * m).map(|j| 1 << j).collect();
+        for bit in 0..2 * m {
+            let pivot = (bit..2 * m)
+                .find(|&j| (images[j] >> bit) & 1 != 0)
+                .expect("the first two dimensions of the Sobol sequence form a (0, 2)-sequence");
+            images.swap(bit, pivot);
+            preimages.swap(bit, pivot);
+            for j in 0..2 * m {
+                if j != bit && (images[j] >> bit) & 1 != 0 {
+                    images[j] ^= images[bit];
+                    preimages[j] ^= preimages[bit];
+                }
+            }
+        }
+
+        Self {
+            log2_scale,
+            frame_offsets,
+            pixel_inverse: preimages,
+        }
+    }
+
+    /// The index of sample `frame` of the pixel `p`.
+    pub fn index(&self, frame: u64, p: Point2i) -> u64 {
+        let m = self.log2_scale;
+        if m == 0 {
+            return frame;
+        }
+        assert!(
+            frame >> self.frame_offsets.len() == 0,
+            "too many samples for the Sobol sampler"
+        );
+        let mut index = frame << (2 * m);
+
+        // The pixel that the points of the block would fall in with the lowest 2m bits of their
+        // index cleared, which the lowest bits must move them from
+        let mut offset = 0;
+        for (c, &frame_offset) in self.frame_offsets.iter().enumerate() {
+            if (frame >> c) & 1 != 0 {
+                offset ^= frame_offset;
+            }
+        }
+        let mask = (1 << m) - 1;
+        let mut b = ((((p.x as u64) & mask) << m) | ((p.y as u64) & mask)) ^ offset;
+        let mut c = 0;
+        while b != 0 {
+            if b & 1 != 0 {
+                index ^= self.pixel_inverse[c];
+            }
+            b >>= 1;
+            c += 1;
+        }
+        index
+    }
+}
+
+/// The inverse of `a` modulo `n`, for `a` and `n` that are coprime.
+pub fn multiplicative_inverse(a: i64, n: i64) -> i64 {
+    let (x, _) = extended_gcd(a, n);
+    x.rem_euclid(n)
+}
+
+/// Integers `x` and `y` with `a x + b y = gcd(a, b)`.
+fn extended_gcd(a: i64, b: i64) -> (i64, i64) {
+    if b == 0 {
+        return (1, 0);
+    }
+    let (xp, yp) = extended_gcd(b, a % b);
+    (yp, xp - (a / b) * yp)
+}
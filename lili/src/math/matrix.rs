@@ -0,0 +1,298 @@
+//! Square matrices.
+use std::ops::{Add, Index, IndexMut, Mul};
+
+use super::{difference_of_products, Float};
+
+/// An `N`x`N` matrix, stored in row-major order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SquareMatrix<const N: usize> {
+    m: [[Float; N]; N],
+}
+
+impl<const N: usize> Default for SquareMatrix<N> {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<const N: usize> SquareMatrix<N> {
+    pub fn new(m: [[Float; N]; N]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        let mut m = [[0.0; N]; N];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+
+    pub fn zero() -> Self {
+        Self { m: [[0.0; N]; N] }
+    }
+
+    /// Creates a matrix with the given values on its diagonal and zeros elsewhere.
+    pub fn diag(values: [Float; N]) -> Self {
+        let mut m = [[0.0; N]; N];
+        for (i, value) in values.into_iter().enumerate() {
+            m[i][i] = value;
+        }
+        Self { m }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = [[0.0; N]; N];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                t[j][i] = *value;
+            }
+        }
+        Self { m: t }
+    }
+
+    /// Multiplies the matrix with a column vector.
+    pub fn mul_vector(&self, v: &[Float; N]) -> [Float; N] {
+        let mut result = [0.0; N];
+        for (r, row) in result.iter_mut().zip(&self.m) {
+            *r = inner_product(row.iter().copied().zip(v.iter().copied()));
+        }
+        result
+    }
+}
+
+impl SquareMatrix<2> {
+    pub fn determinant(&self) -> Float {
+        let m = &self.m;
+        difference_of_products(m[0][0], m[1][1], m[0][1], m[1][0])
+    }
+
+    /// Returns the inverse of the matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+
+        let m = &self.m;
+        let inv_det = 1.0 / det;
+        Some(Self::new([
+            [m[1][1] * inv_det, -m[0][1] * inv_det],
+            [-m[1][0] * inv_det, m[0][0] * inv_det],
+        ]))
+    }
+}
+
+impl SquareMatrix<3> {
+    pub fn determinant(&self) -> Float {
+        let m = &self.m;
+        let minor12 = difference_of_products(m[1][1], m[2][2], m[1][2], m[2][1]);
+        let minor02 = difference_of_products(m[1][0], m[2][2], m[1][2], m[2][0]);
+        let minor01 = difference_of_products(m[1][0], m[2][1], m[1][1], m[2][0]);
+        m[0][2].mul_add(
+            minor01,
+            difference_of_products(m[0][0], minor12, m[0][1], minor02),
+        )
+    }
+
+    /// Returns the inverse of the matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+
+        let m = &self.m;
+        let inv_det = 1.0 / det;
+        let mut r = [[0.0; 3]; 3];
+        r[0][0] = inv_det * difference_of_products(m[1][1], m[2][2], m[1][2], m[2][1]);
+        r[1][0] = inv_det * difference_of_products(m[1][2], m[2][0], m[1][0], m[2][2]);
+        r[2][0] = inv_det * difference_of_products(m[1][0], m[2][1], m[1][1], m[2][0]);
+        r[0][1] = inv_det * difference_of_products(m[0][2], m[2][1], m[0][1], m[2][2]);
+        r[1][1] = inv_det * difference_of_products(m[0][0], m[2][2], m[0][2], m[2][0]);
+        r[2][1] = inv_det * difference_of_products(m[0][1], m[2][0], m[0][0], m[2][1]);
+        r[0][2] = inv_det * difference_of_products(m[0][1], m[1][2], m[0][2], m[1][1]);
+        r[1][2] = inv_det * difference_of_products(m[0][2], m[1][0], m[0][0], m[1][2]);
+        r[2][2] = inv_det * difference_of_products(m[0][0], m[1][1], m[0][1], m[1][0]);
+        Some(Self::new(r))
+    }
+}
+
+impl SquareMatrix<4> {
+    /// The 2x2 minors of the top two rows (`s`) and of the bottom two rows (`c`), from which both
+    /// the determinant and the inverse are computed.
+    fn minors(&self) -> ([Float; 6], [Float; 6]) {
+        let m = &self.m;
+        let s = [
+            difference_of_products(m[0][0], m[1][1], m[1][0], m[0][1]),
+            difference_of_products(m[0][0], m[1][2], m[1][0], m[0][2]),
+            difference_of_products(m[0][0], m[1][3], m[1][0], m[0][3]),
+            difference_of_products(m[0][1], m[1][2], m[1][1], m[0][2]),
+            difference_of_products(m[0][1], m[1][3], m[1][1], m[0][3]),
+            difference_of_products(m[0][2], m[1][3], m[1][2], m[0][3]),
+        ];
+        let c = [
+            difference_of_products(m[2][0], m[3][1], m[3][0], m[2][1]),
+            difference_of_products(m[2][0], m[3][2], m[3][0], m[2][2]),
+            difference_of_products(m[2][0], m[3][3], m[3][0], m[2][3]),
+            difference_of_products(m[2][1], m[3][2], m[3][1], m[2][2]),
+            difference_of_products(m[2][1], m[3][3], m[3][1], m[2][3]),
+            difference_of_products(m[2][2], m[3][3], m[3][2], m[2][3]),
+        ];
+        (s, c)
+    }
+
+    fn determinant_from_minors(s: &[Float; 6], c: &[Float; 6]) -> Float {
+        inner_product([
+            (s[0], c[5]),
+            (-s[1], c[4]),
+            (s[2], c[3]),
+            (s[3], c[2]),
+            (s[5], c[0]),
+            (-s[4], c[1]),
+        ])
+    }
+
+    pub fn determinant(&self) -> Float {
+        let (s, c) = self.minors();
+        Self::determinant_from_minors(&s, &c)
+    }
+
+    /// Returns the inverse of the matrix, or `None` if it is singular.
+    ///
+    /// The inverse is computed from the cofactors of the matrix, using error-free products so
+    /// that it stays accurate for nearly singular matrices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::math::matrix::SquareMatrix;
+    ///
+    /// let m = SquareMatrix::new([
+    ///     [2.0, 0.0, 0.0, 1.0],
+    ///     [0.0, 4.0, 0.0, 2.0],
+    ///     [0.0, 0.0, 8.0, 3.0],
+    ///     [0.0, 0.0, 0.0, 1.0],
+    /// ]);
+    /// let inverse = m.inverse().unwrap();
+    /// assert!((m * inverse).is_identity());
+    ///
+    /// assert!(SquareMatrix::<4>::zero().inverse().is_none());
+    /// ```
+    pub fn inverse(&self) -> Option<Self> {
+        let (s, c) = self.minors();
+        let det = Self::determinant_from_minors(&s, &c);
+        if det == 0.0 {
+            return None;
+        }
+
+        let m = &self.m;
+        let inv_det = 1.0 / det;
+        let cofactor = |terms: [(Float, Float); 3]| inv_det * inner_product(terms);
+        Some(Self::new([
+            [
+                cofactor([(m[1][1], c[5]), (m[1][3], c[3]), (-m[1][2], c[4])]),
+                cofactor([(-m[0][1], c[5]), (m[0][2], c[4]), (-m[0][3], c[3])]),
+                cofactor([(m[3][1], s[5]), (m[3][3], s[3]), (-m[3][2], s[4])]),
+                cofactor([(-m[2][1], s[5]), (m[2][2], s[4]), (-m[2][3], s[3])]),
+            ],
+            [
+                cofactor([(-m[1][0], c[5]), (m[1][2], c[2]), (-m[1][3], c[1])]),
+                cofactor([(m[0][0], c[5]), (m[0][3], c[1]), (-m[0][2], c[2])]),
+                cofactor([(-m[3][0], s[5]), (m[3][2], s[2]), (-m[3][3], s[1])]),
+                cofactor([(m[2][0], s[5]), (m[2][3], s[1]), (-m[2][2], s[2])]),
+            ],
+            [
+                cofactor([(m[1][0], c[4]), (m[1][3], c[0]), (-m[1][1], c[2])]),
+                cofactor([(-m[0][0], c[4]), (m[0][1], c[2]), (-m[0][3], c[0])]),
+                cofactor([(m[3][0], s[4]), (m[3][3], s[0]), (-m[3][1], s[2])]),
+                cofactor([(-m[2][0], s[4]), (m[2][1], s[2]), (-m[2][3], s[0])]),
+            ],
+            [
+                cofactor([(-m[1][0], c[3]), (m[1][1], c[1]), (-m[1][2], c[0])]),
+                cofactor([(m[0][0], c[3]), (m[0][2], c[0]), (-m[0][1], c[1])]),
+                cofactor([(-m[3][0], s[3]), (m[3][1], s[1]), (-m[3][2], s[0])]),
+                cofactor([(m[2][0], s[3]), (m[2][2], s[0]), (-m[2][1], s[1])]),
+            ],
+        ]))
+    }
+}
+
+/// Returns the sum of the products of each pair of values.
+///
+/// The rounding errors of the products and of the sum are tracked and added back in at the end,
+/// so the result is nearly as accurate as if it were computed with twice the precision.
+fn inner_product(terms: impl IntoIterator<Item = (Float, Float)>) -> Float {
+    let mut sum: Float = 0.0;
+    let mut error: Float = 0.0;
+    for (a, b) in terms {
+        // The product and its rounding error
+        let product = a * b;
+        let product_error = a.mul_add(b, -product);
+
+        // The sum and its rounding error
+        let new_sum = sum + product;
+        let delta = new_sum - sum;
+        let sum_error = (sum - (new_sum - delta)) + (product - delta);
+
+        sum = new_sum;
+        error += product_error + sum_error;
+    }
+    sum + error
+}
+
+impl<const N: usize> Index<usize> for SquareMatrix<N> {
+    type Output = [Float; N];
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for SquareMatrix<N> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+impl<const N: usize> Mul for SquareMatrix<N> {
+    type Output = SquareMatrix<N>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut r = [[0.0; N]; N];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = inner_product((0..N).map(|k| (self.m[i][k], rhs.m[k][j])));
+            }
+        }
+        Self { m: r }
+    }
+}
+
+impl<const N: usize> Mul<Float> for SquareMatrix<N> {
+    type Output = SquareMatrix<N>;
+
+    fn mul(self, rhs: Float) -> Self::Output {
+        Self {
+            m: self.m.map(|row| row.map(|value| value * rhs)),
+        }
+    }
+}
+
+impl<const N: usize> Add for SquareMatrix<N> {
+    type Output = SquareMatrix<N>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut r = self.m;
+        for (row, rhs_row) in r.iter_mut().zip(rhs.m) {
+            for (value, rhs_value) in row.iter_mut().zip(rhs_row) {
+                *value += rhs_value;
+            }
+        }
+        Self { m: r }
+    }
+}
@@ -1,5 +1,4 @@
 //! Traits for working with numbers.
-use super::Float;
 
 /// A trait for checking if a value is NaN (Not a Number).
 pub trait IsNan {
@@ -7,13 +6,20 @@ pub trait IsNan {
     fn is_nan(&self) -> bool;
 }
 
-impl IsNan for Float {
+impl IsNan for f32 {
     #[inline]
     fn is_nan(&self) -> bool {
         f32::is_nan(*self)
     }
 }
 
+impl IsNan for f64 {
+    #[inline]
+    fn is_nan(&self) -> bool {
+        f64::is_nan(*self)
+    }
+}
+
 impl IsNan for i32 {
     #[inline]
     fn is_nan(&self) -> bool {
@@ -34,6 +40,13 @@ impl Max for f32 {
     }
 }
 
+impl Max for f64 {
+    #[inline]
+    fn max(self, other: Self) -> Self {
+        self.max(other)
+    }
+}
+
 impl Max for i32 {
     #[inline]
     fn max(self, other: Self) -> Self {
@@ -54,6 +67,13 @@ impl Min for f32 {
     }
 }
 
+impl Min for f64 {
+    #[inline]
+    fn min(self, other: Self) -> Self {
+        self.min(other)
+    }
+}
+
 impl Min for i32 {
     #[inline]
     fn min(self, other: Self) -> Self {
@@ -68,8 +88,11 @@ pub trait Ceil {
     /// # Examples
     ///
     /// ```
-    /// assert_eq!(5.3.ceil(), 6.0);
-    /// assert_eq!((-5.3).ceil(), -5.0);
+    /// use lili::math::num_traits::Ceil;
+    ///
+    /// assert_eq!(Ceil::ceil(5.3_f32), 6.0);
+    /// assert_eq!(Ceil::ceil(-5.3_f64), -5.0);
+    /// assert_eq!(Ceil::ceil(5), 5);
     /// ```
     fn ceil(self) -> Self;
 }
@@ -81,6 +104,13 @@ impl Ceil for f32 {
     }
 }
 
+impl Ceil for f64 {
+    #[inline]
+    fn ceil(self) -> Self {
+        self.ceil()
+    }
+}
+
 impl Ceil for i32 {
     #[inline]
     fn ceil(self) -> Self {
@@ -101,6 +131,13 @@ impl Floor for f32 {
     }
 }
 
+impl Floor for f64 {
+    #[inline]
+    fn floor(self) -> Self {
+        self.floor()
+    }
+}
+
 impl Floor for i32 {
     #[inline]
     fn floor(self) -> Self {
@@ -121,6 +158,13 @@ impl Abs for f32 {
     }
 }
 
+impl Abs for f64 {
+    #[inline]
+    fn abs(self) -> Self {
+        self.abs()
+    }
+}
+
 impl Abs for i32 {
     #[inline]
     fn abs(self) -> Self {
@@ -135,8 +179,10 @@ pub trait MulAdd {
     /// # Examples
     ///
     /// ```
-    /// assert_eq!(2.0.mul_add(3.0, 4.0), 10.0);
-    /// assert_eq!(2.mul_add(3, 4), 10);
+    /// use lili::math::num_traits::MulAdd;
+    ///
+    /// assert_eq!(MulAdd::mul_add(2.0_f32, 3.0, 4.0), 10.0);
+    /// assert_eq!(MulAdd::mul_add(2, 3, 4), 10);
     /// ```
     fn mul_add(self, a: Self, b: Self) -> Self;
 }
@@ -148,6 +194,13 @@ impl MulAdd for f32 {
     }
 }
 
+impl MulAdd for f64 {
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self.mul_add(a, b)
+    }
+}
+
 impl MulAdd for i32 {
     #[inline]
     fn mul_add(self, a: Self, b: Self) -> Self {
@@ -0,0 +1,60 @@
+//! Progressive multi-jittered (0, 2) point sets with blue noise properties, following
+//! Christensen et al., "Progressive Multi-Jittered Sample Sequences" (2018).
+use super::{points::Point2f, Float};
+
+/// The number of point sets that [`get_pmj02bn_sample`] provides.
+pub const N_PMJ02BN_SETS: usize = 5;
+
+/// The number of points in each set.
+pub const N_PMJ02BN_SAMPLES: usize = 65536;
+
+/// The number of bits of the fixed-point coordinates of the points, which `f32` represents
+/// exactly.
+pub const PMJ02BN_BITS: u32 = 24;
+
+/// The point sets, one after the other, as pairs of fixed-point coordinates in little-endian
+/// 32-bit values, which are generated by the `pmj02bn_tables` example.
+const PMJ02BN_SETS: &[u8] = include_bytes!("tables/pmj02bn.bin");
+
+const _: () = assert!(PMJ02BN_SETS.len() == 8 * N_PMJ02BN_SETS * N_PMJ02BN_SAMPLES);
+
+/// Point `sample_index` of the point set `set_index`.
+///
+/// Every prefix of a set whose length is a power of two is a (0, 2)-net: dividing the unit square
+/// into as many rectangles of the same power-of-two width and height as there are points, for any
+/// shape of the rectangles, puts one point in each. Within these constraints, the points are
+/// placed far from the points before them, so that the error of estimates that use them is blue
+/// noise.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::pmj02tables::get_pmj02bn_sample;
+///
+/// // The first 16 points are stratified in 16 x 1, 8 x 2, 4 x 4, 2 x 8 and 1 x 16 grids
+/// for (nx, ny) in [(16.0, 1.0), (8.0, 2.0), (4.0, 4.0), (2.0, 8.0), (1.0, 16.0)] {
+///     let mut cells: Vec<(u32, u32)> = (0..16)
+///         .map(|i| {
+///             let p = get_pmj02bn_sample(0, i);
+///             ((p.x * nx) as u32, (p.y * ny) as u32)
+///         })
+///         .collect();
+///     cells.sort();
+///     cells.dedup();
+///     assert_eq!(cells.len(), 16);
+/// }
+/// ```
+pub fn get_pmj02bn_sample(set_index: usize, sample_index: usize) -> Point2f {
+    assert!(set_index < N_PMJ02BN_SETS && sample_index < N_PMJ02BN_SAMPLES);
+    let offset = 8 * (set_index * N_PMJ02BN_SAMPLES + sample_index);
+    let [x, y] = [offset, offset + 4].map(|i| {
+        let bits = u32::from_le_bytes([
+            PMJ02BN_SETS[i],
+            PMJ02BN_SETS[i + 1],
+            PMJ02BN_SETS[i + 2],
+            PMJ02BN_SETS[i + 3],
+        ]);
+        bits as Float * (2.0 as Float).powi(-(PMJ02BN_BITS as i32))
+    });
+    Point2f::new(x, y)
+}
@@ -132,9 +132,9 @@ index_mut_impl3!(Point3);
 
 // Type aliases
 
-/// A 2-dimensional point with `f32` components.
+/// A 2-dimensional point with `Float` components.
 pub type Point2f = Point2<Float>;
-/// A 3-dimensional point with `f32` components.
+/// A 3-dimensional point with `Float` components.
 pub type Point3f = Point3<Float>;
 
 /// A 2-dimensional point with `i32` components.
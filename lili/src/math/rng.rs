@@ -0,0 +1,101 @@
+//! Pseudo-random number generation.
+use super::{hash::mix_bits, Float, FloatExt};
+
+/// O'Neill's PCG32 pseudo-random number generator.
+///
+/// A generator can be set to any of 2^63 independent sequences, and moved forward or back in its
+/// sequence in logarithmic time, so that every sample of every pixel can have a reproducible
+/// sequence of its own.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::rng::Rng;
+///
+/// let mut rng = Rng::new(7, 0);
+/// let values: Vec<u32> = (0..10).map(|_| rng.uniform_u32()).collect();
+///
+/// rng.advance(-5);
+/// assert_eq!(rng.uniform_u32(), values[5]);
+///
+/// let mut skipped = Rng::new(7, 0);
+/// skipped.advance(9);
+/// assert_eq!(skipped.uniform_u32(), values[9]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+    inc: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self {
+            state: Self::DEFAULT_STATE,
+            inc: Self::DEFAULT_STREAM,
+        }
+    }
+}
+
+impl Rng {
+    const DEFAULT_STATE: u64 = 0x853c49e6748fea9b;
+    const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+    const MULT: u64 = 0x5851f42d4c957f2d;
+
+    /// Creates a generator at the start of the sequence `seq_index`, offset by `seed`.
+    pub fn new(seq_index: u64, seed: u64) -> Self {
+        let mut rng = Self::default();
+        rng.set_sequence(seq_index, seed);
+        rng
+    }
+
+    /// Moves the generator to the start of the sequence `seq_index`, offset by `seed`.
+    pub fn set_sequence(&mut self, seq_index: u64, seed: u64) {
+        self.state = 0;
+        self.inc = (seq_index << 1) | 1;
+        self.uniform_u32();
+        self.state = self.state.wrapping_add(seed);
+        self.uniform_u32();
+    }
+
+    /// Moves the generator to the start of the sequence `seq_index`, with a seed derived from it.
+    pub fn set_sequence_index(&mut self, seq_index: u64) {
+        self.set_sequence(seq_index, mix_bits(seq_index));
+    }
+
+    pub fn uniform_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state.wrapping_mul(Self::MULT).wrapping_add(self.inc);
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rot = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rot)
+    }
+
+    pub fn uniform_u64(&mut self) -> u64 {
+        let high = self.uniform_u32() as u64;
+        let low = self.uniform_u32() as u64;
+        (high << 32) | low
+    }
+
+    /// A value in `[0, 1)`.
+    pub fn uniform_float(&mut self) -> Float {
+        (self.uniform_u32() as Float * (2.0 as Float).powi(-32)).min(Float::ONE_MINUS_EPSILON)
+    }
+
+    /// Moves the generator `delta` values forward in its sequence, or back if it is negative.
+    pub fn advance(&mut self, delta: i64) {
+        let (mut cur_mult, mut cur_plus) = (Self::MULT, self.inc);
+        let (mut acc_mult, mut acc_plus) = (1_u64, 0_u64);
+        let mut delta = delta as u64;
+        while delta > 0 {
+            if delta & 1 != 0 {
+                acc_mult = acc_mult.wrapping_mul(cur_mult);
+                acc_plus = acc_plus.wrapping_mul(cur_mult).wrapping_add(cur_plus);
+            }
+            cur_plus = cur_mult.wrapping_add(1).wrapping_mul(cur_plus);
+            cur_mult = cur_mult.wrapping_mul(cur_mult);
+            delta /= 2;
+        }
+        self.state = acc_mult.wrapping_mul(self.state).wrapping_add(acc_plus);
+    }
+}
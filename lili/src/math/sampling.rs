@@ -1,6 +1,10 @@
 //! This module provides functions and structures for various sampling techniques in computer graphics.
 
-use super::{points::Point2f, sqr, Float, FloatExt};
+use super::{
+    bounds::Bounds2f,
+    points::{Point2f, Point2i},
+    sqr, Float, FloatExt,
+};
 
 /// Computes the balance heuristic for two distributions
 ///
@@ -17,8 +21,8 @@ use super::{points::Point2f, sqr, Float, FloatExt};
 /// # Returns
 ///
 /// The balance heuristic value
-pub fn balance_heuristic(nf: u32, f_pdf: f32, ng: u32, g_pdf: f32) -> f32 {
-    ((nf as f32) * f_pdf) / ((nf as f32) * f_pdf + (ng as f32) * g_pdf)
+pub fn balance_heuristic(nf: u32, f_pdf: Float, ng: u32, g_pdf: Float) -> Float {
+    ((nf as Float) * f_pdf) / ((nf as Float) * f_pdf + (ng as Float) * g_pdf)
 }
 
 /// Computes the power heuristic for two distributions
@@ -40,23 +44,31 @@ pub fn balance_heuristic(nf: u32, f_pdf: f32, ng: u32, g_pdf: f32) -> f32 {
 /// # Remarks
 ///
 /// Beta is chosen as 2
-pub fn power_heuristic(nf: u32, f_pdf: f32, ng: u32, g_pdf: f32) -> f32 {
-    let f = (nf as f32) * f_pdf;
-    let g = (ng as f32) * g_pdf;
+pub fn power_heuristic(nf: u32, f_pdf: Float, ng: u32, g_pdf: Float) -> Float {
+    let f = (nf as Float) * f_pdf;
+    let g = (ng as Float) * g_pdf;
 
     sqr(f) / (sqr(f) + sqr(g))
 }
 
-#[derive(Default)]
+/// An index sampled from a discrete distribution
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct DiscreteSample {
-    sample: i32,
-    pmf: Float,
-    u_remapped: Float,
+    /// The sampled index, or -1 if there was nothing to sample
+    pub sample: i32,
+    /// The probability of sampling the index
+    pub pmf: Float,
+    /// A new random value in `[0, 1)`, given by where the random value fell within the
+    /// probability of the index, which can be used for further sampling
+    pub u_remapped: Float,
 }
 
 impl DiscreteSample {
     /// Creates a `DiscreteSample` from a list of weights and a random value `u`
     ///
+    /// This takes linear time in the number of weights; weights that are sampled many times
+    /// should go in an [`AliasTable`] instead.
+    ///
     /// # Arguments
     ///
     /// * `weights` - The list of weights for each sample
@@ -94,15 +106,154 @@ impl DiscreteSample {
                 return Self {
                     sample: i as i32,
                     pmf: *weight / sum_weights,
-                    u_remapped: ((up - sum) / *weight).min(Float::ONE_MINUS_EPSILON),
+                    u_remapped: ((up - (sum - *weight)) / *weight).min(Float::ONE_MINUS_EPSILON),
                 };
             }
         }
 
-        // If we get here, something went wrong
-        #[cfg(debug_assertions)]
-        {
-            panic!("sample_discrete() failed to sample an index");
+        // `up` is below the sum of the weights, so some prefix of them always exceeds it
+        unreachable!("sample_discrete() failed to sample an index");
+    }
+}
+
+/// A table for sampling a discrete distribution in constant time, by Walker's alias method
+///
+/// Each index has a bin with the same probability of being chosen, which holds the index itself
+/// with probability `q` and its alias otherwise, so that the total probability of each index
+/// matches its weight.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::sampling::AliasTable;
+///
+/// let table = AliasTable::new(&[1.0, 3.0]);
+/// assert_eq!(table.pmf(1), 0.75);
+///
+/// let sample = table.sample(0.125);
+/// assert_eq!((sample.sample, sample.pmf), (0, 0.25));
+/// assert!((sample.u_remapped - 0.5).abs() < 1e-6);
+/// ```
+#[derive(Clone)]
+pub struct AliasTable {
+    bins: Vec<AliasBin>,
+}
+
+#[derive(Clone, Copy)]
+struct AliasBin {
+    /// The probability of sampling the index of the bin
+    p: Float,
+    /// The probability of keeping the index of the bin rather than its alias when the bin is
+    /// chosen
+    q: Float,
+    alias: usize,
+}
+
+impl AliasTable {
+    /// Creates a table for sampling indices with probabilities proportional to `weights`, which
+    /// must not be negative
+    ///
+    /// Weights that are all zero give a uniform distribution, whose probabilities are reported
+    /// as zero.
+    pub fn new(weights: &[Float]) -> Self {
+        assert!(!weights.is_empty(), "an alias table needs a weight");
+        debug_assert!(weights.iter().all(|&w| w >= 0.0));
+
+        let n = weights.len();
+        let sum: f64 = weights.iter().map(|&w| w as f64).sum();
+        let mut bins: Vec<AliasBin> = weights
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| AliasBin {
+                p: if sum > 0.0 {
+                    (w as f64 / sum) as Float
+                } else {
+                    0.0
+                },
+                q: 1.0,
+                alias: i,
+            })
+            .collect();
+
+        // Split the indices by whether their probability, scaled so that a bin holds 1, fits in
+        // their own bin or overflows it
+        let (mut under, mut over): (Vec<_>, Vec<_>) = (0..n)
+            .map(|i| {
+                (
+                    i,
+                    if sum > 0.0 {
+                        weights[i] as f64 / sum * n as f64
+                    } else {
+                        0.0
+                    },
+                )
+            })
+            .partition(|&(_, p_hat)| p_hat < 1.0);
+
+        // Fill each underfull bin with an overfull index, which keeps the excess
+        while let (Some(&(un, un_p_hat)), Some(&(ov, ov_p_hat))) = (under.last(), over.last()) {
+            under.pop();
+            over.pop();
+            bins[un].q = un_p_hat as Float;
+            bins[un].alias = ov;
+
+            let p_excess = un_p_hat + ov_p_hat - 1.0;
+            if p_excess < 1.0 {
+                under.push((ov, p_excess));
+            } else {
+                over.push((ov, p_excess));
+            }
+        }
+
+        // The indices left are only off from 1 by rounding errors, so they fill their own bins,
+        // as do all of them when the weights are zero
+        for (i, _) in under.into_iter().chain(over) {
+            bins[i].q = 1.0;
+            bins[i].alias = i;
+        }
+
+        Self { bins }
+    }
+
+    /// The number of indices
+    pub fn size(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// The probability of sampling `index`
+    pub fn pmf(&self, index: usize) -> Float {
+        self.bins[index].p
+    }
+
+    /// Samples an index
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - The random value used for sampling
+    ///
+    /// # Returns
+    ///
+    /// A `DiscreteSample` object containing the sampled index, its probability and the remapped
+    /// random value `u`
+    pub fn sample(&self, u: Float) -> DiscreteSample {
+        // Choose a bin with the leading part of `u`, and the index within it with the rest
+        let n = self.size();
+        let offset = ((u * n as Float) as usize).min(n - 1);
+        let up = (u * n as Float - offset as Float).min(Float::ONE_MINUS_EPSILON);
+
+        let bin = &self.bins[offset];
+        if up < bin.q {
+            DiscreteSample {
+                sample: offset as i32,
+                pmf: bin.p,
+                u_remapped: (up / bin.q).min(Float::ONE_MINUS_EPSILON),
+            }
+        } else {
+            DiscreteSample {
+                sample: bin.alias as i32,
+                pmf: self.bins[bin.alias].p,
+                u_remapped: ((up - bin.q) / (1.0 - bin.q)).min(Float::ONE_MINUS_EPSILON),
+            }
         }
     }
 }
@@ -203,3 +354,300 @@ pub fn invert_bilinear_sample(p: Point2f, w: &[Float; 4]) -> Point2f {
         invert_linear_sample(p.y, w[0] + w[1], w[2] + w[3]),
     )
 }
+
+/// Samples a wavelength in nanometers between 360nm and 830nm, with a density that roughly
+/// follows the sensitivity of the human eye
+///
+/// # Arguments
+///
+/// * `u` - The random value used for sampling
+///
+/// # Returns
+///
+/// The sampled wavelength
+#[allow(clippy::excessive_precision)]
+pub fn sample_visible_wavelengths(u: Float) -> Float {
+    538.0 - 138.888889 * (0.85691062 - 1.82750197 * u).atanh()
+}
+
+/// Computes the probability density function (pdf) of `sample_visible_wavelengths`
+///
+/// # Arguments
+///
+/// * `lambda` - The wavelength in nanometers
+///
+/// # Returns
+///
+/// The value of the pdf at `lambda`, which is 0 outside of 360nm to 830nm
+#[allow(clippy::excessive_precision)]
+pub fn visible_wavelengths_pdf(lambda: Float) -> Float {
+    if !(360.0..=830.0).contains(&lambda) {
+        return 0.0;
+    }
+    0.0039398042 / sqr((0.0072 * (lambda - 538.0)).cosh())
+}
+
+/// Samples the tent function, which falls off linearly from 1 at 0 to 0 at `-r` and `r`
+///
+/// # Arguments
+///
+/// * `u` - The random value used for sampling
+/// * `r` - The radius of the tent
+///
+/// # Returns
+///
+/// The sampled value between `-r` and `r`
+pub fn sample_tent(u: Float, r: Float) -> Float {
+    // Choose a side of the tent, then reuse the random value to sample a linear ramp on it
+    if u < 0.5 {
+        let u = (2.0 * u).min(Float::ONE_MINUS_EPSILON);
+        -r + r * sample_linear(u, 0.0, 1.0)
+    } else {
+        let u = (2.0 * u - 1.0).min(Float::ONE_MINUS_EPSILON);
+        r * sample_linear(u, 1.0, 0.0)
+    }
+}
+
+/// A piecewise-constant distribution over `[min, max]`, with a density proportional to the
+/// absolute values of a function tabulated at evenly spaced intervals
+///
+/// Functions that are zero everywhere give a uniform distribution, whose density is reported as
+/// zero.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::sampling::PiecewiseConstant1D;
+///
+/// let distrib = PiecewiseConstant1D::new(&[1.0, 3.0], 0.0, 2.0);
+/// assert_eq!(distrib.integral(), 4.0);
+///
+/// // The first quarter of the random values go to the first piece
+/// let (x, pdf, offset) = distrib.sample(0.125);
+/// assert_eq!((x, pdf, offset), (0.5, 0.25, 0));
+/// let (x, pdf, offset) = distrib.sample(0.625);
+/// assert_eq!((x, pdf, offset), (1.5, 0.75, 1));
+///
+/// assert_eq!(distrib.invert(1.5), Some(0.625));
+/// ```
+#[derive(Clone)]
+pub struct PiecewiseConstant1D {
+    /// The absolute values of the function over each piece.
+    func: Vec<Float>,
+    /// The cumulative distribution function at the bounds of the pieces, from 0 to 1.
+    cdf: Vec<Float>,
+    min: Float,
+    max: Float,
+    func_integral: Float,
+}
+
+impl PiecewiseConstant1D {
+    /// Creates the distribution of the function whose values over each piece of `[min, max]` are
+    /// `f`
+    pub fn new(f: &[Float], min: Float, max: Float) -> Self {
+        assert!(!f.is_empty(), "a piecewise-constant function needs a piece");
+        assert!(
+            max > min,
+            "the domain of a piecewise-constant function must not be empty"
+        );
+
+        let n = f.len();
+        let func: Vec<Float> = f.iter().map(|v| v.abs()).collect();
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for (i, v) in func.iter().enumerate() {
+            cdf.push(cdf[i] + v * (max - min) / n as Float);
+        }
+
+        let func_integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if func_integral == 0.0 {
+                i as Float / n as Float
+            } else {
+                *c / func_integral
+            };
+        }
+
+        Self {
+            func,
+            cdf,
+            min,
+            max,
+            func_integral,
+        }
+    }
+
+    /// The number of pieces
+    pub fn size(&self) -> usize {
+        self.func.len()
+    }
+
+    /// The integral of the absolute value of the function over its domain
+    pub fn integral(&self) -> Float {
+        self.func_integral
+    }
+
+    /// Samples the distribution
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - The random value used for sampling
+    ///
+    /// # Returns
+    ///
+    /// The sampled value, its density and the index of the piece that it lies in
+    pub fn sample(&self, u: Float) -> (Float, Float, usize) {
+        let offset = find_interval(&self.cdf, u);
+
+        // Compute the offset of the sample within its piece
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 {
+            (u - self.cdf[offset]) / width
+        } else {
+            u - self.cdf[offset]
+        };
+
+        let pdf = if self.func_integral > 0.0 {
+            self.func[offset] / self.func_integral
+        } else {
+            0.0
+        };
+        let t = (offset as Float + du) / self.size() as Float;
+        (t.lerp(self.min, self.max), pdf, offset)
+    }
+
+    /// Computes the density of the distribution at `x`, which is 0 outside of its domain
+    pub fn pdf(&self, x: Float) -> Float {
+        if !(self.min..=self.max).contains(&x) || self.func_integral == 0.0 {
+            return 0.0;
+        }
+        self.func[self.piece(x)] / self.func_integral
+    }
+
+    /// Inverts a sample of the distribution
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The sampled value
+    ///
+    /// # Returns
+    ///
+    /// The random value that gives `x` when sampling, or `None` if `x` is outside of the domain
+    pub fn invert(&self, x: Float) -> Option<Float> {
+        if !(self.min..=self.max).contains(&x) {
+            return None;
+        }
+        let c = (x - self.min) / (self.max - self.min) * self.size() as Float;
+        let offset = self.piece(x);
+        let delta = c - offset as Float;
+        Some(delta.lerp(self.cdf[offset], self.cdf[offset + 1]))
+    }
+
+    /// The index of the piece that `x` lies in, clamped to the pieces of the domain
+    fn piece(&self, x: Float) -> usize {
+        let c = (x - self.min) / (self.max - self.min) * self.size() as Float;
+        (c as usize).min(self.size() - 1)
+    }
+}
+
+/// A piecewise-constant distribution over a rectangle, with a density proportional to the
+/// absolute values of a function tabulated on a grid
+///
+/// The vertical coordinate is sampled from the marginal distribution of the rows, and the
+/// horizontal one from the distribution within the chosen row.
+#[derive(Clone)]
+pub struct PiecewiseConstant2D {
+    domain: Bounds2f,
+    /// The distribution within each row.
+    conditional: Vec<PiecewiseConstant1D>,
+    /// The distribution of the rows.
+    marginal: PiecewiseConstant1D,
+}
+
+impl PiecewiseConstant2D {
+    /// Creates the distribution of the function whose values over the `nu` by `nv` cells of
+    /// `domain` are `f`, row by row
+    pub fn new(f: &[Float], nu: usize, nv: usize, domain: Bounds2f) -> Self {
+        assert_eq!(f.len(), nu * nv, "the function must have a value per cell");
+
+        let conditional: Vec<PiecewiseConstant1D> = f
+            .chunks_exact(nu)
+            .map(|row| PiecewiseConstant1D::new(row, domain.p_min.x, domain.p_max.x))
+            .collect();
+        let row_integrals: Vec<Float> = conditional.iter().map(|c| c.integral()).collect();
+        let marginal = PiecewiseConstant1D::new(&row_integrals, domain.p_min.y, domain.p_max.y);
+
+        Self {
+            domain,
+            conditional,
+            marginal,
+        }
+    }
+
+    pub fn domain(&self) -> Bounds2f {
+        self.domain
+    }
+
+    /// The number of cells in each direction
+    pub fn resolution(&self) -> Point2i {
+        Point2i::new(
+            self.conditional[0].size() as i32,
+            self.marginal.size() as i32,
+        )
+    }
+
+    /// The integral of the absolute value of the function over its domain
+    pub fn integral(&self) -> Float {
+        self.marginal.integral()
+    }
+
+    /// Samples the distribution
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - The random values used for sampling
+    ///
+    /// # Returns
+    ///
+    /// The sampled point, its density and the cell that it lies in
+    pub fn sample(&self, u: Point2f) -> (Point2f, Float, Point2i) {
+        let (y, pdf_y, v) = self.marginal.sample(u.y);
+        let (x, pdf_x, u) = self.conditional[v].sample(u.x);
+        (
+            Point2f::new(x, y),
+            pdf_x * pdf_y,
+            Point2i::new(u as i32, v as i32),
+        )
+    }
+
+    /// Computes the density of the distribution at `p`, which is 0 outside of its domain
+    pub fn pdf(&self, p: Point2f) -> Float {
+        if !self.domain.inside(p) || self.integral() == 0.0 {
+            return 0.0;
+        }
+        let row = self.marginal.piece(p.y);
+        let conditional = &self.conditional[row];
+        conditional.func[conditional.piece(p.x)] / self.integral()
+    }
+
+    /// Inverts a sample of the distribution
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - The sampled point
+    ///
+    /// # Returns
+    ///
+    /// The random values that give `p` when sampling, or `None` if `p` is outside of the domain
+    pub fn invert(&self, p: Point2f) -> Option<Point2f> {
+        let v = self.marginal.invert(p.y)?;
+        let u = self.conditional[self.marginal.piece(p.y)].invert(p.x)?;
+        Some(Point2f::new(u, v))
+    }
+}
+
+/// The index of the interval of `cdf` that `u` lies in, clamped to the intervals it has
+fn find_interval(cdf: &[Float], u: Float) -> usize {
+    let n = cdf.len() - 1;
+    (cdf.partition_point(|c| *c <= u).max(1) - 1).min(n - 1)
+}
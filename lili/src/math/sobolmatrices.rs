@@ -0,0 +1,40 @@
+//! The generator matrices of the Sobol sequence.
+/// The number of dimensions of the Sobol sequence that [`SOBOL_MATRICES`] provides.
+pub const N_SOBOL_DIMENSIONS: usize = 1024;
+
+/// The number of columns of each generator matrix, and so the number of bits of the sample
+/// indices that it uses.
+pub const SOBOL_MATRIX_SIZE: usize = 52;
+
+/// The generator matrices of the first [`N_SOBOL_DIMENSIONS`] dimensions of the Sobol sequence,
+/// one after the other.
+///
+/// Column `j` of a matrix is the 32-bit value that bit `j` of the sample index contributes to the
+/// sample, with the first digit of the sample in the most significant bit. The first dimension is
+/// the van der Corput sequence, and each of the others is given by the next primitive polynomial
+/// over GF(2), in order of degree, and initial direction numbers that are drawn at random among
+/// the valid ones with a fixed seed.
+///
+/// The matrices are computed by the `sobol_matrices` example, which can also compute them from
+/// the direction numbers of Joe and Kuo.
+pub static SOBOL_MATRICES: [[u32; SOBOL_MATRIX_SIZE]; N_SOBOL_DIMENSIONS] =
+    from_bytes(include_bytes!("tables/sobolmatrices.bin"));
+
+/// Reads matrices in the format written by the `sobol_matrices` example, which is their columns
+/// as little-endian 32-bit values.
+const fn from_bytes(bytes: &[u8]) -> [[u32; SOBOL_MATRIX_SIZE]; N_SOBOL_DIMENSIONS] {
+    assert!(bytes.len() == 4 * SOBOL_MATRIX_SIZE * N_SOBOL_DIMENSIONS);
+    let mut matrices = [[0; SOBOL_MATRIX_SIZE]; N_SOBOL_DIMENSIONS];
+    let mut i = 0;
+    while i < N_SOBOL_DIMENSIONS {
+        let mut j = 0;
+        while j < SOBOL_MATRIX_SIZE {
+            let k = 4 * (i * SOBOL_MATRIX_SIZE + j);
+            matrices[i][j] =
+                u32::from_le_bytes([bytes[k], bytes[k + 1], bytes[k + 2], bytes[k + 3]]);
+            j += 1;
+        }
+        i += 1;
+    }
+    matrices
+}
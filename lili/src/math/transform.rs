@@ -0,0 +1,283 @@
+//! Transformations between coordinate spaces.
+use std::ops::Mul;
+
+use crate::rays::{Differential, Ray, RayDifferential};
+
+use super::{
+    length::Length,
+    matrix::SquareMatrix,
+    normalize::Normalize,
+    normals::Normal3f,
+    points::Point3f,
+    vectors::{Cross, Vector3f},
+    Float, FloatExt,
+};
+
+/// A transformation represented by a 4x4 matrix, along with its inverse.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::{points::Point3f, transform::Transform, vectors::Vector3f};
+///
+/// let t = Transform::translate(Vector3f::new(1.0, 2.0, 3.0)) * Transform::scale(2.0, 2.0, 2.0);
+/// let p = t.apply(&Point3f::new(1.0, 1.0, 1.0));
+/// assert_eq!((p.x, p.y, p.z), (3.0, 4.0, 5.0));
+///
+/// // Vectors are not affected by translations
+/// let v = t.apply(&Vector3f::new(1.0, 0.0, 0.0));
+/// assert_eq!((v.x, v.y, v.z), (2.0, 0.0, 0.0));
+///
+/// let p = t.apply_inverse(&p);
+/// assert_eq!((p.x, p.y, p.z), (1.0, 1.0, 1.0));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Transform {
+    m: SquareMatrix<4>,
+    m_inv: SquareMatrix<4>,
+}
+
+impl Transform {
+    /// Creates a transformation from its matrix.
+    ///
+    /// If the matrix is singular, the inverse is filled with NaN.
+    pub fn new(m: SquareMatrix<4>) -> Self {
+        let m_inv = m
+            .inverse()
+            .unwrap_or(SquareMatrix::new([[Float::NAN; 4]; 4]));
+        Self { m, m_inv }
+    }
+
+    /// Creates a transformation from a matrix and its already known inverse.
+    pub fn from_matrices(m: SquareMatrix<4>, m_inv: SquareMatrix<4>) -> Self {
+        Self { m, m_inv }
+    }
+
+    /// Creates a transformation from 16 values in row-major order.
+    pub fn from_slice(values: &[Float; 16]) -> Self {
+        let mut m = SquareMatrix::zero();
+        for (i, value) in values.iter().enumerate() {
+            m[i / 4][i % 4] = *value;
+        }
+        Self::new(m)
+    }
+
+    pub fn matrix(&self) -> &SquareMatrix<4> {
+        &self.m
+    }
+
+    pub fn inverse_matrix(&self) -> &SquareMatrix<4> {
+        &self.m_inv
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            m: self.m_inv,
+            m_inv: self.m,
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self {
+            m: self.m.transpose(),
+            m_inv: self.m_inv.transpose(),
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.m.is_identity()
+    }
+
+    /// Returns `true` if the transformation scales lengths by more than `tolerance`.
+    pub fn has_scale(&self, tolerance: Float) -> bool {
+        let la2 = self.apply(&Vector3f::new(1.0, 0.0, 0.0)).length_squared();
+        let lb2 = self.apply(&Vector3f::new(0.0, 1.0, 0.0)).length_squared();
+        let lc2 = self.apply(&Vector3f::new(0.0, 0.0, 1.0)).length_squared();
+        (la2 - 1.0).abs() > tolerance
+            || (lb2 - 1.0).abs() > tolerance
+            || (lc2 - 1.0).abs() > tolerance
+    }
+
+    /// Returns `true` if the transformation changes a left-handed coordinate system into a
+    /// right-handed one, or the other way around.
+    pub fn swaps_handedness(&self) -> bool {
+        let m = &self.m;
+        let upper_left = SquareMatrix::new([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]],
+        ]);
+        upper_left.determinant() < 0.0
+    }
+
+    /// Applies the transformation to a point, vector, normal or ray.
+    pub fn apply<T: Transformable>(&self, value: &T) -> T {
+        value.transformed(&self.m, &self.m_inv)
+    }
+
+    /// Applies the inverse of the transformation, without having to construct it first.
+    pub fn apply_inverse<T: Transformable>(&self, value: &T) -> T {
+        value.transformed(&self.m_inv, &self.m)
+    }
+
+    pub fn translate(delta: Vector3f) -> Self {
+        let m = SquareMatrix::new([
+            [1.0, 0.0, 0.0, delta.x],
+            [0.0, 1.0, 0.0, delta.y],
+            [0.0, 0.0, 1.0, delta.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let m_inv = SquareMatrix::new([
+            [1.0, 0.0, 0.0, -delta.x],
+            [0.0, 1.0, 0.0, -delta.y],
+            [0.0, 0.0, 1.0, -delta.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Self { m, m_inv }
+    }
+
+    pub fn scale(x: Float, y: Float, z: Float) -> Self {
+        let m = SquareMatrix::diag([x, y, z, 1.0]);
+        let m_inv = SquareMatrix::diag([1.0 / x, 1.0 / y, 1.0 / z, 1.0]);
+        Self { m, m_inv }
+    }
+
+    /// Rotation by `theta` degrees around `axis`.
+    pub fn rotate(theta: Float, axis: Vector3f) -> Self {
+        let (sin_theta, cos_theta) = theta.deg_to_rad().sin_cos();
+        let a = axis.normalize();
+
+        let mut m = SquareMatrix::identity();
+        m[0][0] = a.x * a.x + (1.0 - a.x * a.x) * cos_theta;
+        m[0][1] = a.x * a.y * (1.0 - cos_theta) - a.z * sin_theta;
+        m[0][2] = a.x * a.z * (1.0 - cos_theta) + a.y * sin_theta;
+        m[1][0] = a.x * a.y * (1.0 - cos_theta) + a.z * sin_theta;
+        m[1][1] = a.y * a.y + (1.0 - a.y * a.y) * cos_theta;
+        m[1][2] = a.y * a.z * (1.0 - cos_theta) - a.x * sin_theta;
+        m[2][0] = a.x * a.z * (1.0 - cos_theta) - a.y * sin_theta;
+        m[2][1] = a.y * a.z * (1.0 - cos_theta) + a.x * sin_theta;
+        m[2][2] = a.z * a.z + (1.0 - a.z * a.z) * cos_theta;
+
+        // Rotations are orthogonal, so the inverse is the transpose
+        Self {
+            m,
+            m_inv: m.transpose(),
+        }
+    }
+
+    /// The camera-from-world transformation of a camera at `pos` looking at `look`.
+    ///
+    /// Returns `None` if `up` is parallel to the viewing direction.
+    pub fn look_at(pos: Point3f, look: Point3f, up: Vector3f) -> Option<Self> {
+        let dir = (look - pos).normalize();
+        let right = up.normalize().cross(&dir);
+        if right.length() == 0.0 {
+            return None;
+        }
+        let right = right.normalize();
+        let new_up = dir.cross(&right);
+
+        let world_from_camera = SquareMatrix::new([
+            [right.x, new_up.x, dir.x, pos.x],
+            [right.y, new_up.y, dir.y, pos.y],
+            [right.z, new_up.z, dir.z, pos.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let camera_from_world = world_from_camera.inverse()?;
+
+        Some(Self {
+            m: camera_from_world,
+            m_inv: world_from_camera,
+        })
+    }
+
+    /// Projects points in camera space onto the `z = 1` plane, with the `near` and `far` planes
+    /// mapped to `z = 0` and `z = 1`.
+    ///
+    /// `fov` is the full field of view in degrees, which is scaled to the range -1 to 1.
+    pub fn perspective(fov: Float, near: Float, far: Float) -> Self {
+        let persp = SquareMatrix::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, far / (far - near), -far * near / (far - near)],
+            [0.0, 0.0, 1.0, 0.0],
+        ]);
+        let inv_tan_ang = 1.0 / (fov.deg_to_rad() / 2.0).tan();
+        Self::scale(inv_tan_ang, inv_tan_ang, 1.0) * Self::new(persp)
+    }
+
+    /// Maps `z` values from between the `near` and `far` planes to between 0 and 1, leaving `x`
+    /// and `y` unchanged.
+    pub fn orthographic(near: Float, far: Float) -> Self {
+        Self::scale(1.0, 1.0, 1.0 / (far - near)) * Self::translate(Vector3f::new(0.0, 0.0, -near))
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    /// Composes two transformations; the result applies `rhs` first.
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            m: self.m * rhs.m,
+            m_inv: rhs.m_inv * self.m_inv,
+        }
+    }
+}
+
+/// Types that a [`Transform`] can be applied to.
+pub trait Transformable {
+    /// Returns `self` transformed by the matrix `m`, whose inverse is `m_inv`.
+    fn transformed(&self, m: &SquareMatrix<4>, m_inv: &SquareMatrix<4>) -> Self;
+}
+
+impl Transformable for Point3f {
+    fn transformed(&self, m: &SquareMatrix<4>, _m_inv: &SquareMatrix<4>) -> Self {
+        let [xp, yp, zp, wp] = m.mul_vector(&[self.x, self.y, self.z, 1.0]);
+        if wp == 1.0 {
+            Point3f::new(xp, yp, zp)
+        } else {
+            Point3f::new(xp, yp, zp) / wp
+        }
+    }
+}
+
+impl Transformable for Vector3f {
+    fn transformed(&self, m: &SquareMatrix<4>, _m_inv: &SquareMatrix<4>) -> Self {
+        let [x, y, z, _] = m.mul_vector(&[self.x, self.y, self.z, 0.0]);
+        Vector3f::new(x, y, z)
+    }
+}
+
+impl Transformable for Normal3f {
+    /// Normals are transformed by the inverse transpose, which keeps them perpendicular to the
+    /// surfaces they belong to.
+    fn transformed(&self, _m: &SquareMatrix<4>, m_inv: &SquareMatrix<4>) -> Self {
+        let [x, y, z, _] = m_inv.transpose().mul_vector(&[self.x, self.y, self.z, 0.0]);
+        Normal3f::new(x, y, z)
+    }
+}
+
+impl Transformable for Ray {
+    fn transformed(&self, m: &SquareMatrix<4>, m_inv: &SquareMatrix<4>) -> Self {
+        Ray {
+            o: self.o.transformed(m, m_inv),
+            d: self.d.transformed(m, m_inv),
+            ..self.clone()
+        }
+    }
+}
+
+impl Transformable for RayDifferential {
+    fn transformed(&self, m: &SquareMatrix<4>, m_inv: &SquareMatrix<4>) -> Self {
+        RayDifferential {
+            ray: self.ray.transformed(m, m_inv),
+            differential: self.differential.as_ref().map(|d| Differential {
+                rx_origin: d.rx_origin.transformed(m, m_inv),
+                ry_origin: d.ry_origin.transformed(m, m_inv),
+                rx_direction: d.rx_direction.transformed(m, m_inv),
+                ry_direction: d.ry_direction.transformed(m, m_inv),
+            }),
+        }
+    }
+}
@@ -95,14 +95,22 @@ pub trait TupleElement:
     + Copy
 {
     const ZERO: Self;
+    /// The smallest finite value of the type.
+    const MIN: Self;
+    /// The largest finite value of the type.
+    const MAX: Self;
 }
 
 impl TupleElement for Float {
     const ZERO: Self = 0.0;
+    const MIN: Self = Float::MIN;
+    const MAX: Self = Float::MAX;
 }
 
 impl TupleElement for i32 {
     const ZERO: Self = 0;
+    const MIN: Self = i32::MIN;
+    const MAX: Self = i32::MAX;
 }
 
 // Tuple macros
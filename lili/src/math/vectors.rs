@@ -128,9 +128,9 @@ index_mut_impl3!(Vector3);
 
 // Type aliases
 
-/// A 2-dimensional vector with `f32` components.
+/// A 2-dimensional vector with `Float` components.
 pub type Vector2f = Vector2<Float>;
-/// A 3-dimensional vector with `f32` components.
+/// A 3-dimensional vector with `Float` components.
 pub type Vector3f = Vector3<Float>;
 /// A 2-dimensional vector with `i32` components.
 pub type Vector2i = Vector2<i32>;
@@ -1,2 +1,15 @@
 #[derive(Default, Clone)]
 pub struct Medium {}
+
+/// The media on either side of a surface; `inside` is on the side opposite to the surface normal.
+#[derive(Default, Clone)]
+pub struct MediumInterface {
+    pub inside: Option<Medium>,
+    pub outside: Option<Medium>,
+}
+
+impl MediumInterface {
+    pub fn new(inside: Option<Medium>, outside: Option<Medium>) -> Self {
+        Self { inside, outside }
+    }
+}
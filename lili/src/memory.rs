@@ -0,0 +1,158 @@
+//! Memory allocation for short-lived values, such as those created while tracing a single sample.
+use std::{
+    alloc::{self, Layout},
+    cell::{Cell, RefCell},
+    fmt,
+    ptr::NonNull,
+    slice,
+};
+
+/// A bump allocator whose allocations are all freed at once by [`reset`](Self::reset).
+///
+/// Allocating only moves a pointer forward in a block of memory, so it is much cheaper than
+/// allocating on the heap. When the block is full, a block twice as large replaces it, and the
+/// smaller blocks are freed by the next reset, so a buffer that is reset after each sample soon
+/// stops allocating altogether.
+///
+/// The destructors of the values in the buffer are never run.
+///
+/// # Examples
+///
+/// ```
+/// use lili::memory::ScratchBuffer;
+///
+/// let mut buf = ScratchBuffer::new(16);
+/// let x = buf.alloc(1.5_f64);
+/// let values = buf.alloc_slice_fill_with(100, |i| i as u32);
+/// *x += values[99] as f64;
+/// assert_eq!(*x, 100.5);
+///
+/// buf.reset();
+/// assert!(buf.capacity() >= 100 * 4);
+/// ```
+pub struct ScratchBuffer {
+    ptr: Cell<NonNull<u8>>,
+    size: Cell<usize>,
+    offset: Cell<usize>,
+    /// Full blocks, which still hold values until the next reset.
+    smaller_buffers: RefCell<Vec<(NonNull<u8>, usize)>>,
+}
+
+// The buffer owns its memory, and its values can only be reached through borrows of it
+unsafe impl Send for ScratchBuffer {}
+
+impl ScratchBuffer {
+    /// The alignment of the blocks of memory, which is that of a cache line.
+    const ALIGN: usize = 64;
+
+    /// Creates a buffer with a first block of `size` bytes.
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            ptr: Cell::new(Self::allocate(size)),
+            size: Cell::new(size),
+            offset: Cell::new(0),
+            smaller_buffers: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The size of the current block of memory, in bytes.
+    pub fn capacity(&self) -> usize {
+        self.size.get()
+    }
+
+    /// Moves `value` into the buffer.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>();
+        // SAFETY: the memory is suitably aligned, and no other allocation overlaps it
+        unsafe {
+            ptr.as_ptr().write(value);
+            &mut *ptr.as_ptr()
+        }
+    }
+
+    /// Allocates a slice of `len` values, given for each index by `f`.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_with<T>(&self, len: usize, mut f: impl FnMut(usize) -> T) -> &mut [T] {
+        let layout = Layout::array::<T>(len).expect("scratch buffer allocation is too large");
+        let ptr = self.alloc_layout(layout).cast::<T>();
+        // SAFETY: as for `alloc`, and every element is initialized before the slice is created
+        unsafe {
+            for i in 0..len {
+                ptr.as_ptr().add(i).write(f(i));
+            }
+            slice::from_raw_parts_mut(ptr.as_ptr(), len)
+        }
+    }
+
+    /// Frees all of the values in the buffer, keeping only its largest block of memory.
+    pub fn reset(&mut self) {
+        for (ptr, size) in self.smaller_buffers.get_mut().drain(..) {
+            Self::deallocate(ptr, size);
+        }
+        self.offset.set(0);
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        let base = self.ptr.get().as_ptr() as usize;
+        let start = (base + self.offset.get()).next_multiple_of(layout.align()) - base;
+        if start + layout.size() > self.size.get() {
+            self.grow(layout);
+            return self.alloc_layout(layout);
+        }
+        self.offset.set(start + layout.size());
+        // SAFETY: `start` lies within the current block
+        unsafe { NonNull::new_unchecked(self.ptr.get().as_ptr().add(start)) }
+    }
+
+    /// Replaces the current block by one that is at least twice as large and fits `layout`
+    /// whatever the alignment of its start.
+    fn grow(&self, layout: Layout) {
+        let size = (2 * self.size.get()).max(layout.size() + layout.align());
+        let old_ptr = self.ptr.replace(Self::allocate(size));
+        let old_size = self.size.replace(size);
+        self.smaller_buffers.borrow_mut().push((old_ptr, old_size));
+        self.offset.set(0);
+    }
+
+    fn allocate(size: usize) -> NonNull<u8> {
+        let layout = Self::block_layout(size);
+        // SAFETY: blocks are never empty
+        let ptr = unsafe { alloc::alloc(layout) };
+        NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+    }
+
+    fn deallocate(ptr: NonNull<u8>, size: usize) {
+        // SAFETY: the block was allocated by `allocate` with the same size
+        unsafe { alloc::dealloc(ptr.as_ptr(), Self::block_layout(size)) }
+    }
+
+    fn block_layout(size: usize) -> Layout {
+        Layout::from_size_align(size, Self::ALIGN).expect("scratch buffer block is too large")
+    }
+}
+
+impl Default for ScratchBuffer {
+    /// Creates a buffer with a small first block, which grows to fit what is allocated in it.
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Drop for ScratchBuffer {
+    fn drop(&mut self) {
+        self.reset();
+        Self::deallocate(self.ptr.get(), self.size.get());
+    }
+}
+
+impl fmt::Debug for ScratchBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScratchBuffer")
+            .field("capacity", &self.size.get())
+            .field("used", &self.offset.get())
+            .field("smaller_buffers", &self.smaller_buffers.borrow().len())
+            .finish()
+    }
+}
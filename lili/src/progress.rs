@@ -0,0 +1,338 @@
+//! Reporting the progress of long-running work, such as rendering.
+use std::{
+    env,
+    fmt::Write as _,
+    io::{self, IsTerminal, Write as _},
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::Options;
+
+/// How a [`ProgressReporter`] shows progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStyle {
+    /// Nothing is shown.
+    Quiet,
+    /// A progress bar on the terminal, with the elapsed time, estimated time remaining and rate.
+    /// Bars of reporters that are active at the same time are shown one per line.
+    Bar,
+    /// One JSON object per line, every second and when the work is done, for other programs to
+    /// read.
+    Json,
+}
+
+impl ProgressStyle {
+    /// The style that the options ask for: JSON lines are written even when the output is quiet,
+    /// since they are not meant for people.
+    pub fn from_options(options: &Options) -> Self {
+        if options.progress_json {
+            Self::Json
+        } else if options.quiet {
+            Self::Quiet
+        } else {
+            Self::Bar
+        }
+    }
+
+    /// How often progress is shown.
+    fn interval(self) -> Duration {
+        match self {
+            Self::Json => Duration::from_secs(1),
+            _ => Duration::from_millis(250),
+        }
+    }
+}
+
+/// Shows the progress of work made of a known number of samples, which any number of threads may
+/// report as they complete them.
+///
+/// Progress is shown by a background thread until the reporter is [`done`](Self::done) or
+/// dropped. A reporter that is dropped before it is done, e.g. because the work failed, shows
+/// the samples that were completed and that the work was aborted.
+///
+/// # Examples
+///
+/// ```
+/// use lili::progress::{ProgressReporter, ProgressStyle};
+///
+/// let mut progress = ProgressReporter::new(1000, "Rendering", ProgressStyle::Quiet);
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| (0..250).for_each(|_| progress.update(1)));
+///     }
+/// });
+/// assert_eq!(progress.completed(), 1000);
+/// progress.done();
+/// ```
+pub struct ProgressReporter {
+    state: Arc<ProgressState>,
+    style: ProgressStyle,
+    exit: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct ProgressState {
+    title: String,
+    total: i64,
+    completed: AtomicI64,
+    start: Instant,
+}
+
+impl ProgressReporter {
+    /// Starts reporting the progress of `total` samples of work described by `title`.
+    pub fn new(total: i64, title: &str, style: ProgressStyle) -> Self {
+        let state = Arc::new(ProgressState {
+            title: title.to_string(),
+            total,
+            completed: AtomicI64::new(0),
+            start: Instant::now(),
+        });
+        let exit = Arc::new(AtomicBool::new(false));
+
+        let thread = (style != ProgressStyle::Quiet).then(|| {
+            if style == ProgressStyle::Bar {
+                bars().active.push(state.clone());
+            }
+            let (state, exit) = (state.clone(), exit.clone());
+            thread::spawn(move || {
+                // Wait a little before the first report, so that short tasks show nothing but
+                // their final state
+                while !exit.load(Ordering::Acquire) {
+                    thread::park_timeout(style.interval());
+                    if !exit.load(Ordering::Acquire) {
+                        show(style, &state, Report::Running);
+                    }
+                }
+            })
+        });
+
+        Self {
+            state,
+            style,
+            exit,
+            thread,
+        }
+    }
+
+    /// Records that `n` more samples are complete.
+    pub fn update(&self, n: i64) {
+        self.state.completed.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// The number of samples that are complete.
+    pub fn completed(&self) -> i64 {
+        self.state.completed.load(Ordering::Relaxed)
+    }
+
+    /// The time since the reporter was created.
+    pub fn elapsed(&self) -> Duration {
+        self.state.start.elapsed()
+    }
+
+    /// Stops reporting progress, showing the work as complete.
+    pub fn done(&mut self) {
+        self.stop(Report::Finished);
+    }
+
+    /// Stops the thread that shows progress, then shows it for the last time.
+    fn stop(&mut self, report: Report) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        if report == Report::Finished {
+            self.state
+                .completed
+                .store(self.state.total, Ordering::Relaxed);
+        }
+        self.exit.store(true, Ordering::Release);
+        thread.thread().unpark();
+        // The thread only panics if the output cannot be written, which is not worth reporting
+        let _ = thread.join();
+        show(self.style, &self.state, report);
+    }
+}
+
+impl Drop for ProgressReporter {
+    fn drop(&mut self) {
+        self.stop(Report::Aborted);
+    }
+}
+
+/// Which report of the progress of some work is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Report {
+    /// A report while the work is running.
+    Running,
+    /// The last report, after the work is done.
+    Finished,
+    /// The last report of work that stopped before it was done.
+    Aborted,
+}
+
+impl ProgressState {
+    fn completed(&self) -> i64 {
+        self.completed.load(Ordering::Relaxed).min(self.total)
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// The number of samples completed per second.
+    fn rate(&self) -> f64 {
+        let elapsed = self.elapsed();
+        if elapsed > 0.0 {
+            self.completed() as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// The estimated time until the work is complete, assuming that it continues at the same
+    /// rate, or `None` before anything is complete.
+    fn eta(&self) -> Option<f64> {
+        let completed = self.completed();
+        (completed > 0).then(|| self.elapsed() * (self.total - completed) as f64 / completed as f64)
+    }
+
+    /// The progress bar of the work, fitted to `width` columns.
+    fn bar(&self, width: usize, report: Report) -> String {
+        let times = match self.eta() {
+            _ if report == Report::Finished => format!("({:.1}s)", self.elapsed()),
+            _ if report == Report::Aborted => format!("({:.1}s, aborted)", self.elapsed()),
+            Some(eta) => format!("({:.1}s|{:.1}s)", self.elapsed(), eta),
+            None => format!("({:.1}s|?s)", self.elapsed()),
+        };
+        let rate = format!("{}samples/s", si_prefixed(self.rate()));
+        let bar_width = width
+            .saturating_sub(self.title.chars().count() + times.len() + rate.len() + 5)
+            .max(10);
+        let filled = if self.total > 0 {
+            (bar_width as f64 * self.completed() as f64 / self.total as f64) as usize
+        } else {
+            bar_width
+        };
+        format!(
+            "{} [{}{}] {times} {rate}",
+            self.title,
+            "+".repeat(filled),
+            " ".repeat(bar_width - filled)
+        )
+    }
+
+    fn json(&self, report: Report) -> String {
+        let mut json = String::from("{\"title\":");
+        push_json_string(&mut json, &self.title);
+        let eta = self
+            .eta()
+            .map_or("null".to_string(), |eta| format!("{eta:.3}"));
+        let _ = write!(
+            json,
+            ",\"completed\":{},\"total\":{},\"elapsed\":{:.3},\"eta\":{eta},\
+             \"samples_per_second\":{:.1},\"finished\":{},\"aborted\":{}}}",
+            self.completed(),
+            self.total,
+            self.elapsed(),
+            self.rate(),
+            report == Report::Finished,
+            report == Report::Aborted,
+        );
+        json
+    }
+}
+
+/// The progress bars that are shown, and the number of lines they took when last drawn.
+struct Bars {
+    active: Vec<Arc<ProgressState>>,
+    lines: usize,
+}
+
+static BARS: Mutex<Bars> = Mutex::new(Bars {
+    active: Vec::new(),
+    lines: 0,
+});
+
+fn bars() -> MutexGuard<'static, Bars> {
+    BARS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Shows the progress of `state`, for the last time unless the work is still running.
+fn show(style: ProgressStyle, state: &Arc<ProgressState>, report: Report) {
+    let finished = report != Report::Running;
+    match style {
+        ProgressStyle::Quiet => {}
+        // Progress is not worth failing for, so errors writing it, e.g. to a closed pipe, are
+        // ignored
+        ProgressStyle::Json => {
+            let _ = writeln!(io::stdout().lock(), "{}", state.json(report));
+        }
+        ProgressStyle::Bar => {
+            let mut bars = bars();
+            if finished {
+                bars.active.retain(|bar| !Arc::ptr_eq(bar, state));
+            }
+            let width = env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .unwrap_or(80);
+            let mut stdout = io::stdout().lock();
+
+            // Without a terminal to redraw the bars on, only show each of them once it is
+            // complete
+            if !stdout.is_terminal() {
+                if finished {
+                    let _ = writeln!(stdout, "{}", state.bar(width, report));
+                }
+                return;
+            }
+
+            // Redraw all the bars in place, leaving finished ones above those still active
+            let mut out = String::new();
+            if bars.lines > 0 {
+                let _ = write!(out, "\x1b[{}A", bars.lines);
+            }
+            out.push_str("\r\x1b[J");
+            if finished {
+                let _ = writeln!(out, "{}", state.bar(width, report));
+            }
+            for bar in &bars.active {
+                let _ = writeln!(out, "{}", bar.bar(width, Report::Running));
+            }
+            bars.lines = bars.active.len();
+            let _ = stdout.write_all(out.as_bytes());
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// Formats `value` with an SI prefix, as in `1.50M`.
+fn si_prefixed(value: f64) -> String {
+    const PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
+    let mut value = value;
+    let mut prefix = 0;
+    while value >= 1000.0 && prefix + 1 < PREFIXES.len() {
+        value /= 1000.0;
+        prefix += 1;
+    }
+    format!("{value:.2}{} ", PREFIXES[prefix])
+}
+
+fn push_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
@@ -1,8 +1,15 @@
 use crate::{
-    math::{points::Point3f, vectors::Vector3f, Float},
+    math::{
+        dot::Dot, interval::Point3fi, normals::Normal3f, points::Point3f, tuples::Tuple,
+        vectors::Vector3f, Float, FloatExt,
+    },
     media::Medium,
 };
 
+/// The fraction of a ray towards another point that is left out at its end, so that a shadow
+/// ray does not intersect the surface the point lies on.
+pub const SHADOW_EPSILON: Float = 0.0001;
+
 #[derive(Default, Clone)]
 pub struct Ray {
     pub o: Point3f,
@@ -91,3 +98,75 @@ impl From<Ray> for RayDifferential {
         }
     }
 }
+
+/// Offsets the point `pi` along the normal `n` so that a ray leaving it in direction `w` starts
+/// outside of the error bounds of `pi`, and cannot intersect the surface it was found on again.
+///
+/// # Examples
+///
+/// ```
+/// use lili::math::{
+///     interval::Point3fi, normals::Normal3f, points::Point3f, vectors::Vector3f,
+/// };
+/// use lili::rays::offset_ray_origin;
+///
+/// let pi = Point3fi::from_point_and_error(
+///     Point3f::new(0.0, 0.0, 1.0),
+///     Vector3f::new(1e-4, 1e-4, 1e-4),
+/// );
+/// let n = Normal3f::new(0.0, 0.0, 1.0);
+///
+/// let above = offset_ray_origin(&pi, &n, &Vector3f::new(0.0, 1.0, 1.0));
+/// assert!(above.z > pi.z.upper_bound());
+///
+/// let below = offset_ray_origin(&pi, &n, &Vector3f::new(0.0, 1.0, -1.0));
+/// assert!(below.z < pi.z.lower_bound());
+/// ```
+pub fn offset_ray_origin(pi: &Point3fi, n: &Normal3f, w: &Vector3f) -> Point3f {
+    // Move past the error box along the normal, to the side that w points to
+    let d = n.abs().dot(pi.error());
+    let mut offset = Vector3f::new(n.x * d, n.y * d, n.z * d);
+    if w.dot(n) < 0.0 {
+        offset = -offset;
+    }
+    let mut po = pi.midpoint() + offset;
+
+    // Round away from the surface, so that the rounding of the sum does not bring the point back
+    // inside the error box
+    for i in 0..3 {
+        if offset[i] > 0.0 {
+            po[i] = po[i].next_float_up();
+        } else if offset[i] < 0.0 {
+            po[i] = po[i].next_float_down();
+        }
+    }
+    po
+}
+
+/// Spawns a ray in direction `d` from the point `pi` on a surface with normal `n`.
+pub fn spawn_ray(pi: &Point3fi, n: &Normal3f, time: Float, d: Vector3f) -> Ray {
+    Ray::new_with_time(offset_ray_origin(pi, n, &d), d, time, Box::new(None))
+}
+
+/// Spawns a ray from the point `p_from` on a surface with normal `n` towards `p_to`.
+///
+/// The ray reaches `p_to` at `t = 1`, so intersections should be searched for up to
+/// `1 - SHADOW_EPSILON`.
+pub fn spawn_ray_to(p_from: &Point3fi, n: &Normal3f, time: Float, p_to: Point3f) -> Ray {
+    let o = offset_ray_origin(p_from, n, &(p_to - p_from.midpoint()));
+    Ray::new_with_time(o, p_to - o, time, Box::new(None))
+}
+
+/// Spawns a ray between two points on surfaces, offsetting both ends so that the ray intersects
+/// neither of the surfaces.
+pub fn spawn_ray_between(
+    p_from: &Point3fi,
+    n_from: &Normal3f,
+    time: Float,
+    p_to: &Point3fi,
+    n_to: &Normal3f,
+) -> Ray {
+    let pf = offset_ray_origin(p_from, n_from, &(p_to.midpoint() - p_from.midpoint()));
+    let pt = offset_ray_origin(p_to, n_to, &(pf - p_to.midpoint()));
+    Ray::new_with_time(pf, pt - pf, time, Box::new(None))
+}
@@ -0,0 +1,313 @@
+//! Samplers, which generate the sample values that rendering turns into camera rays, wavelengths,
+//! light samples and the like.
+mod halton;
+pub use halton::*;
+
+mod independent;
+pub use independent::*;
+
+mod pmj02bn;
+pub use pmj02bn::*;
+
+mod sobol;
+pub use sobol::*;
+
+mod stratified;
+pub use stratified::*;
+
+use crate::{
+    error::warning,
+    math::{
+        lowdiscrepancy::SobolScrambler,
+        points::{Point2f, Point2i},
+    },
+    scene::{parser::ParseError, SceneEntity},
+    Float, Options,
+};
+
+/// How low-discrepancy samplers randomize their points, which avoids the artifacts that the
+/// regular structure of the points would otherwise cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomizeStrategy {
+    None,
+    /// Randomly permutes the digits of the points, with one permutation for each digit.
+    PermuteDigits,
+    /// Owen scrambling restricted to base 2, which can be computed for all digits at once.
+    FastOwen,
+    /// Permutes each digit of the points according to the digits before it.
+    Owen,
+}
+
+impl RandomizeStrategy {
+    fn create(entity: &SceneEntity, default: &str) -> Result<Self, ParseError> {
+        match entity
+            .parameters
+            .get_one_string("randomization", default)?
+            .as_str()
+        {
+            "none" => Ok(Self::None),
+            "permutedigits" => Ok(Self::PermuteDigits),
+            "fastowen" => Ok(Self::FastOwen),
+            "owen" => Ok(Self::Owen),
+            name => Err(ParseError::new(
+                &entity.loc,
+                format!("\"{name}\": unknown randomization strategy"),
+            )),
+        }
+    }
+
+    /// The scrambler that randomizes the points of the Sobol sequence with the seed `hash`.
+    fn sobol_scrambler(self, hash: u32) -> SobolScrambler {
+        match self {
+            Self::None => SobolScrambler::None,
+            Self::PermuteDigits => SobolScrambler::BinaryPermute(hash),
+            Self::FastOwen => SobolScrambler::FastOwen(hash),
+            Self::Owen => SobolScrambler::Owen(hash),
+        }
+    }
+}
+
+/// A sampler, chosen with the `Sampler` directive.
+///
+/// Each sample of each pixel is a sequence of values, which are consumed one or two dimensions at
+/// a time after [`start_pixel_sample`](Self::start_pixel_sample). The values only depend on the
+/// pixel, the sample index and the dimension, so rendering is reproducible however its work is
+/// split between threads.
+///
+/// # Examples
+///
+/// ```
+/// use lili::{
+///     math::points::Point2i,
+///     sampler::{Sampler, StratifiedSampler},
+/// };
+///
+/// let mut sampler = Sampler::Stratified(StratifiedSampler::new(2, 2, true, 0));
+///
+/// // The four samples of a pixel each lie in a different quarter of it
+/// let mut quarters = Vec::new();
+/// for i in 0..sampler.samples_per_pixel() {
+///     sampler.start_pixel_sample(Point2i::new(3, 5), i, 0);
+///     let u = sampler.get_pixel_2d();
+///     quarters.push(((2.0 * u.x) as i32, (2.0 * u.y) as i32));
+/// }
+/// quarters.sort();
+/// assert_eq!(quarters, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+///
+/// // Starting the same sample again gives the same values
+/// sampler.start_pixel_sample(Point2i::new(3, 5), 2, 0);
+/// let u = sampler.get_1d();
+/// sampler.start_pixel_sample(Point2i::new(3, 5), 2, 0);
+/// assert_eq!(sampler.get_1d(), u);
+/// ```
+#[derive(Clone)]
+pub enum Sampler {
+    Halton(HaltonSampler),
+    Independent(IndependentSampler),
+    PMJ02BN(PMJ02BNSampler),
+    Sobol(SobolSampler),
+    Stratified(StratifiedSampler),
+    ZSobol(ZSobolSampler),
+}
+
+impl Sampler {
+    /// Creates the sampler described by a `Sampler` directive, for an image of `full_resolution`
+    /// pixels. The seed defaults to the one in the options.
+    pub fn create(
+        entity: &SceneEntity,
+        options: &Options,
+        full_resolution: Point2i,
+    ) -> Result<Self, ParseError> {
+        let params = &entity.parameters;
+        let seed = params.get_one_int("seed", options.seed as i32)?;
+
+        let sampler = match entity.name.as_str() {
+            "halton" => {
+                let samples_per_pixel = params.get_one_int("pixelsamples", 16)?;
+                let randomize = RandomizeStrategy::create(entity, "permutedigits")?;
+                if randomize == RandomizeStrategy::FastOwen {
+                    return Err(ParseError::new(
+                        &entity.loc,
+                        "\"fastowen\" randomization is not supported by the Halton sampler",
+                    ));
+                }
+                Self::Halton(HaltonSampler::new(
+                    samples_per_pixel,
+                    full_resolution,
+                    randomize,
+                    seed,
+                ))
+            }
+            "independent" => {
+                let samples_per_pixel = params.get_one_int("pixelsamples", 4)?;
+                Self::Independent(IndependentSampler::new(samples_per_pixel, seed))
+            }
+            "pmj02bn" => {
+                let mut samples_per_pixel = params.get_one_int("pixelsamples", 16)?;
+                if !(1..=PMJ02BNSampler::MAX_SAMPLES_PER_PIXEL).contains(&samples_per_pixel) {
+                    return Err(ParseError::new(
+                        &entity.loc,
+                        format!(
+                            "the PMJ02BN sampler supports 1 to {} pixel samples",
+                            PMJ02BNSampler::MAX_SAMPLES_PER_PIXEL
+                        ),
+                    ));
+                }
+                if !(samples_per_pixel as u32).is_power_of_two() {
+                    let rounded = (samples_per_pixel as u32).next_power_of_two() as i32;
+                    warning(
+                        Some(&entity.loc),
+                        format!(
+                            "the PMJ02BN sampler needs a power-of-two number of pixel samples; \
+                             rounding {samples_per_pixel} up to {rounded}"
+                        ),
+                    );
+                    samples_per_pixel = rounded;
+                }
+                Self::PMJ02BN(PMJ02BNSampler::new(samples_per_pixel, seed))
+            }
+            "sobol" | "zsobol" => {
+                let samples_per_pixel = params.get_one_int("pixelsamples", 16)?;
+                let randomize = RandomizeStrategy::create(entity, "fastowen")?;
+                if samples_per_pixel > 0 && !(samples_per_pixel as u32).is_power_of_two() {
+                    warning(
+                        Some(&entity.loc),
+                        format!(
+                            "Sobol samplers with non power-of-two sample counts \
+                             ({samples_per_pixel}) are sub-optimal"
+                        ),
+                    );
+                }
+                if entity.name == "sobol" {
+                    Self::Sobol(SobolSampler::new(
+                        samples_per_pixel,
+                        full_resolution,
+                        randomize,
+                        seed,
+                    ))
+                } else {
+                    Self::ZSobol(ZSobolSampler::new(
+                        samples_per_pixel,
+                        full_resolution,
+                        randomize,
+                        seed,
+                    ))
+                }
+            }
+            "stratified" => {
+                let x_samples = params.get_one_int("xsamples", 4)?;
+                let y_samples = params.get_one_int("ysamples", 4)?;
+                let jitter = params.get_one_bool("jitter", true)?;
+                if x_samples <= 0 || y_samples <= 0 {
+                    return Err(ParseError::new(
+                        &entity.loc,
+                        "the number of strata must be positive",
+                    ));
+                }
+                Self::Stratified(StratifiedSampler::new(x_samples, y_samples, jitter, seed))
+            }
+            name => {
+                return Err(ParseError::new(
+                    &entity.loc,
+                    format!("\"{name}\": unknown sampler type"),
+                ))
+            }
+        };
+        if sampler.samples_per_pixel() <= 0 {
+            return Err(ParseError::new(
+                &entity.loc,
+                "the number of pixel samples must be positive",
+            ));
+        }
+        params.report_unused()?;
+        Ok(sampler)
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        match self {
+            Self::Halton(s) => s.samples_per_pixel(),
+            Self::Independent(s) => s.samples_per_pixel(),
+            Self::PMJ02BN(s) => s.samples_per_pixel(),
+            Self::Sobol(s) => s.samples_per_pixel(),
+            Self::Stratified(s) => s.samples_per_pixel(),
+            Self::ZSobol(s) => s.samples_per_pixel(),
+        }
+    }
+
+    /// The seed that the sampler was created with, which the scene may set instead of the
+    /// options.
+    pub fn seed(&self) -> i32 {
+        match self {
+            Self::Halton(s) => s.seed(),
+            Self::Independent(s) => s.seed(),
+            Self::PMJ02BN(s) => s.seed(),
+            Self::Sobol(s) => s.seed(),
+            Self::Stratified(s) => s.seed(),
+            Self::ZSobol(s) => s.seed(),
+        }
+    }
+
+    /// The name of the sampler in the `Sampler` directive.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Halton(_) => "halton",
+            Self::Independent(_) => "independent",
+            Self::PMJ02BN(_) => "pmj02bn",
+            Self::Sobol(_) => "sobol",
+            Self::Stratified(_) => "stratified",
+            Self::ZSobol(_) => "zsobol",
+        }
+    }
+
+    /// Starts generating the values of sample `sample_index` of pixel `p`, from `dimension` on.
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        match self {
+            Self::Halton(s) => s.start_pixel_sample(p, sample_index, dimension),
+            Self::Independent(s) => s.start_pixel_sample(p, sample_index, dimension),
+            Self::PMJ02BN(s) => s.start_pixel_sample(p, sample_index, dimension),
+            Self::Sobol(s) => s.start_pixel_sample(p, sample_index, dimension),
+            Self::Stratified(s) => s.start_pixel_sample(p, sample_index, dimension),
+            Self::ZSobol(s) => s.start_pixel_sample(p, sample_index, dimension),
+        }
+    }
+
+    /// The value of the next dimension of the sample.
+    pub fn get_1d(&mut self) -> Float {
+        match self {
+            Self::Halton(s) => s.get_1d(),
+            Self::Independent(s) => s.get_1d(),
+            Self::PMJ02BN(s) => s.get_1d(),
+            Self::Sobol(s) => s.get_1d(),
+            Self::Stratified(s) => s.get_1d(),
+            Self::ZSobol(s) => s.get_1d(),
+        }
+    }
+
+    /// The values of the next two dimensions of the sample.
+    pub fn get_2d(&mut self) -> Point2f {
+        match self {
+            Self::Halton(s) => s.get_2d(),
+            Self::Independent(s) => s.get_2d(),
+            Self::PMJ02BN(s) => s.get_2d(),
+            Self::Sobol(s) => s.get_2d(),
+            Self::Stratified(s) => s.get_2d(),
+            Self::ZSobol(s) => s.get_2d(),
+        }
+    }
+
+    /// The values of the next two dimensions of the sample, which give the position of the
+    /// sample in its pixel.
+    ///
+    /// Samplers may distribute these better than other dimensions, since their values matter
+    /// most to the image.
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        match self {
+            Self::Halton(s) => s.get_pixel_2d(),
+            Self::Independent(s) => s.get_pixel_2d(),
+            Self::PMJ02BN(s) => s.get_pixel_2d(),
+            Self::Sobol(s) => s.get_pixel_2d(),
+            Self::Stratified(s) => s.get_pixel_2d(),
+            Self::ZSobol(s) => s.get_pixel_2d(),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! The sampler based on the Halton sequence.
+use std::sync::Arc;
+
+use crate::{
+    math::{
+        hash::hash,
+        lowdiscrepancy::{
+            compute_radical_inverse_permutations, inverse_radical_inverse, multiplicative_inverse,
+            owen_scrambled_radical_inverse, radical_inverse, scrambled_radical_inverse,
+            DigitPermutation,
+        },
+        points::{Point2f, Point2i},
+        PRIME_TABLE_SIZE,
+    },
+    sampler::RandomizeStrategy,
+    Float,
+};
+
+/// A sampler whose values are the points of the Halton sequence, which uses a prime base for each
+/// dimension.
+///
+/// The first two dimensions are spread over tiles of up to 128 by 128 pixels, so that each pixel
+/// gets the sample indices whose first two dimensions fall within it. The values of the other
+/// dimensions are randomized to avoid structured artifacts, and dimensions wrap back to the third
+/// one past the end of the prime table.
+#[derive(Clone)]
+pub struct HaltonSampler {
+    samples_per_pixel: i32,
+    randomize: RandomizeStrategy,
+    seed: i32,
+    digit_permutations: Option<Arc<Vec<DigitPermutation>>>,
+    /// The powers of 2 and 3 that scale the first two dimensions to pixels.
+    base_scales: [u64; 2],
+    base_exponents: [u32; 2],
+    mult_inverse: [u64; 2],
+    halton_index: u64,
+    dimension: usize,
+}
+
+impl HaltonSampler {
+    /// The largest tile of pixels that the first two dimensions are spread over.
+    const MAX_RESOLUTION: i32 = 128;
+
+    /// Creates a sampler for an image of `full_resolution` pixels.
+    ///
+    /// Panics if `randomize` is [`RandomizeStrategy::FastOwen`], which only applies to base 2.
+    pub fn new(
+        samples_per_pixel: i32,
+        full_resolution: Point2i,
+        randomize: RandomizeStrategy,
+        seed: i32,
+    ) -> Self {
+        assert!(
+            randomize != RandomizeStrategy::FastOwen,
+            "the Halton sampler does not support fast Owen scrambling"
+        );
+        let digit_permutations = (randomize == RandomizeStrategy::PermuteDigits)
+            .then(|| Arc::new(compute_radical_inverse_permutations(seed as u64)));
+
+        let mut base_scales = [1; 2];
+        let mut base_exponents = [0; 2];
+        for (i, (base, resolution)) in [(2, full_resolution.x), (3, full_resolution.y)]
+            .into_iter()
+            .enumerate()
+        {
+            while base_scales[i] < resolution.min(Self::MAX_RESOLUTION) as u64 {
+                base_scales[i] *= base;
+                base_exponents[i] += 1;
+            }
+        }
+        let mult_inverse = [
+            multiplicative_inverse(base_scales[1] as i64, base_scales[0] as i64) as u64,
+            multiplicative_inverse(base_scales[0] as i64, base_scales[1] as i64) as u64,
+        ];
+
+        Self {
+            samples_per_pixel,
+            randomize,
+            seed,
+            digit_permutations,
+            base_scales,
+            base_exponents,
+            mult_inverse,
+            halton_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.samples_per_pixel
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        // Find the first index whose first two dimensions fall within the pixel, by the Chinese
+        // remainder theorem, and step to the requested sample from there
+        let sample_stride = self.base_scales[0] * self.base_scales[1];
+        self.halton_index = 0;
+        if sample_stride > 1 {
+            let pm = [
+                p.x.rem_euclid(Self::MAX_RESOLUTION) as u64,
+                p.y.rem_euclid(Self::MAX_RESOLUTION) as u64,
+            ];
+            for (i, base) in [2, 3].into_iter().enumerate() {
+                let dim_offset = inverse_radical_inverse(pm[i], base, self.base_exponents[i]);
+                self.halton_index +=
+                    dim_offset * (sample_stride / self.base_scales[i]) * self.mult_inverse[i];
+            }
+            self.halton_index %= sample_stride;
+        }
+        self.halton_index += sample_index as u64 * sample_stride;
+        self.dimension = dimension.max(2) as usize;
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        if self.dimension >= PRIME_TABLE_SIZE {
+            self.dimension = 2;
+        }
+        self.dimension += 1;
+        self.sample_dimension(self.dimension - 1)
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        if self.dimension + 1 >= PRIME_TABLE_SIZE {
+            self.dimension = 2;
+        }
+        let dim = self.dimension;
+        self.dimension += 2;
+        Point2f::new(self.sample_dimension(dim), self.sample_dimension(dim + 1))
+    }
+
+    /// The position of the sample in its pixel, given by the first two dimensions scaled up to
+    /// pixels, which are never randomized.
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        Point2f::new(
+            radical_inverse(0, self.halton_index >> self.base_exponents[0]),
+            radical_inverse(1, self.halton_index / self.base_scales[1]),
+        )
+    }
+
+    fn sample_dimension(&self, dimension: usize) -> Float {
+        match self.randomize {
+            RandomizeStrategy::None => radical_inverse(dimension, self.halton_index),
+            RandomizeStrategy::PermuteDigits => {
+                let perms = self
+                    .digit_permutations
+                    .as_ref()
+                    .expect("digit permutations are computed for this strategy");
+                scrambled_radical_inverse(dimension, self.halton_index, &perms[dimension])
+            }
+            RandomizeStrategy::Owen | RandomizeStrategy::FastOwen => {
+                let hash = hash(&[dimension as i64, self.seed as i64]) as u32;
+                owen_scrambled_radical_inverse(dimension, self.halton_index, hash)
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! The sampler that generates uniformly distributed random values.
+use crate::{
+    math::{
+        hash::hash,
+        points::{Point2f, Point2i},
+        rng::Rng,
+    },
+    Float,
+};
+
+/// A sampler whose values are independent and uniformly distributed.
+///
+/// It converges slowly, but makes a useful baseline to compare other samplers to.
+#[derive(Clone)]
+pub struct IndependentSampler {
+    samples_per_pixel: i32,
+    seed: i32,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(samples_per_pixel: i32, seed: i32) -> Self {
+        Self {
+            samples_per_pixel,
+            seed,
+            rng: Rng::default(),
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.samples_per_pixel
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        start_rng(&mut self.rng, p, self.seed, sample_index, dimension);
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        self.rng.uniform_float()
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        Point2f::new(self.rng.uniform_float(), self.rng.uniform_float())
+    }
+
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        self.get_2d()
+    }
+}
+
+/// Moves `rng` to the values of a sample, in a sequence of its own for each pixel and seed.
+///
+/// Each sample may use up to 65536 random values before they overlap with those of the next
+/// sample.
+pub(super) fn start_rng(rng: &mut Rng, p: Point2i, seed: i32, sample_index: i32, dimension: i32) {
+    rng.set_sequence_index(hash(&[p.x as i64, p.y as i64, seed as i64]));
+    rng.advance(sample_index as i64 * 65536 + dimension as i64);
+}
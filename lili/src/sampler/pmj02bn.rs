@@ -0,0 +1,153 @@
+//! The sampler based on progressive multi-jittered (0, 2) point sets.
+use std::sync::Arc;
+
+use crate::math::{
+    hash::{hash, permutation_element},
+    pmj02tables::{get_pmj02bn_sample, N_PMJ02BN_SAMPLES, N_PMJ02BN_SETS},
+    points::{Point2f, Point2i},
+    Float, FloatExt,
+};
+
+/// A sampler whose pixel samples come from a set of blue noise (0, 2) points that covers a tile
+/// of pixels, so that the samples of each pixel are well distributed and those of neighboring
+/// pixels complement each other.
+///
+/// Other pairs of dimensions take the first points of the other sets, in a random order for each
+/// pixel and shifted by a random offset, and single dimensions are stratified with one stratum
+/// per sample. The number of samples per pixel must be a power of two, and works best as a power
+/// of four.
+#[derive(Clone)]
+pub struct PMJ02BNSampler {
+    samples_per_pixel: i32,
+    seed: i32,
+    pixel_tile_size: i32,
+    /// The samples of each pixel of the tile, one pixel after the other.
+    pixel_samples: Arc<Vec<Point2f>>,
+    pixel: Point2i,
+    sample_index: i32,
+    dimension: i32,
+}
+
+impl PMJ02BNSampler {
+    /// The largest number of samples per pixel.
+    pub const MAX_SAMPLES_PER_PIXEL: i32 = N_PMJ02BN_SAMPLES as i32;
+
+    pub fn new(samples_per_pixel: i32, seed: i32) -> Self {
+        assert!(
+            samples_per_pixel > 0
+                && samples_per_pixel <= Self::MAX_SAMPLES_PER_PIXEL
+                && (samples_per_pixel as u32).is_power_of_two(),
+            "invalid number of samples per pixel for the PMJ02BN sampler"
+        );
+
+        // Split the points of the first set between the pixels of a tile, which gets as many
+        // points as there are when the samples per pixel are rounded up to a power of four
+        let log4_samples = N_PMJ02BN_SAMPLES.ilog2() / 2;
+        let log4_samples_per_pixel = (samples_per_pixel as u32).ilog2().div_ceil(2);
+        let pixel_tile_size = 1 << (log4_samples - log4_samples_per_pixel);
+        let spp = samples_per_pixel as usize;
+        let mut pixel_samples =
+            vec![Point2f::new(0.0, 0.0); (pixel_tile_size * pixel_tile_size) as usize * spp];
+        let mut n_stored = vec![0; (pixel_tile_size * pixel_tile_size) as usize];
+        for i in 0..N_PMJ02BN_SAMPLES {
+            let p = get_pmj02bn_sample(0, i);
+            let (x, y) = (
+                p.x * pixel_tile_size as Float,
+                p.y * pixel_tile_size as Float,
+            );
+            let pixel_offset = x as usize + y as usize * pixel_tile_size as usize;
+            // With a power of two but not of four samples, each pixel gets twice as many points
+            // as it needs, and keeps the first ones
+            if n_stored[pixel_offset] == spp {
+                continue;
+            }
+            pixel_samples[pixel_offset * spp + n_stored[pixel_offset]] =
+                Point2f::new(x - x.floor(), y - y.floor());
+            n_stored[pixel_offset] += 1;
+        }
+        debug_assert!(n_stored.iter().all(|&n| n == spp));
+
+        Self {
+            samples_per_pixel,
+            seed,
+            pixel_tile_size,
+            pixel_samples: Arc::new(pixel_samples),
+            pixel: Point2i::new(0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.samples_per_pixel
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        self.pixel = p;
+        self.sample_index = sample_index;
+        self.dimension = dimension.max(2);
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        let index = self.permuted_index();
+        let delta = self.offset(self.dimension);
+        self.dimension += 1;
+        ((index as Float + delta) / self.samples_per_pixel as Float).min(Float::ONE_MINUS_EPSILON)
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        // The first set gives the pixel samples, and the others are reused past the last one
+        let set = 1 + (self.dimension as usize / 2) % (N_PMJ02BN_SETS - 1);
+        let index = self.permuted_index();
+        let u = get_pmj02bn_sample(set, index as usize);
+        let shift = |u: Float, delta: Float| {
+            let u = u + delta;
+            (if u >= 1.0 { u - 1.0 } else { u }).min(Float::ONE_MINUS_EPSILON)
+        };
+        let u = Point2f::new(
+            shift(u.x, self.offset(self.dimension)),
+            shift(u.y, self.offset(self.dimension + 1)),
+        );
+        self.dimension += 2;
+        u
+    }
+
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        let px = self.pixel.x.rem_euclid(self.pixel_tile_size);
+        let py = self.pixel.y.rem_euclid(self.pixel_tile_size);
+        let offset = (px + py * self.pixel_tile_size) * self.samples_per_pixel;
+        self.pixel_samples[(offset + self.sample_index) as usize]
+    }
+
+    /// The index of the point that the current sample uses for the current dimension, which is
+    /// shuffled for each pixel and dimension.
+    fn permuted_index(&self) -> u32 {
+        let hash = hash(&[
+            self.pixel.x as i64,
+            self.pixel.y as i64,
+            self.dimension as i64,
+            self.seed as i64,
+        ]);
+        permutation_element(
+            self.sample_index as u32,
+            self.samples_per_pixel as u32,
+            hash as u32,
+        )
+    }
+
+    /// A random offset in `[0, 1)` for the samples of the current pixel in `dimension`.
+    fn offset(&self, dimension: i32) -> Float {
+        let hash = hash(&[
+            self.pixel.x as i64,
+            self.pixel.y as i64,
+            dimension as i64,
+            self.seed as i64,
+            1,
+        ]);
+        ((hash >> 40) as Float * (2.0 as Float).powi(-24)).min(Float::ONE_MINUS_EPSILON)
+    }
+}
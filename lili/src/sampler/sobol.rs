@@ -0,0 +1,245 @@
+//! The samplers based on the Sobol sequence.
+use crate::{
+    math::{
+        hash::{hash, mix_bits},
+        lowdiscrepancy::{sobol_sample, SobolIntervalToIndex, SobolScrambler},
+        points::{Point2f, Point2i},
+        sobolmatrices::N_SOBOL_DIMENSIONS,
+        Float, FloatExt,
+    },
+    sampler::RandomizeStrategy,
+};
+
+/// A sampler whose values are the points of the Sobol sequence.
+///
+/// As for the [`HaltonSampler`](super::HaltonSampler), the first two dimensions are spread over
+/// the image, so that each pixel gets the points that fall within it. They are spread over a
+/// square whose side is the smallest power of two that covers the image, and dimensions wrap back
+/// to the third one past the last Sobol dimension. The sampler works best with a power-of-two
+/// number of samples per pixel.
+#[derive(Clone)]
+pub struct SobolSampler {
+    samples_per_pixel: i32,
+    scale: i32,
+    seed: i32,
+    randomize: RandomizeStrategy,
+    interval_to_index: SobolIntervalToIndex,
+    pixel: Point2i,
+    dimension: usize,
+    sobol_index: u64,
+}
+
+impl SobolSampler {
+    pub fn new(
+        samples_per_pixel: i32,
+        full_resolution: Point2i,
+        randomize: RandomizeStrategy,
+        seed: i32,
+    ) -> Self {
+        let scale = (full_resolution.x.max(full_resolution.y).max(1) as u32).next_power_of_two();
+        Self {
+            samples_per_pixel,
+            scale: scale as i32,
+            seed,
+            randomize,
+            interval_to_index: SobolIntervalToIndex::new(scale.ilog2()),
+            pixel: Point2i::new(0, 0),
+            dimension: 0,
+            sobol_index: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.samples_per_pixel
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        self.pixel = p;
+        self.dimension = dimension.max(2) as usize;
+        self.sobol_index = self.interval_to_index.index(sample_index as u64, p);
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        if self.dimension >= N_SOBOL_DIMENSIONS {
+            self.dimension = 2;
+        }
+        self.dimension += 1;
+        self.sample_dimension(self.dimension - 1)
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        if self.dimension + 1 >= N_SOBOL_DIMENSIONS {
+            self.dimension = 2;
+        }
+        let dim = self.dimension;
+        self.dimension += 2;
+        Point2f::new(self.sample_dimension(dim), self.sample_dimension(dim + 1))
+    }
+
+    /// The position of the sample in its pixel, given by the first two dimensions scaled up to
+    /// pixels, which are never randomized.
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        let u = |dimension: usize, p: i32| {
+            let u = sobol_sample(self.sobol_index, dimension, SobolScrambler::None);
+            (u * self.scale as Float - p as Float).clamp(0.0, Float::ONE_MINUS_EPSILON)
+        };
+        Point2f::new(u(0, self.pixel.x), u(1, self.pixel.y))
+    }
+
+    fn sample_dimension(&self, dimension: usize) -> Float {
+        let hash = hash(&[dimension as i64, self.seed as i64]) as u32;
+        sobol_sample(
+            self.sobol_index,
+            dimension,
+            self.randomize.sobol_scrambler(hash),
+        )
+    }
+}
+
+/// A sampler whose values are points of the Sobol sequence, with the samples of the pixels
+/// ordered along a Morton curve and randomly shuffled, so that neighboring pixels get well
+/// distributed samples and the error of the image is spread as blue noise.
+///
+/// Each dimension takes the first two dimensions of the Sobol sequence, scrambled differently, so
+/// its sample counts should be powers of two, and preferably of four.
+#[derive(Clone)]
+pub struct ZSobolSampler {
+    samples_per_pixel: i32,
+    seed: i32,
+    randomize: RandomizeStrategy,
+    log2_samples_per_pixel: u32,
+    n_base4_digits: u32,
+    morton_index: u64,
+    dimension: u32,
+}
+
+impl ZSobolSampler {
+    pub fn new(
+        samples_per_pixel: i32,
+        full_resolution: Point2i,
+        randomize: RandomizeStrategy,
+        seed: i32,
+    ) -> Self {
+        let log2_samples_per_pixel = (samples_per_pixel.max(1) as u32).ilog2();
+        let resolution =
+            (full_resolution.x.max(full_resolution.y).max(1) as u32).next_power_of_two();
+        let log4_samples_per_pixel = log2_samples_per_pixel.div_ceil(2);
+        Self {
+            samples_per_pixel,
+            seed,
+            randomize,
+            log2_samples_per_pixel,
+            n_base4_digits: resolution.ilog2() + log4_samples_per_pixel,
+            morton_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.samples_per_pixel
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        self.dimension = dimension as u32;
+        self.morton_index = (encode_morton2(p.x as u32, p.y as u32) << self.log2_samples_per_pixel)
+            | sample_index as u64;
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        let sample_index = self.sample_index();
+        self.dimension += 1;
+        let hash = hash(&[self.dimension as i64, self.seed as i64]) as u32;
+        sobol_sample(sample_index, 0, self.randomize.sobol_scrambler(hash))
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        let sample_index = self.sample_index();
+        self.dimension += 2;
+        let bits = hash(&[self.dimension as i64, self.seed as i64]);
+        let hashes = [bits as u32, (bits >> 32) as u32];
+        Point2f::new(
+            sobol_sample(sample_index, 0, self.randomize.sobol_scrambler(hashes[0])),
+            sobol_sample(sample_index, 1, self.randomize.sobol_scrambler(hashes[1])),
+        )
+    }
+
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        self.get_2d()
+    }
+
+    /// The index of the Sobol point of the current sample and dimension.
+    ///
+    /// The base-4 digits of the Morton index of the sample are each randomly permuted according
+    /// to the digits above them, which shuffles the order in which the quadrants of each level of
+    /// the curve are visited.
+    fn sample_index(&self) -> u64 {
+        const PERMUTATIONS: [[u8; 4]; 24] = [
+            [0, 1, 2, 3],
+            [0, 1, 3, 2],
+            [0, 2, 1, 3],
+            [0, 2, 3, 1],
+            [0, 3, 2, 1],
+            [0, 3, 1, 2],
+            [1, 0, 2, 3],
+            [1, 0, 3, 2],
+            [1, 2, 0, 3],
+            [1, 2, 3, 0],
+            [1, 3, 2, 0],
+            [1, 3, 0, 2],
+            [2, 1, 0, 3],
+            [2, 1, 3, 0],
+            [2, 0, 1, 3],
+            [2, 0, 3, 1],
+            [2, 3, 0, 1],
+            [2, 3, 1, 0],
+            [3, 1, 2, 0],
+            [3, 1, 0, 2],
+            [3, 2, 1, 0],
+            [3, 2, 0, 1],
+            [3, 0, 2, 1],
+            [3, 0, 1, 2],
+        ];
+
+        // With an odd power of two samples per pixel, the last digit is in base 2
+        let pow2_samples = self.log2_samples_per_pixel & 1;
+        let dimension_bits = 0x55555555_u64 * self.dimension as u64;
+        let mut sample_index = 0;
+        for i in (pow2_samples..self.n_base4_digits).rev() {
+            let digit_shift = 2 * i - pow2_samples;
+            let digit = (self.morton_index >> digit_shift) & 3;
+            let higher_digits = self.morton_index >> (digit_shift + 2);
+            let p = (mix_bits(higher_digits ^ dimension_bits) >> 24) % 24;
+            let digit = PERMUTATIONS[p as usize][digit as usize] as u64;
+            sample_index |= digit << digit_shift;
+        }
+        if pow2_samples != 0 {
+            let digit = self.morton_index & 1;
+            sample_index |= digit ^ (mix_bits((self.morton_index >> 1) ^ dimension_bits) & 1);
+        }
+        sample_index
+    }
+}
+
+/// Interleaves the bits of `x` and `y`, starting with the lowest bit of `x`.
+fn encode_morton2(x: u32, y: u32) -> u64 {
+    (left_shift2(y as u64) << 1) | left_shift2(x as u64)
+}
+
+/// Spreads the bits of `x` out to the even bits of the result.
+fn left_shift2(mut x: u64) -> u64 {
+    x &= 0xffffffff;
+    x = (x ^ (x << 16)) & 0x0000ffff0000ffff;
+    x = (x ^ (x << 8)) & 0x00ff00ff00ff00ff;
+    x = (x ^ (x << 4)) & 0x0f0f0f0f0f0f0f0f;
+    x = (x ^ (x << 2)) & 0x3333333333333333;
+    x = (x ^ (x << 1)) & 0x5555555555555555;
+    x
+}
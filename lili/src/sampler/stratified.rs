@@ -0,0 +1,104 @@
+//! The sampler that divides each dimension of the pixel samples into strata.
+use crate::{
+    math::{
+        hash::{hash, permutation_element},
+        points::{Point2f, Point2i},
+        rng::Rng,
+    },
+    sampler::independent::start_rng,
+    Float,
+};
+
+/// A sampler that divides the pixel into a grid of `x_samples` by `y_samples` strata and places
+/// one sample in each.
+///
+/// Other dimensions are divided into as many strata as there are samples, and the strata are
+/// shuffled independently for each dimension, so that the values of different dimensions are not
+/// correlated. Without jitter, samples are at the centers of their strata.
+#[derive(Clone)]
+pub struct StratifiedSampler {
+    x_samples: i32,
+    y_samples: i32,
+    jitter: bool,
+    seed: i32,
+    rng: Rng,
+    pixel: Point2i,
+    sample_index: i32,
+    dimension: i32,
+}
+
+impl StratifiedSampler {
+    pub fn new(x_samples: i32, y_samples: i32, jitter: bool, seed: i32) -> Self {
+        Self {
+            x_samples,
+            y_samples,
+            jitter,
+            seed,
+            rng: Rng::default(),
+            pixel: Point2i::new(0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> i32 {
+        self.x_samples * self.y_samples
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    pub fn start_pixel_sample(&mut self, p: Point2i, sample_index: i32, dimension: i32) {
+        self.pixel = p;
+        self.sample_index = sample_index;
+        self.dimension = dimension;
+        start_rng(&mut self.rng, p, self.seed, sample_index, dimension);
+    }
+
+    pub fn get_1d(&mut self) -> Float {
+        let spp = self.samples_per_pixel();
+        let stratum = self.stratum(1);
+        let delta = self.jitter_offset();
+        (stratum as Float + delta) / spp as Float
+    }
+
+    pub fn get_2d(&mut self) -> Point2f {
+        let stratum = self.stratum(2) as i32;
+        let (x, y) = (stratum % self.x_samples, stratum / self.x_samples);
+        let (dx, dy) = (self.jitter_offset(), self.jitter_offset());
+        Point2f::new(
+            (x as Float + dx) / self.x_samples as Float,
+            (y as Float + dy) / self.y_samples as Float,
+        )
+    }
+
+    pub fn get_pixel_2d(&mut self) -> Point2f {
+        self.get_2d()
+    }
+
+    /// The stratum of the current sample for the next `n` dimensions.
+    fn stratum(&mut self, n: i32) -> u32 {
+        let hash = hash(&[
+            self.pixel.x as i64,
+            self.pixel.y as i64,
+            self.dimension as i64,
+            self.seed as i64,
+        ]);
+        self.dimension += n;
+        permutation_element(
+            self.sample_index as u32,
+            self.samples_per_pixel() as u32,
+            hash as u32,
+        )
+    }
+
+    /// The position of the sample within its stratum.
+    fn jitter_offset(&mut self) -> Float {
+        if self.jitter {
+            self.rng.uniform_float()
+        } else {
+            0.5
+        }
+    }
+}
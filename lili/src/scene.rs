@@ -1,11 +1,1155 @@
-use std::ffi::OsString;
+//! Scene descriptions and the builder that creates them from pbrt-v4 scene files.
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    path::PathBuf,
+};
 
-pub struct BasicScene {}
+use crate::{
+    color::RgbColorSpace,
+    error::{self, FileLoc},
+    math::{hash::murmur_hash64a, points::Point3f, transform::Transform, vectors::Vector3f},
+    Float,
+};
 
-pub struct BasicSceneBuilder {}
+pub mod parameters;
+use parameters::ParameterDictionary;
+
+pub mod parser;
+use parser::{ActiveTransform, ParseError, ParsedParameter, ParserTarget};
+
+pub mod tokenizer;
+
+/// An object declared in a scene file, such as a camera, a shape or a light.
+#[derive(Debug, Clone)]
+pub struct SceneEntity {
+    /// The implementation of the entity, e.g. `perspective` for a camera or `sphere` for a shape.
+    pub name: String,
+    /// Where the entity was declared.
+    pub loc: FileLoc,
+    pub parameters: ParameterDictionary,
+}
+
+impl SceneEntity {
+    /// Creates an entity, checking that its parameters are well-formed. RGB values among the
+    /// parameters are interpreted in `color_space`.
+    pub fn new(
+        name: &str,
+        parameters: Vec<ParsedParameter>,
+        color_space: &'static RgbColorSpace,
+        loc: FileLoc,
+    ) -> Result<Self, ParseError> {
+        Ok(Self {
+            name: name.to_string(),
+            loc,
+            parameters: ParameterDictionary::new(parameters, color_space)?,
+        })
+    }
+
+    /// Creates an entity whose parameters are completed by those given with `Attribute`.
+    fn with_attributes(
+        name: &str,
+        parameters: Vec<ParsedParameter>,
+        attributes: &ParameterDictionary,
+        color_space: &'static RgbColorSpace,
+        loc: FileLoc,
+    ) -> Result<Self, ParseError> {
+        let mut entity = Self::new(name, parameters, color_space, loc)?;
+        entity.parameters.extend(attributes);
+        Ok(entity)
+    }
+
+    fn with_default_name(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            loc: FileLoc::default(),
+            parameters: ParameterDictionary::default(),
+        }
+    }
+}
+
+/// The transformation of an entity at the start and at the end of the shutter interval.
+///
+/// Entities only move if the two transformations differ, which happens when `ActiveTransform`
+/// was used to give them separately.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionTransform {
+    pub start_transform: Transform,
+    pub start_time: Float,
+    pub end_transform: Transform,
+    pub end_time: Float,
+}
+
+impl Default for MotionTransform {
+    fn default() -> Self {
+        Self {
+            start_transform: Transform::default(),
+            start_time: 0.0,
+            end_transform: Transform::default(),
+            end_time: 1.0,
+        }
+    }
+}
+
+impl MotionTransform {
+    /// Returns `true` if the transformation changes over the shutter interval.
+    pub fn is_animated(&self) -> bool {
+        self.start_transform != self.end_transform
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            start_transform: self.start_transform.inverse(),
+            end_transform: self.end_transform.inverse(),
+            ..*self
+        }
+    }
+}
+
+/// An entity that is placed in the scene by the current transformation, such as a medium.
+#[derive(Debug, Clone)]
+pub struct TransformedSceneEntity {
+    pub entity: SceneEntity,
+    pub world_from_object: MotionTransform,
+}
+
+/// The camera, declared by a `Camera` directive.
+#[derive(Debug, Clone)]
+pub struct CameraSceneEntity {
+    pub entity: SceneEntity,
+    pub world_from_camera: MotionTransform,
+    /// The medium the camera is in, or an empty string if it is in a vacuum.
+    pub medium: String,
+}
+
+impl CameraSceneEntity {
+    fn with_default_name(name: &str) -> Self {
+        Self {
+            entity: SceneEntity::with_default_name(name),
+            world_from_camera: MotionTransform::default(),
+            medium: String::new(),
+        }
+    }
+}
+
+/// A light source, declared by a `LightSource` directive.
+#[derive(Debug, Clone)]
+pub struct LightSceneEntity {
+    pub entity: SceneEntity,
+    pub world_from_light: MotionTransform,
+    /// The medium the light is in, or an empty string if it is in a vacuum.
+    pub medium: String,
+}
+
+/// The material of a shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaterialRef {
+    /// An index into [`BasicScene::materials`].
+    Index(usize),
+    /// The name of a material declared with `MakeNamedMaterial`.
+    Named(String),
+}
+
+/// A shape, declared by a `Shape` directive, along with the graphics state it was declared in.
+///
+/// # Examples
+///
+/// ```
+/// use lili::scene::{BasicSceneBuilder, MaterialRef};
+///
+/// let scene = BasicSceneBuilder::parse_string(
+///     r#"
+///     WorldBegin
+///     AttributeBegin
+///         Translate 1 2 3
+///         ReverseOrientation
+///         Material "conductor"
+///         Shape "sphere"
+///     AttributeEnd
+///     Shape "disk"
+///     "#,
+/// )
+/// .unwrap();
+///
+/// let sphere = &scene.shapes[0];
+/// assert_eq!(sphere.world_from_object.start_transform.matrix()[1][3], 2.0);
+/// assert!(sphere.reverse_orientation);
+/// assert_eq!(sphere.material, MaterialRef::Index(1));
+///
+/// let disk = &scene.shapes[1];
+/// assert!(disk.world_from_object.start_transform.is_identity());
+/// assert!(!disk.reverse_orientation);
+/// assert_eq!(disk.material, MaterialRef::Index(0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShapeSceneEntity {
+    pub entity: SceneEntity,
+    pub world_from_object: MotionTransform,
+    /// Whether `ReverseOrientation` flipped the direction of the shape's normals.
+    pub reverse_orientation: bool,
+    pub material: MaterialRef,
+    /// The index into [`BasicScene::area_lights`] of the area light that the shape emits, if any.
+    pub light_index: Option<usize>,
+    /// The medium inside the shape, or an empty string for a vacuum.
+    pub inside_medium: String,
+    /// The medium outside the shape, or an empty string for a vacuum.
+    pub outside_medium: String,
+}
+
+/// A named texture, declared by a `Texture` directive.
+#[derive(Debug, Clone)]
+pub struct TextureSceneEntity {
+    /// The name that materials use to refer to the texture.
+    pub tex_name: String,
+    /// The texture implementation and its parameters.
+    pub entity: SceneEntity,
+    pub world_from_texture: MotionTransform,
+}
+
+/// A collection of shapes declared between `ObjectBegin` and `ObjectEnd`.
+#[derive(Debug, Clone)]
+pub struct InstanceDefinitionSceneEntity {
+    pub name: String,
+    pub loc: FileLoc,
+    pub shapes: Vec<ShapeSceneEntity>,
+}
+
+/// A use of an object instance, declared by an `ObjectInstance` directive.
+#[derive(Debug, Clone)]
+pub struct InstanceSceneEntity {
+    pub name: String,
+    pub loc: FileLoc,
+    pub world_from_instance: MotionTransform,
+}
+
+/// An `Option` directive that overrides a command line option.
+#[derive(Debug, Clone)]
+pub struct SceneOption {
+    pub name: String,
+    pub value: String,
+    pub loc: FileLoc,
+}
+
+/// Every entity of a parsed scene description.
+///
+/// Entities that are not given in the scene file keep pbrt's defaults, e.g. a `perspective`
+/// camera and a `gaussian` filter. The first material is the `diffuse` material that shapes use
+/// until another one is set.
+#[derive(Debug, Clone)]
+pub struct BasicScene {
+    pub options: Vec<SceneOption>,
+    pub camera: CameraSceneEntity,
+    pub film: SceneEntity,
+    pub sampler: SceneEntity,
+    pub filter: SceneEntity,
+    pub integrator: SceneEntity,
+    pub accelerator: SceneEntity,
+    pub named_media: Vec<(String, TransformedSceneEntity)>,
+    pub float_textures: Vec<TextureSceneEntity>,
+    pub spectrum_textures: Vec<TextureSceneEntity>,
+    pub materials: Vec<SceneEntity>,
+    pub named_materials: Vec<(String, SceneEntity)>,
+    pub lights: Vec<LightSceneEntity>,
+    pub area_lights: Vec<SceneEntity>,
+    pub shapes: Vec<ShapeSceneEntity>,
+    pub instance_definitions: HashMap<String, InstanceDefinitionSceneEntity>,
+    pub instances: Vec<InstanceSceneEntity>,
+    /// A hash of the text of the scene description files, which tells renders of different
+    /// scenes apart.
+    pub fingerprint: u64,
+}
+
+impl Default for BasicScene {
+    fn default() -> Self {
+        Self {
+            options: Vec::new(),
+            camera: CameraSceneEntity::with_default_name("perspective"),
+            film: SceneEntity::with_default_name("rgb"),
+            sampler: SceneEntity::with_default_name("zsobol"),
+            filter: SceneEntity::with_default_name("gaussian"),
+            integrator: SceneEntity::with_default_name("volpath"),
+            accelerator: SceneEntity::with_default_name("bvh"),
+            named_media: Vec::new(),
+            float_textures: Vec::new(),
+            spectrum_textures: Vec::new(),
+            materials: vec![SceneEntity::with_default_name("diffuse")],
+            named_materials: Vec::new(),
+            lights: Vec::new(),
+            area_lights: Vec::new(),
+            shapes: Vec::new(),
+            instance_definitions: HashMap::new(),
+            instances: Vec::new(),
+            fingerprint: 0,
+        }
+    }
+}
+
+/// Option names accepted by the `Option` directive.
+const SCENE_OPTIONS: [&str; 11] = [
+    "disablepixeljitter",
+    "disabletexturefiltering",
+    "disablewavelengthjitter",
+    "displacementedgescale",
+    "forcediffuse",
+    "msereferenceimage",
+    "msereferenceout",
+    "pixelstats",
+    "rendercoordsys",
+    "seed",
+    "wavefront",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockState {
+    OptionsBlock,
+    WorldBlock,
+}
+
+/// The number of current transformations, one for each end of the shutter interval.
+const MAX_TRANSFORMS: usize = 2;
+
+type TransformSet = [Transform; MAX_TRANSFORMS];
+
+/// The state that directives modify and that entities take on when they are declared.
+#[derive(Debug, Clone)]
+struct GraphicsState {
+    ctm: TransformSet,
+    active_transform: ActiveTransform,
+    transform_start_time: Float,
+    transform_end_time: Float,
+    reverse_orientation: bool,
+    material: MaterialRef,
+    area_light: Option<SceneEntity>,
+    inside_medium: String,
+    outside_medium: String,
+    shape_attributes: ParameterDictionary,
+    light_attributes: ParameterDictionary,
+    material_attributes: ParameterDictionary,
+    medium_attributes: ParameterDictionary,
+    texture_attributes: ParameterDictionary,
+    color_space: &'static RgbColorSpace,
+}
+
+impl Default for GraphicsState {
+    fn default() -> Self {
+        Self {
+            ctm: TransformSet::default(),
+            active_transform: ActiveTransform::All,
+            transform_start_time: 0.0,
+            transform_end_time: 1.0,
+            reverse_orientation: false,
+            material: MaterialRef::Index(0),
+            area_light: None,
+            inside_medium: String::new(),
+            outside_medium: String::new(),
+            shape_attributes: ParameterDictionary::default(),
+            light_attributes: ParameterDictionary::default(),
+            material_attributes: ParameterDictionary::default(),
+            medium_attributes: ParameterDictionary::default(),
+            texture_attributes: ParameterDictionary::default(),
+            color_space: RgbColorSpace::srgb(),
+        }
+    }
+}
+
+impl GraphicsState {
+    /// Replaces each active current transformation with the result of `f`.
+    fn for_active_transforms(&mut self, f: impl Fn(&Transform) -> Transform) {
+        let active = match self.active_transform {
+            ActiveTransform::StartTime => [true, false],
+            ActiveTransform::EndTime => [false, true],
+            ActiveTransform::All => [true, true],
+        };
+
+        for (t, active) in self.ctm.iter_mut().zip(active) {
+            if active {
+                *t = f(t);
+            }
+        }
+    }
+
+    fn motion_transform(&self, transforms: &TransformSet) -> MotionTransform {
+        MotionTransform {
+            start_transform: transforms[0],
+            start_time: self.transform_start_time,
+            end_transform: transforms[1],
+            end_time: self.transform_end_time,
+        }
+    }
+
+    /// The current transformation, which maps from object space to world space.
+    fn world_from_object(&self) -> MotionTransform {
+        self.motion_transform(&self.ctm)
+    }
+}
+
+/// What pushed a graphics state onto the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushedBy {
+    AttributeBegin,
+    ObjectBegin,
+}
+
+/// Builds a [`BasicScene`] from the directives of a scene description.
+pub struct BasicSceneBuilder {
+    scene: BasicScene,
+    current_block: BlockState,
+    graphics_state: GraphicsState,
+    pushed_graphics_states: Vec<(GraphicsState, PushedBy, FileLoc)>,
+    pushed_transforms: Vec<(TransformSet, ActiveTransform, FileLoc)>,
+    named_coordinate_systems: HashMap<String, TransformSet>,
+    named_material_names: HashSet<String>,
+    medium_names: HashSet<String>,
+    float_texture_names: HashSet<String>,
+    spectrum_texture_names: HashSet<String>,
+    active_instance_definition: Option<InstanceDefinitionSceneEntity>,
+}
+
+impl Default for BasicSceneBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl BasicSceneBuilder {
-    pub fn parse_files(filenames: Vec<OsString>) -> BasicScene {
-        BasicScene {}
+    pub fn new() -> Self {
+        Self {
+            scene: BasicScene::default(),
+            current_block: BlockState::OptionsBlock,
+            graphics_state: GraphicsState::default(),
+            pushed_graphics_states: Vec::new(),
+            pushed_transforms: Vec::new(),
+            named_coordinate_systems: HashMap::new(),
+            named_material_names: HashSet::new(),
+            medium_names: HashSet::new(),
+            float_texture_names: HashSet::new(),
+            spectrum_texture_names: HashSet::new(),
+            active_instance_definition: None,
+        }
+    }
+
+    /// Parses the given pbrt-v4 scene files, in order, into a single scene.
+    ///
+    /// If no files are given, the scene is read from standard input.
+    pub fn parse_files(filenames: Vec<OsString>) -> Result<BasicScene, ParseError> {
+        let filenames = filenames.into_iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        let mut builder = Self::new();
+        parser::parse_files(&mut builder, &filenames)?;
+        Ok(builder.scene)
+    }
+
+    /// Parses a pbrt-v4 scene description held in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::scene::BasicSceneBuilder;
+    ///
+    /// let scene = BasicSceneBuilder::parse_string(
+    ///     r#"
+    ///     Camera "orthographic"
+    ///     WorldBegin
+    ///     Shape "sphere" "float radius" 2
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(scene.camera.entity.name, "orthographic");
+    /// assert_eq!(scene.shapes[0].entity.parameters.get_one_float("radius", 1.0).unwrap(), 2.0);
+    ///
+    /// let error = BasicSceneBuilder::parse_string("WorldBegin\nCamera \"pinhole\"").unwrap_err();
+    /// assert_eq!((error.loc.line, error.loc.column), (2, 1));
+    /// ```
+    pub fn parse_string(source: &str) -> Result<BasicScene, ParseError> {
+        let mut builder = Self::new();
+        parser::parse_string(&mut builder, source)?;
+        Ok(builder.scene)
+    }
+
+    fn verify_options(&self, directive: &str, loc: &FileLoc) -> Result<(), ParseError> {
+        if self.current_block == BlockState::WorldBlock {
+            return Err(ParseError::new(
+                loc,
+                format!("options cannot be set inside world block; \"{directive}\" not allowed"),
+            ));
+        }
+        Ok(())
+    }
+
+    fn verify_world(&self, directive: &str, loc: &FileLoc) -> Result<(), ParseError> {
+        if self.current_block == BlockState::OptionsBlock {
+            return Err(ParseError::new(
+                loc,
+                format!(
+                    "scene description must be inside world block; \"{directive}\" not allowed"
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Restores the graphics state saved by the matching `AttributeBegin` or `ObjectBegin`.
+    fn pop_graphics_state(&mut self, end: PushedBy, loc: &FileLoc) -> Result<(), ParseError> {
+        let Some((state, begin, begin_loc)) = self.pushed_graphics_states.pop() else {
+            return Ok(());
+        };
+
+        if begin != end {
+            let (begin, end) = match begin {
+                PushedBy::AttributeBegin => ("AttributeBegin", "ObjectEnd"),
+                PushedBy::ObjectBegin => ("ObjectBegin", "AttributeEnd"),
+            };
+            return Err(ParseError::new(
+                loc,
+                format!("mismatched nesting: open {begin} from {begin_loc} at {end}"),
+            ));
+        }
+
+        self.graphics_state = state;
+        Ok(())
+    }
+}
+
+impl ParserTarget for BasicSceneBuilder {
+    fn option(&mut self, name: &str, value: &str, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_options("Option", &loc)?;
+
+        // The option may be declared with a type, as in "bool disablepixeljitter".
+        let name = name.split_whitespace().last().unwrap_or_default();
+        let name = name.to_lowercase().replace(['-', '_'], "");
+        if !SCENE_OPTIONS.contains(&name.as_str()) {
+            return Err(ParseError::new(&loc, format!("{name}: unknown option")));
+        }
+
+        self.scene.options.push(SceneOption {
+            name,
+            value: value.to_string(),
+            loc,
+        });
+        Ok(())
+    }
+
+    fn identity(&mut self, _loc: FileLoc) -> Result<(), ParseError> {
+        self.graphics_state
+            .for_active_transforms(|_| Transform::default());
+        Ok(())
+    }
+
+    fn translate(
+        &mut self,
+        dx: Float,
+        dy: Float,
+        dz: Float,
+        _loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        let translate = Transform::translate(Vector3f::new(dx, dy, dz));
+        self.graphics_state
+            .for_active_transforms(|t| *t * translate);
+        Ok(())
+    }
+
+    fn rotate(
+        &mut self,
+        angle: Float,
+        ax: Float,
+        ay: Float,
+        az: Float,
+        _loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        let rotate = Transform::rotate(angle, Vector3f::new(ax, ay, az));
+        self.graphics_state.for_active_transforms(|t| *t * rotate);
+        Ok(())
+    }
+
+    fn scale(&mut self, sx: Float, sy: Float, sz: Float, _loc: FileLoc) -> Result<(), ParseError> {
+        let scale = Transform::scale(sx, sy, sz);
+        self.graphics_state.for_active_transforms(|t| *t * scale);
+        Ok(())
+    }
+
+    fn look_at(
+        &mut self,
+        ex: Float,
+        ey: Float,
+        ez: Float,
+        lx: Float,
+        ly: Float,
+        lz: Float,
+        ux: Float,
+        uy: Float,
+        uz: Float,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        let look_at = Transform::look_at(
+            Point3f::new(ex, ey, ez),
+            Point3f::new(lx, ly, lz),
+            Vector3f::new(ux, uy, uz),
+        )
+        .ok_or_else(|| {
+            ParseError::new(
+                &loc,
+                "LookAt: \"up\" vector and viewing direction are pointing in the same direction",
+            )
+        })?;
+        self.graphics_state.for_active_transforms(|t| *t * look_at);
+        Ok(())
+    }
+
+    fn concat_transform(
+        &mut self,
+        transform: [Float; 16],
+        _loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        // Scene files give matrices in column-major order.
+        let transform = Transform::from_slice(&transform).transpose();
+        self.graphics_state
+            .for_active_transforms(|t| *t * transform);
+        Ok(())
+    }
+
+    fn transform(&mut self, transform: [Float; 16], _loc: FileLoc) -> Result<(), ParseError> {
+        let transform = Transform::from_slice(&transform).transpose();
+        self.graphics_state.for_active_transforms(|_| transform);
+        Ok(())
+    }
+
+    fn coordinate_system(&mut self, name: &str, _loc: FileLoc) -> Result<(), ParseError> {
+        self.named_coordinate_systems
+            .insert(name.to_string(), self.graphics_state.ctm);
+        Ok(())
+    }
+
+    fn coord_sys_transform(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError> {
+        match self.named_coordinate_systems.get(name) {
+            Some(ctm) => self.graphics_state.ctm = *ctm,
+            None => error::warning(
+                Some(&loc),
+                format!("couldn't find named coordinate system \"{name}\""),
+            ),
+        }
+        Ok(())
+    }
+
+    fn active_transform(
+        &mut self,
+        active: ActiveTransform,
+        _loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.graphics_state.active_transform = active;
+        Ok(())
+    }
+
+    fn transform_times(
+        &mut self,
+        start: Float,
+        end: Float,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("TransformTimes", &loc)?;
+        self.graphics_state.transform_start_time = start;
+        self.graphics_state.transform_end_time = end;
+        Ok(())
+    }
+
+    fn transform_begin(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        error::warning(
+            Some(&loc),
+            "TransformBegin is deprecated and should be replaced with AttributeBegin",
+        );
+        self.pushed_transforms.push((
+            self.graphics_state.ctm,
+            self.graphics_state.active_transform,
+            loc,
+        ));
+        Ok(())
+    }
+
+    fn transform_end(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        error::warning(
+            Some(&loc),
+            "TransformEnd is deprecated and should be replaced with AttributeEnd",
+        );
+        match self.pushed_transforms.pop() {
+            Some((ctm, active_transform, _)) => {
+                self.graphics_state.ctm = ctm;
+                self.graphics_state.active_transform = active_transform;
+            }
+            None => error::warning(
+                Some(&loc),
+                "unmatched TransformEnd encountered; ignoring it",
+            ),
+        }
+        Ok(())
+    }
+
+    fn reverse_orientation(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("ReverseOrientation", &loc)?;
+        self.graphics_state.reverse_orientation = !self.graphics_state.reverse_orientation;
+        Ok(())
+    }
+
+    fn color_space(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError> {
+        self.graphics_state.color_space = RgbColorSpace::get_named(name)
+            .ok_or_else(|| ParseError::new(&loc, format!("\"{name}\": unknown color space")))?;
+        Ok(())
+    }
+
+    fn pixel_filter(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("PixelFilter", &loc)?;
+        self.scene.filter = SceneEntity::new(name, params, self.graphics_state.color_space, loc)?;
+        Ok(())
+    }
+
+    fn film(
+        &mut self,
+        ty: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("Film", &loc)?;
+        self.scene.film = SceneEntity::new(ty, params, self.graphics_state.color_space, loc)?;
+        Ok(())
+    }
+
+    fn sampler(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("Sampler", &loc)?;
+        self.scene.sampler = SceneEntity::new(name, params, self.graphics_state.color_space, loc)?;
+        Ok(())
+    }
+
+    fn accelerator(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("Accelerator", &loc)?;
+        self.scene.accelerator =
+            SceneEntity::new(name, params, self.graphics_state.color_space, loc)?;
+        Ok(())
+    }
+
+    fn integrator(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("Integrator", &loc)?;
+        self.scene.integrator =
+            SceneEntity::new(name, params, self.graphics_state.color_space, loc)?;
+        Ok(())
+    }
+
+    fn camera(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_options("Camera", &loc)?;
+
+        // The current transformation maps from world space to camera space.
+        let world_from_camera = self.graphics_state.ctm.map(|t| t.inverse());
+        self.named_coordinate_systems
+            .insert("camera".to_string(), world_from_camera);
+
+        self.scene.camera = CameraSceneEntity {
+            entity: SceneEntity::new(name, params, self.graphics_state.color_space, loc)?,
+            world_from_camera: self.graphics_state.motion_transform(&world_from_camera),
+            medium: self.graphics_state.outside_medium.clone(),
+        };
+        Ok(())
+    }
+
+    fn make_named_medium(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        if !self.medium_names.insert(name.to_string()) {
+            return Err(ParseError::new(
+                &loc,
+                format!("\"{name}\": named medium redefined"),
+            ));
+        }
+
+        let entity = TransformedSceneEntity {
+            entity: SceneEntity::with_attributes(
+                name,
+                params,
+                &self.graphics_state.medium_attributes,
+                self.graphics_state.color_space,
+                loc,
+            )?,
+            world_from_object: self.graphics_state.world_from_object(),
+        };
+        self.scene.named_media.push((name.to_string(), entity));
+        Ok(())
+    }
+
+    fn medium_interface(
+        &mut self,
+        inside_name: &str,
+        outside_name: &str,
+        _loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.graphics_state.inside_medium = inside_name.to_string();
+        self.graphics_state.outside_medium = outside_name.to_string();
+        Ok(())
+    }
+
+    fn world_begin(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_options("WorldBegin", &loc)?;
+        self.current_block = BlockState::WorldBlock;
+
+        self.graphics_state.ctm = TransformSet::default();
+        self.graphics_state.active_transform = ActiveTransform::All;
+        self.named_coordinate_systems
+            .insert("world".to_string(), self.graphics_state.ctm);
+        Ok(())
+    }
+
+    fn attribute_begin(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("AttributeBegin", &loc)?;
+        self.pushed_graphics_states.push((
+            self.graphics_state.clone(),
+            PushedBy::AttributeBegin,
+            loc,
+        ));
+        Ok(())
+    }
+
+    fn attribute_end(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("AttributeEnd", &loc)?;
+        if self.pushed_graphics_states.is_empty() {
+            error::warning(
+                Some(&loc),
+                "unmatched AttributeEnd encountered; ignoring it",
+            );
+            return Ok(());
+        }
+        self.pop_graphics_state(PushedBy::AttributeBegin, &loc)
+    }
+
+    fn attribute(
+        &mut self,
+        target: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("Attribute", &loc)?;
+
+        let state = &mut self.graphics_state;
+        let attributes = match target {
+            "shape" => &mut state.shape_attributes,
+            "light" => &mut state.light_attributes,
+            "material" => &mut state.material_attributes,
+            "medium" => &mut state.medium_attributes,
+            "texture" => &mut state.texture_attributes,
+            _ => {
+                return Err(ParseError::new(
+                    &loc,
+                    format!("unknown attribute target \"{target}\""),
+                ))
+            }
+        };
+
+        // Entities may not understand every attribute, so they are never reported as unused.
+        // Later attributes take precedence over earlier ones with the same name.
+        let mut new_attributes = ParameterDictionary::new(params, state.color_space)?;
+        new_attributes.mark_used();
+        new_attributes.extend(attributes);
+        *attributes = new_attributes;
+        Ok(())
+    }
+
+    fn texture(
+        &mut self,
+        name: &str,
+        ty: &str,
+        tex_name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("Texture", &loc)?;
+
+        let (names, textures) = match ty {
+            "float" => (
+                &mut self.float_texture_names,
+                &mut self.scene.float_textures,
+            ),
+            "spectrum" => (
+                &mut self.spectrum_texture_names,
+                &mut self.scene.spectrum_textures,
+            ),
+            _ => {
+                return Err(ParseError::new(
+                    &loc,
+                    format!("{ty}: texture type unknown; must be \"float\" or \"spectrum\""),
+                ))
+            }
+        };
+
+        if !names.insert(name.to_string()) {
+            return Err(ParseError::new(
+                &loc,
+                format!("\"{name}\": texture redefined"),
+            ));
+        }
+
+        textures.push(TextureSceneEntity {
+            tex_name: name.to_string(),
+            entity: SceneEntity::with_attributes(
+                tex_name,
+                params,
+                &self.graphics_state.texture_attributes,
+                self.graphics_state.color_space,
+                loc,
+            )?,
+            world_from_texture: self.graphics_state.world_from_object(),
+        });
+        Ok(())
+    }
+
+    fn material(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("Material", &loc)?;
+        self.scene.materials.push(SceneEntity::with_attributes(
+            name,
+            params,
+            &self.graphics_state.material_attributes,
+            self.graphics_state.color_space,
+            loc,
+        )?);
+        self.graphics_state.material = MaterialRef::Index(self.scene.materials.len() - 1);
+        Ok(())
+    }
+
+    fn make_named_material(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("MakeNamedMaterial", &loc)?;
+
+        if !self.named_material_names.insert(name.to_string()) {
+            return Err(ParseError::new(
+                &loc,
+                format!("\"{name}\": named material redefined"),
+            ));
+        }
+
+        let entity = SceneEntity::with_attributes(
+            name,
+            params,
+            &self.graphics_state.material_attributes,
+            self.graphics_state.color_space,
+            loc,
+        )?;
+        self.scene.named_materials.push((name.to_string(), entity));
+        Ok(())
+    }
+
+    fn named_material(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("NamedMaterial", &loc)?;
+        self.graphics_state.material = MaterialRef::Named(name.to_string());
+        Ok(())
+    }
+
+    fn light_source(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("LightSource", &loc)?;
+        self.scene.lights.push(LightSceneEntity {
+            entity: SceneEntity::with_attributes(
+                name,
+                params,
+                &self.graphics_state.light_attributes,
+                self.graphics_state.color_space,
+                loc,
+            )?,
+            world_from_light: self.graphics_state.world_from_object(),
+            medium: self.graphics_state.outside_medium.clone(),
+        });
+        Ok(())
+    }
+
+    fn area_light_source(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("AreaLightSource", &loc)?;
+        self.graphics_state.area_light = Some(SceneEntity::with_attributes(
+            name,
+            params,
+            &self.graphics_state.light_attributes,
+            self.graphics_state.color_space,
+            loc,
+        )?);
+        Ok(())
+    }
+
+    fn shape(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError> {
+        self.verify_world("Shape", &loc)?;
+
+        let state = &self.graphics_state;
+        let mut entity = ShapeSceneEntity {
+            entity: SceneEntity::with_attributes(
+                name,
+                params,
+                &state.shape_attributes,
+                state.color_space,
+                loc,
+            )?,
+            world_from_object: state.world_from_object(),
+            reverse_orientation: state.reverse_orientation,
+            material: state.material.clone(),
+            light_index: None,
+            inside_medium: state.inside_medium.clone(),
+            outside_medium: state.outside_medium.clone(),
+        };
+
+        match &mut self.active_instance_definition {
+            Some(definition) => {
+                if state.area_light.is_some() {
+                    error::warning(
+                        Some(&entity.entity.loc),
+                        "area lights not supported with object instancing",
+                    );
+                }
+                definition.shapes.push(entity);
+            }
+            None => {
+                if let Some(area_light) = &state.area_light {
+                    self.scene.area_lights.push(area_light.clone());
+                    entity.light_index = Some(self.scene.area_lights.len() - 1);
+                }
+                self.scene.shapes.push(entity);
+            }
+        }
+        Ok(())
+    }
+
+    fn object_begin(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("ObjectBegin", &loc)?;
+
+        if self.active_instance_definition.is_some() {
+            return Err(ParseError::new(
+                &loc,
+                "ObjectBegin called inside of instance definition",
+            ));
+        }
+
+        if self.scene.instance_definitions.contains_key(name) {
+            return Err(ParseError::new(
+                &loc,
+                format!("{name}: trying to redefine an object instance"),
+            ));
+        }
+
+        self.pushed_graphics_states.push((
+            self.graphics_state.clone(),
+            PushedBy::ObjectBegin,
+            loc.clone(),
+        ));
+        self.active_instance_definition = Some(InstanceDefinitionSceneEntity {
+            name: name.to_string(),
+            loc,
+            shapes: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn object_end(&mut self, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("ObjectEnd", &loc)?;
+
+        let Some(definition) = self.active_instance_definition.take() else {
+            return Err(ParseError::new(
+                &loc,
+                "ObjectEnd called outside of instance definition",
+            ));
+        };
+
+        self.scene
+            .instance_definitions
+            .insert(definition.name.clone(), definition);
+        self.pop_graphics_state(PushedBy::ObjectBegin, &loc)
+    }
+
+    fn object_instance(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError> {
+        self.verify_world("ObjectInstance", &loc)?;
+
+        if self.active_instance_definition.is_some() {
+            return Err(ParseError::new(
+                &loc,
+                "ObjectInstance can't be called inside instance definition",
+            ));
+        }
+
+        self.scene.instances.push(InstanceSceneEntity {
+            name: name.to_string(),
+            loc,
+            world_from_instance: self.graphics_state.world_from_object(),
+        });
+        Ok(())
+    }
+
+    fn file_contents(&mut self, contents: &str) {
+        self.scene.fingerprint = murmur_hash64a(contents.as_bytes(), self.scene.fingerprint);
+    }
+
+    fn end_of_files(&mut self) -> Result<(), ParseError> {
+        if let Some(definition) = &self.active_instance_definition {
+            return Err(ParseError::new(
+                &definition.loc,
+                format!("{}: missing ObjectEnd", definition.name),
+            ));
+        }
+
+        for (_, _, loc) in &self.pushed_graphics_states {
+            error::warning(Some(loc), "missing end to AttributeBegin");
+        }
+        for (_, _, loc) in &self.pushed_transforms {
+            error::warning(Some(loc), "missing end to TransformBegin");
+        }
+
+        // Instances may be used before they are defined, so they can only be checked at the end.
+        for instance in &self.scene.instances {
+            if !self.scene.instance_definitions.contains_key(&instance.name) {
+                return Err(ParseError::new(
+                    &instance.loc,
+                    format!("{}: object instance not defined", instance.name),
+                ));
+            }
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,630 @@
+//! Typed access to the parameter lists of scene directives.
+use std::{cell::Cell, fmt, sync::Arc};
+
+use crate::{
+    color::{Rgb, RgbColorSpace},
+    math::{
+        normals::Normal3f,
+        points::{Point2f, Point3f},
+        vectors::{Vector2f, Vector3f},
+    },
+    spectrum::{
+        named, BlackbodySpectrum, PiecewiseLinearSpectrum, RgbAlbedoSpectrum,
+        RgbIlluminantSpectrum, RgbUnboundedSpectrum, Spectrum, SpectrumType,
+    },
+    Float,
+};
+
+use super::parser::{ParseError, ParsedParameter};
+
+/// The types that a parameter may be declared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterType {
+    Float,
+    Integer,
+    Bool,
+    Point2,
+    Vector2,
+    Point3,
+    Vector3,
+    Normal,
+    String,
+    Texture,
+    Spectrum,
+    Rgb,
+    Blackbody,
+}
+
+impl ParameterType {
+    /// Returns the type with the given name, accepting the older pbrt-v3 names as well.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "float" => Some(Self::Float),
+            "integer" => Some(Self::Integer),
+            "bool" => Some(Self::Bool),
+            "point2" => Some(Self::Point2),
+            "vector2" => Some(Self::Vector2),
+            "point3" | "point" => Some(Self::Point3),
+            "vector3" | "vector" => Some(Self::Vector3),
+            "normal" | "normal3" => Some(Self::Normal),
+            "string" => Some(Self::String),
+            "texture" => Some(Self::Texture),
+            "spectrum" => Some(Self::Spectrum),
+            "rgb" | "color" => Some(Self::Rgb),
+            "blackbody" => Some(Self::Blackbody),
+            _ => None,
+        }
+    }
+
+    /// The name of the type as it is written in scene files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Float => "float",
+            Self::Integer => "integer",
+            Self::Bool => "bool",
+            Self::Point2 => "point2",
+            Self::Vector2 => "vector2",
+            Self::Point3 => "point3",
+            Self::Vector3 => "vector3",
+            Self::Normal => "normal",
+            Self::String => "string",
+            Self::Texture => "texture",
+            Self::Spectrum => "spectrum",
+            Self::Rgb => "rgb",
+            Self::Blackbody => "blackbody",
+        }
+    }
+
+    /// The number of values that make up a single element of the type.
+    fn values_per_element(&self) -> usize {
+        match self {
+            Self::Point2 | Self::Vector2 => 2,
+            Self::Point3 | Self::Vector3 | Self::Normal | Self::Rgb => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Parameter {
+    ty: ParameterType,
+    param: ParsedParameter,
+    looked_up: Cell<bool>,
+    /// The color space that RGB values are given in.
+    color_space: &'static RgbColorSpace,
+}
+
+/// The parameters of a scene entity, with typed lookups.
+///
+/// Every lookup marks the parameter as used, so that parameters that no entity understood can be
+/// reported with [`ParameterDictionary::report_unused`].
+///
+/// # Examples
+///
+/// ```
+/// use lili::scene::BasicSceneBuilder;
+///
+/// let scene = BasicSceneBuilder::parse_string(
+///     r#"WorldBegin Shape "sphere" "float radius" 2 "integer count" 3 "float raduis" 4"#,
+/// )
+/// .unwrap();
+/// let params = &scene.shapes[0].entity.parameters;
+///
+/// assert_eq!(params.get_one_float("radius", 1.0).unwrap(), 2.0);
+/// assert_eq!(params.get_one_float("zmin", -1.0).unwrap(), -1.0);
+/// assert!(params.get_one_float("count", 0.0).is_err());
+/// assert!(params.report_unused().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParameterDictionary {
+    params: Vec<Parameter>,
+    color_space: &'static RgbColorSpace,
+}
+
+impl Default for ParameterDictionary {
+    fn default() -> Self {
+        Self {
+            params: Vec::new(),
+            color_space: RgbColorSpace::srgb(),
+        }
+    }
+}
+
+impl ParameterDictionary {
+    /// Creates a dictionary, checking that every parameter has a known type and values that match
+    /// it. RGB values are interpreted in `color_space`.
+    pub fn new(
+        params: Vec<ParsedParameter>,
+        color_space: &'static RgbColorSpace,
+    ) -> Result<Self, ParseError> {
+        let params = params
+            .into_iter()
+            .map(|param| {
+                let ty = ParameterType::from_name(&param.ty).ok_or_else(|| {
+                    ParseError::new(
+                        &param.loc,
+                        format!(
+                            "\"{}\": unknown parameter type \"{}\"",
+                            param.name, param.ty
+                        ),
+                    )
+                })?;
+                check_values(ty, &param)?;
+
+                Ok(Parameter {
+                    ty,
+                    param,
+                    looked_up: Cell::new(false),
+                    color_space,
+                })
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
+        Ok(Self {
+            params,
+            color_space,
+        })
+    }
+
+    /// The color space that was current when the parameters were given.
+    pub fn color_space(&self) -> &'static RgbColorSpace {
+        self.color_space
+    }
+
+    /// Adds the parameters of `other` after those of `self`, so that they only take effect where
+    /// `self` does not define a parameter with the same name.
+    ///
+    /// The added parameters keep the color space of `other`.
+    pub fn extend(&mut self, other: &ParameterDictionary) {
+        self.params.extend(other.params.iter().cloned());
+    }
+
+    /// Returns `true` if no parameters were given.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Returns the type of the named parameter, if it was given.
+    pub fn get_type(&self, name: &str) -> Option<ParameterType> {
+        self.params
+            .iter()
+            .find(|p| p.param.name == name)
+            .map(|p| p.ty)
+    }
+
+    /// Finds the named parameter and checks that it has one of the `expected` types.
+    fn lookup(
+        &self,
+        name: &str,
+        expected: &[ParameterType],
+    ) -> Result<Option<&Parameter>, ParseError> {
+        let Some(p) = self.params.iter().find(|p| p.param.name == name) else {
+            return Ok(None);
+        };
+
+        p.looked_up.set(true);
+        if !expected.contains(&p.ty) {
+            let expected = expected
+                .iter()
+                .map(|ty| format!("\"{ty}\""))
+                .collect::<Vec<_>>()
+                .join(" or ");
+            return Err(ParseError::new(
+                &p.param.loc,
+                format!(
+                    "\"{name}\": parameter has type \"{}\" but {expected} was expected",
+                    p.ty
+                ),
+            ));
+        }
+
+        Ok(Some(p))
+    }
+
+    /// Finds the named parameter and checks that it holds exactly one element.
+    fn lookup_single(
+        &self,
+        name: &str,
+        ty: ParameterType,
+    ) -> Result<Option<&ParsedParameter>, ParseError> {
+        let Some(p) = self.lookup(name, &[ty])? else {
+            return Ok(None);
+        };
+
+        let count =
+            p.param.floats.len() + p.param.ints.len() + p.param.strings.len() + p.param.bools.len();
+        if count != ty.values_per_element() {
+            return Err(ParseError::new(
+                &p.param.loc,
+                format!("\"{name}\": expected a single value"),
+            ));
+        }
+
+        Ok(Some(&p.param))
+    }
+
+    pub fn get_one_float(&self, name: &str, default: Float) -> Result<Float, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Float)?
+            .map_or(default, |p| p.floats[0]))
+    }
+
+    pub fn get_one_int(&self, name: &str, default: i32) -> Result<i32, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Integer)?
+            .map_or(default, |p| p.ints[0]))
+    }
+
+    pub fn get_one_bool(&self, name: &str, default: bool) -> Result<bool, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Bool)?
+            .map_or(default, |p| p.bools[0]))
+    }
+
+    pub fn get_one_string(&self, name: &str, default: &str) -> Result<String, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::String)?
+            .map_or_else(|| default.to_string(), |p| p.strings[0].clone()))
+    }
+
+    pub fn get_one_point2f(&self, name: &str, default: Point2f) -> Result<Point2f, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Point2)?
+            .map_or(default, |p| Point2f::new(p.floats[0], p.floats[1])))
+    }
+
+    pub fn get_one_vector2f(&self, name: &str, default: Vector2f) -> Result<Vector2f, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Vector2)?
+            .map_or(default, |p| Vector2f::new(p.floats[0], p.floats[1])))
+    }
+
+    pub fn get_one_point3f(&self, name: &str, default: Point3f) -> Result<Point3f, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Point3)?
+            .map_or(default, |p| {
+                Point3f::new(p.floats[0], p.floats[1], p.floats[2])
+            }))
+    }
+
+    pub fn get_one_vector3f(&self, name: &str, default: Vector3f) -> Result<Vector3f, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Vector3)?
+            .map_or(default, |p| {
+                Vector3f::new(p.floats[0], p.floats[1], p.floats[2])
+            }))
+    }
+
+    pub fn get_one_normal3f(&self, name: &str, default: Normal3f) -> Result<Normal3f, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Normal)?
+            .map_or(default, |p| {
+                Normal3f::new(p.floats[0], p.floats[1], p.floats[2])
+            }))
+    }
+
+    /// Returns the name of the texture bound to the named parameter, if there is one.
+    pub fn get_texture(&self, name: &str) -> Result<Option<String>, ParseError> {
+        Ok(self
+            .lookup_single(name, ParameterType::Texture)?
+            .map(|p| p.strings[0].clone()))
+    }
+
+    /// Returns the spectrum given by the named `spectrum`, `rgb` or `blackbody` parameter, for a
+    /// quantity of type `ty`.
+    ///
+    /// RGB values are converted to spectra in the color space that was current when the
+    /// parameter was given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::{scene::BasicSceneBuilder, spectrum::SpectrumType};
+    ///
+    /// let scene = BasicSceneBuilder::parse_string(
+    ///     r#"ColorSpace "rec2020" WorldBegin Material "diffuse" "rgb reflectance" [0.8 0.2 0.1]"#,
+    /// )
+    /// .unwrap();
+    /// let params = &scene.materials[1].parameters;
+    /// assert_eq!(params.color_space().name(), "rec2020");
+    ///
+    /// let reflectance = params
+    ///     .get_one_spectrum("reflectance", SpectrumType::Albedo)
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert!(reflectance.evaluate(650.0) > reflectance.evaluate(450.0));
+    /// ```
+    pub fn get_one_spectrum(
+        &self,
+        name: &str,
+        ty: SpectrumType,
+    ) -> Result<Option<Arc<dyn Spectrum>>, ParseError> {
+        let Some(p) = self.lookup(
+            name,
+            &[
+                ParameterType::Spectrum,
+                ParameterType::Rgb,
+                ParameterType::Blackbody,
+            ],
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let mut spectra = spectra(p, ty)?;
+        if spectra.len() != 1 {
+            return Err(ParseError::new(
+                &p.param.loc,
+                format!("\"{name}\": expected a single spectrum"),
+            ));
+        }
+
+        Ok(spectra.pop())
+    }
+
+    pub fn get_float_array(&self, name: &str) -> Result<Vec<Float>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Float])?
+            .map_or_else(Vec::new, |p| p.param.floats.clone()))
+    }
+
+    pub fn get_int_array(&self, name: &str) -> Result<Vec<i32>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Integer])?
+            .map_or_else(Vec::new, |p| p.param.ints.clone()))
+    }
+
+    pub fn get_bool_array(&self, name: &str) -> Result<Vec<bool>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Bool])?
+            .map_or_else(Vec::new, |p| p.param.bools.clone()))
+    }
+
+    pub fn get_string_array(&self, name: &str) -> Result<Vec<String>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::String])?
+            .map_or_else(Vec::new, |p| p.param.strings.clone()))
+    }
+
+    pub fn get_point2f_array(&self, name: &str) -> Result<Vec<Point2f>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Point2])?
+            .map_or_else(Vec::new, |p| {
+                p.param
+                    .floats
+                    .chunks_exact(2)
+                    .map(|v| Point2f::new(v[0], v[1]))
+                    .collect()
+            }))
+    }
+
+    pub fn get_vector2f_array(&self, name: &str) -> Result<Vec<Vector2f>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Vector2])?
+            .map_or_else(Vec::new, |p| {
+                p.param
+                    .floats
+                    .chunks_exact(2)
+                    .map(|v| Vector2f::new(v[0], v[1]))
+                    .collect()
+            }))
+    }
+
+    pub fn get_point3f_array(&self, name: &str) -> Result<Vec<Point3f>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Point3])?
+            .map_or_else(Vec::new, |p| {
+                p.param
+                    .floats
+                    .chunks_exact(3)
+                    .map(|v| Point3f::new(v[0], v[1], v[2]))
+                    .collect()
+            }))
+    }
+
+    pub fn get_vector3f_array(&self, name: &str) -> Result<Vec<Vector3f>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Vector3])?
+            .map_or_else(Vec::new, |p| {
+                p.param
+                    .floats
+                    .chunks_exact(3)
+                    .map(|v| Vector3f::new(v[0], v[1], v[2]))
+                    .collect()
+            }))
+    }
+
+    pub fn get_normal3f_array(&self, name: &str) -> Result<Vec<Normal3f>, ParseError> {
+        Ok(self
+            .lookup(name, &[ParameterType::Normal])?
+            .map_or_else(Vec::new, |p| {
+                p.param
+                    .floats
+                    .chunks_exact(3)
+                    .map(|v| Normal3f::new(v[0], v[1], v[2]))
+                    .collect()
+            }))
+    }
+
+    /// Returns every spectrum given by the named `spectrum`, `rgb` or `blackbody` parameter, for
+    /// quantities of type `ty`.
+    pub fn get_spectrum_array(
+        &self,
+        name: &str,
+        ty: SpectrumType,
+    ) -> Result<Vec<Arc<dyn Spectrum>>, ParseError> {
+        self.lookup(
+            name,
+            &[
+                ParameterType::Spectrum,
+                ParameterType::Rgb,
+                ParameterType::Blackbody,
+            ],
+        )?
+        .map_or_else(|| Ok(Vec::new()), |p| spectra(p, ty))
+    }
+
+    /// Marks every parameter as used, so that [`ParameterDictionary::report_unused`] ignores them.
+    ///
+    /// This is meant for parameters given with the `Attribute` directive, which apply to every
+    /// entity of a kind even though each entity only understands some of them.
+    pub fn mark_used(&self) {
+        for p in &self.params {
+            p.looked_up.set(true);
+        }
+    }
+
+    /// Returns the parameters that were never looked up.
+    pub fn unused(&self) -> impl Iterator<Item = &ParsedParameter> {
+        self.params
+            .iter()
+            .filter(|p| !p.looked_up.get())
+            .map(|p| &p.param)
+    }
+
+    /// Returns an error for the first parameter that was never looked up.
+    ///
+    /// This should be called once an entity has looked up every parameter it supports, so that
+    /// misspelled or unsupported parameters do not go unnoticed.
+    pub fn report_unused(&self) -> Result<(), ParseError> {
+        match self.unused().next() {
+            Some(p) => Err(ParseError::new(
+                &p.loc,
+                format!("\"{}\": unused parameter", p.name),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Creates the spectra given by a spectrum-valued parameter.
+///
+/// RGB values become smooth spectra in the color space of the parameter, and spectra given by
+/// wavelength/value pairs are normalized to a luminance of 1 if they describe illuminants.
+fn spectra(p: &Parameter, ty: SpectrumType) -> Result<Vec<Arc<dyn Spectrum>>, ParseError> {
+    let error =
+        |message: &str| ParseError::new(&p.param.loc, format!("\"{}\": {message}", p.param.name));
+
+    match p.ty {
+        ParameterType::Rgb => p
+            .param
+            .floats
+            .chunks_exact(3)
+            .map(|rgb| {
+                let rgb = Rgb::new(rgb[0], rgb[1], rgb[2]);
+                if rgb.min_component_value() < 0.0 {
+                    return Err(error("RGB values must not be negative"));
+                }
+                let spectrum: Arc<dyn Spectrum> = match ty {
+                    SpectrumType::Albedo if rgb.max_component_value() > 1.0 => {
+                        return Err(error("RGB albedo values must not be greater than 1"));
+                    }
+                    SpectrumType::Albedo => Arc::new(RgbAlbedoSpectrum::new(p.color_space, rgb)),
+                    SpectrumType::Unbounded => {
+                        Arc::new(RgbUnboundedSpectrum::new(p.color_space, rgb))
+                    }
+                    SpectrumType::Illuminant => {
+                        Arc::new(RgbIlluminantSpectrum::new(p.color_space, rgb))
+                    }
+                };
+                Ok(spectrum)
+            })
+            .collect(),
+        ParameterType::Blackbody => Ok(p
+            .param
+            .floats
+            .iter()
+            .map(|t| Arc::new(BlackbodySpectrum::new(*t)) as Arc<dyn Spectrum>)
+            .collect()),
+        _ if !p.param.strings.is_empty() => p
+            .param
+            .strings
+            .iter()
+            .map(|name| {
+                named::get(name).ok_or_else(|| error(&format!("unknown named spectrum \"{name}\"")))
+            })
+            .collect(),
+        _ => {
+            let values = p.param.floats.iter().skip(1).step_by(2);
+            if ty == SpectrumType::Albedo && values.clone().any(|v| *v > 1.0) {
+                return Err(error("albedo values must not be greater than 1"));
+            }
+            let normalize = ty == SpectrumType::Illuminant;
+            let spectrum = PiecewiseLinearSpectrum::from_interleaved(&p.param.floats, normalize)
+                .ok_or_else(|| error("invalid wavelength/value pairs"))?;
+            Ok(vec![Arc::new(spectrum)])
+        }
+    }
+}
+
+/// Checks that the values of a parameter match its declared type.
+fn check_values(ty: ParameterType, param: &ParsedParameter) -> Result<(), ParseError> {
+    let error = |message: &str| {
+        Err(ParseError::new(
+            &param.loc,
+            format!("\"{}\": {message}", param.name),
+        ))
+    };
+
+    let (has_floats, has_ints, has_strings, has_bools) = (
+        !param.floats.is_empty(),
+        !param.ints.is_empty(),
+        !param.strings.is_empty(),
+        !param.bools.is_empty(),
+    );
+    if !(has_floats || has_ints || has_strings || has_bools) {
+        return error("no values provided for parameter");
+    }
+
+    match ty {
+        ParameterType::Bool if !has_bools => {
+            error("non-Boolean values provided for Boolean-valued parameter")
+        }
+        ParameterType::Integer if !has_ints => {
+            error("non-integer values provided for integer-valued parameter")
+        }
+        ParameterType::String | ParameterType::Texture if !has_strings => {
+            error("non-string values provided for string-valued parameter")
+        }
+        ParameterType::Spectrum if has_bools => {
+            error("Boolean values provided for spectrum-valued parameter")
+        }
+        ParameterType::Spectrum if has_floats && !param.floats.len().is_multiple_of(2) => {
+            error("expected wavelength/value pairs for spectrum-valued parameter")
+        }
+        ParameterType::Spectrum => {
+            let mut lambda = param.floats.iter().step_by(2);
+            let mut previous = lambda.next();
+            for l in lambda {
+                if previous.is_some_and(|previous| l <= previous) {
+                    return error("spectrum wavelengths must be increasing");
+                }
+                previous = Some(l);
+            }
+            Ok(())
+        }
+        ParameterType::Float
+        | ParameterType::Point2
+        | ParameterType::Vector2
+        | ParameterType::Point3
+        | ParameterType::Vector3
+        | ParameterType::Normal
+        | ParameterType::Rgb
+        | ParameterType::Blackbody
+            if !has_floats =>
+        {
+            error("non-numeric values provided for numeric parameter")
+        }
+        _ if !param.floats.len().is_multiple_of(ty.values_per_element()) => error(&format!(
+            "number of values provided is not a multiple of {}",
+            ty.values_per_element()
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,693 @@
+//! Parser for the pbrt-v4 scene description format.
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    error::{warning, FileLoc},
+    Float,
+};
+
+use super::tokenizer::{Token, Tokenizer};
+
+/// An error encountered while parsing a scene description.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// Where the error occurred.
+    pub loc: FileLoc,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(loc: &FileLoc, message: impl Into<String>) -> Self {
+        Self {
+            loc: loc.clone(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.loc, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// A parameter as it appears in a scene file, e.g. `"float radius" [ 2 ]`.
+///
+/// Values are stored according to how they were written: numbers go to `ints` for `integer`
+/// parameters and to `floats` otherwise, quoted strings go to `strings` and `true`/`false` go to
+/// `bools`. Checking them against the declared type is left to the consumer.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedParameter {
+    /// The declared type of the parameter, e.g. `float` or `rgb`.
+    pub ty: String,
+    /// The name of the parameter.
+    pub name: String,
+    /// The location of the parameter declaration.
+    pub loc: FileLoc,
+    pub floats: Vec<Float>,
+    pub ints: Vec<i32>,
+    pub strings: Vec<String>,
+    pub bools: Vec<bool>,
+}
+
+/// Which transformation matrices subsequent transformation directives apply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveTransform {
+    StartTime,
+    EndTime,
+    All,
+}
+
+/// Receives the directives of a scene description as the parser encounters them.
+///
+/// Each method corresponds to one directive, takes its already-parsed arguments and the
+/// location of the directive, and may reject the directive by returning an error.
+#[allow(clippy::too_many_arguments)]
+pub trait ParserTarget {
+    fn option(&mut self, name: &str, value: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn identity(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn translate(
+        &mut self,
+        dx: Float,
+        dy: Float,
+        dz: Float,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn rotate(
+        &mut self,
+        angle: Float,
+        ax: Float,
+        ay: Float,
+        az: Float,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn scale(&mut self, sx: Float, sy: Float, sz: Float, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn look_at(
+        &mut self,
+        ex: Float,
+        ey: Float,
+        ez: Float,
+        lx: Float,
+        ly: Float,
+        lz: Float,
+        ux: Float,
+        uy: Float,
+        uz: Float,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn concat_transform(&mut self, transform: [Float; 16], loc: FileLoc) -> Result<(), ParseError>;
+
+    fn transform(&mut self, transform: [Float; 16], loc: FileLoc) -> Result<(), ParseError>;
+
+    fn coordinate_system(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn coord_sys_transform(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn active_transform(&mut self, active: ActiveTransform, loc: FileLoc)
+        -> Result<(), ParseError>;
+
+    fn transform_times(&mut self, start: Float, end: Float, loc: FileLoc)
+        -> Result<(), ParseError>;
+
+    fn transform_begin(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn transform_end(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn reverse_orientation(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn color_space(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn pixel_filter(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn film(
+        &mut self,
+        ty: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn sampler(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn accelerator(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn integrator(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn camera(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn make_named_medium(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn medium_interface(
+        &mut self,
+        inside_name: &str,
+        outside_name: &str,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn world_begin(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn attribute_begin(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn attribute_end(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn attribute(
+        &mut self,
+        target: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn texture(
+        &mut self,
+        name: &str,
+        ty: &str,
+        tex_name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn material(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn make_named_material(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn named_material(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn light_source(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn area_light_source(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn shape(
+        &mut self,
+        name: &str,
+        params: Vec<ParsedParameter>,
+        loc: FileLoc,
+    ) -> Result<(), ParseError>;
+
+    fn object_begin(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn object_end(&mut self, loc: FileLoc) -> Result<(), ParseError>;
+
+    fn object_instance(&mut self, name: &str, loc: FileLoc) -> Result<(), ParseError>;
+
+    /// Called with the text of every file before its directives are parsed, including the files
+    /// that are included or imported.
+    fn file_contents(&mut self, contents: &str);
+
+    /// Called once the whole scene description has been parsed.
+    fn end_of_files(&mut self) -> Result<(), ParseError>;
+}
+
+/// Parses each of the given files in order, passing their directives to `target`.
+///
+/// If no files are given, the scene description is read from standard input.
+pub fn parse_files(
+    target: &mut impl ParserTarget,
+    filenames: &[PathBuf],
+) -> Result<(), ParseError> {
+    if filenames.is_empty() {
+        let name: Arc<str> = "(stdin)".into();
+        let contents = io::read_to_string(io::stdin())
+            .map_err(|err| ParseError::new(&FileLoc::new(name.clone()), err.to_string()))?;
+        target.file_contents(&contents);
+        Parser::new(Tokenizer::new(&contents, name)).parse(target)?;
+    }
+
+    for filename in filenames {
+        let tokenizer = open_file(target, filename, None)?;
+        Parser::new(tokenizer).parse(target)?;
+    }
+
+    target.end_of_files()
+}
+
+/// Parses a scene description held in memory, passing its directives to `target`.
+///
+/// Files referenced by `Include` and `Import` are resolved relative to the current directory.
+pub fn parse_string(target: &mut impl ParserTarget, source: &str) -> Result<(), ParseError> {
+    target.file_contents(source);
+    Parser::new(Tokenizer::new(source, "(string)".into())).parse(target)?;
+
+    target.end_of_files()
+}
+
+fn open_file(
+    target: &mut impl ParserTarget,
+    filename: &Path,
+    loc: Option<&FileLoc>,
+) -> Result<Tokenizer, ParseError> {
+    let name: Arc<str> = filename.to_string_lossy().into();
+    let contents = fs::read_to_string(filename).map_err(|err| {
+        let loc = loc.cloned().unwrap_or_else(|| FileLoc::new(name.clone()));
+        ParseError::new(&loc, format!("{}: {err}", filename.display()))
+    })?;
+    target.file_contents(&contents);
+    Ok(Tokenizer::new(&contents, name))
+}
+
+struct Parser {
+    file_stack: Vec<Tokenizer>,
+    unget_token: Option<Token>,
+}
+
+impl Parser {
+    fn new(tokenizer: Tokenizer) -> Self {
+        Self {
+            file_stack: vec![tokenizer],
+            unget_token: None,
+        }
+    }
+
+    /// Returns the next token, moving on from included files once they run out.
+    fn next_token(&mut self) -> Result<Option<Token>, ParseError> {
+        if let Some(token) = self.unget_token.take() {
+            return Ok(Some(token));
+        }
+
+        while let Some(tokenizer) = self.file_stack.last_mut() {
+            match tokenizer.next_token()? {
+                Some(token) => return Ok(Some(token)),
+                None => {
+                    self.file_stack.pop();
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn required_token(&mut self, after: &Token) -> Result<Token, ParseError> {
+        self.next_token()?.ok_or_else(|| {
+            ParseError::new(
+                &after.loc,
+                format!("premature end of file after \"{}\"", after.text),
+            )
+        })
+    }
+
+    fn unget(&mut self, token: Token) {
+        debug_assert!(self.unget_token.is_none());
+        self.unget_token = Some(token);
+    }
+
+    fn quoted_string(&mut self, after: &Token) -> Result<String, ParseError> {
+        self.required_token(after)?.dequote()
+    }
+
+    fn number(&mut self, after: &Token) -> Result<Float, ParseError> {
+        let token = self.required_token(after)?;
+        parse_float(&token)
+    }
+
+    fn numbers<const N: usize>(&mut self, after: &Token) -> Result<[Float; N], ParseError> {
+        let mut values = [0.0; N];
+        for value in &mut values {
+            *value = self.number(after)?;
+        }
+        Ok(values)
+    }
+
+    /// Parses a bracketed list of 16 numbers as used by `Transform` and `ConcatTransform`.
+    fn matrix(&mut self, after: &Token) -> Result<[Float; 16], ParseError> {
+        let open = self.required_token(after)?;
+        if open.text != "[" {
+            return Err(syntax_error(&open));
+        }
+
+        let values = self.numbers::<16>(after)?;
+
+        let close = self.required_token(after)?;
+        if close.text != "]" {
+            return Err(ParseError::new(
+                &close.loc,
+                format!(
+                    "expected \"]\" after 16 matrix values, got \"{}\"",
+                    close.text
+                ),
+            ));
+        }
+
+        Ok(values)
+    }
+
+    /// Parses the `"type name" value` pairs that follow most directives.
+    fn parameters(&mut self) -> Result<Vec<ParsedParameter>, ParseError> {
+        let mut params = Vec::new();
+
+        while let Some(decl) = self.next_token()? {
+            if !decl.is_quoted() {
+                self.unget(decl);
+                break;
+            }
+
+            let decl_string = decl.dequote()?;
+            let mut parts = decl_string.split_whitespace();
+            let (Some(ty), Some(name), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(ParseError::new(
+                    &decl.loc,
+                    format!("parameter \"{decl_string}\" is not of the form \"type name\""),
+                ));
+            };
+
+            let mut param = ParsedParameter {
+                ty: ty.to_string(),
+                name: name.to_string(),
+                loc: decl.loc.clone(),
+                ..Default::default()
+            };
+
+            let value = self.required_token(&decl)?;
+            if value.text == "[" {
+                loop {
+                    let value = self.required_token(&decl)?;
+                    if value.text == "]" {
+                        break;
+                    }
+                    add_parameter_value(&mut param, &value)?;
+                }
+            } else {
+                add_parameter_value(&mut param, &value)?;
+            }
+
+            params.push(param);
+        }
+
+        Ok(params)
+    }
+
+    fn parse(&mut self, target: &mut impl ParserTarget) -> Result<(), ParseError> {
+        while let Some(token) = self.next_token()? {
+            let loc = token.loc.clone();
+            match token.text.as_str() {
+                "Accelerator" => {
+                    let name = self.quoted_string(&token)?;
+                    target.accelerator(&name, self.parameters()?, loc)?;
+                }
+                "ActiveTransform" => {
+                    let which = self.required_token(&token)?;
+                    let active = match which.text.as_str() {
+                        "All" => ActiveTransform::All,
+                        "EndTime" => ActiveTransform::EndTime,
+                        "StartTime" => ActiveTransform::StartTime,
+                        _ => return Err(syntax_error(&which)),
+                    };
+                    target.active_transform(active, loc)?;
+                }
+                "AreaLightSource" => {
+                    let name = self.quoted_string(&token)?;
+                    target.area_light_source(&name, self.parameters()?, loc)?;
+                }
+                "Attribute" => {
+                    let name = self.quoted_string(&token)?;
+                    target.attribute(&name, self.parameters()?, loc)?;
+                }
+                "AttributeBegin" => target.attribute_begin(loc)?,
+                "AttributeEnd" => target.attribute_end(loc)?,
+                "Camera" => {
+                    let name = self.quoted_string(&token)?;
+                    target.camera(&name, self.parameters()?, loc)?;
+                }
+                "ColorSpace" => {
+                    let name = self.quoted_string(&token)?;
+                    target.color_space(&name, loc)?;
+                }
+                "ConcatTransform" => {
+                    let m = self.matrix(&token)?;
+                    target.concat_transform(m, loc)?;
+                }
+                "CoordinateSystem" => {
+                    let name = self.quoted_string(&token)?;
+                    target.coordinate_system(&name, loc)?;
+                }
+                "CoordSysTransform" => {
+                    let name = self.quoted_string(&token)?;
+                    target.coord_sys_transform(&name, loc)?;
+                }
+                "Film" => {
+                    let name = self.quoted_string(&token)?;
+                    target.film(&name, self.parameters()?, loc)?;
+                }
+                "Identity" => target.identity(loc)?,
+                "Import" | "Include" => {
+                    // pbrt may parse imported files concurrently; here they are parsed in place
+                    // just like included ones.
+                    let filename = self.quoted_string(&token)?;
+                    let path = self.resolve_filename(&filename);
+                    let tokenizer = open_file(target, &path, Some(&loc))?;
+                    self.file_stack.push(tokenizer);
+                }
+                "Integrator" => {
+                    let name = self.quoted_string(&token)?;
+                    target.integrator(&name, self.parameters()?, loc)?;
+                }
+                "LightSource" => {
+                    let name = self.quoted_string(&token)?;
+                    target.light_source(&name, self.parameters()?, loc)?;
+                }
+                "LookAt" => {
+                    let [ex, ey, ez, lx, ly, lz, ux, uy, uz] = self.numbers::<9>(&token)?;
+                    target.look_at(ex, ey, ez, lx, ly, lz, ux, uy, uz, loc)?;
+                }
+                "MakeNamedMaterial" => {
+                    let name = self.quoted_string(&token)?;
+                    target.make_named_material(&name, self.parameters()?, loc)?;
+                }
+                "MakeNamedMedium" => {
+                    let name = self.quoted_string(&token)?;
+                    target.make_named_medium(&name, self.parameters()?, loc)?;
+                }
+                "Material" => {
+                    let name = self.quoted_string(&token)?;
+                    target.material(&name, self.parameters()?, loc)?;
+                }
+                "MediumInterface" => {
+                    let inside = self.quoted_string(&token)?;
+                    // The exterior medium is optional and defaults to the interior one.
+                    let outside = match self.next_token()? {
+                        Some(next) if next.is_quoted() => next.dequote()?,
+                        Some(next) => {
+                            self.unget(next);
+                            inside.clone()
+                        }
+                        None => inside.clone(),
+                    };
+                    target.medium_interface(&inside, &outside, loc)?;
+                }
+                "NamedMaterial" => {
+                    let name = self.quoted_string(&token)?;
+                    target.named_material(&name, loc)?;
+                }
+                "ObjectBegin" => {
+                    let name = self.quoted_string(&token)?;
+                    target.object_begin(&name, loc)?;
+                }
+                "ObjectEnd" => target.object_end(loc)?,
+                "ObjectInstance" => {
+                    let name = self.quoted_string(&token)?;
+                    target.object_instance(&name, loc)?;
+                }
+                "Option" => {
+                    let name = self.quoted_string(&token)?;
+                    let value = self.required_token(&token)?;
+                    let value = if value.is_quoted() {
+                        value.dequote()?
+                    } else {
+                        value.text
+                    };
+                    target.option(&name, &value, loc)?;
+                }
+                "PixelFilter" => {
+                    let name = self.quoted_string(&token)?;
+                    target.pixel_filter(&name, self.parameters()?, loc)?;
+                }
+                "ReverseOrientation" => target.reverse_orientation(loc)?,
+                "Rotate" => {
+                    let [angle, ax, ay, az] = self.numbers::<4>(&token)?;
+                    target.rotate(angle, ax, ay, az, loc)?;
+                }
+                "Sampler" => {
+                    let name = self.quoted_string(&token)?;
+                    target.sampler(&name, self.parameters()?, loc)?;
+                }
+                "Scale" => {
+                    let [sx, sy, sz] = self.numbers::<3>(&token)?;
+                    target.scale(sx, sy, sz, loc)?;
+                }
+                "Shape" => {
+                    let name = self.quoted_string(&token)?;
+                    target.shape(&name, self.parameters()?, loc)?;
+                }
+                "Texture" => {
+                    let name = self.quoted_string(&token)?;
+                    let ty = self.quoted_string(&token)?;
+                    let tex_name = self.quoted_string(&token)?;
+                    target.texture(&name, &ty, &tex_name, self.parameters()?, loc)?;
+                }
+                "Transform" => {
+                    let m = self.matrix(&token)?;
+                    target.transform(m, loc)?;
+                }
+                "TransformBegin" => target.transform_begin(loc)?,
+                "TransformEnd" => target.transform_end(loc)?,
+                "TransformTimes" => {
+                    let [start, end] = self.numbers::<2>(&token)?;
+                    target.transform_times(start, end, loc)?;
+                }
+                "Translate" => {
+                    let [dx, dy, dz] = self.numbers::<3>(&token)?;
+                    target.translate(dx, dy, dz, loc)?;
+                }
+                "WorldBegin" => target.world_begin(loc)?,
+                "WorldEnd" => warning(Some(&loc), "\"WorldEnd\" is deprecated and ignored"),
+                _ => {
+                    return Err(ParseError::new(
+                        &token.loc,
+                        format!("unknown directive \"{}\"", token.text),
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a filename relative to the directory of the file currently being parsed.
+    fn resolve_filename(&self, filename: &str) -> PathBuf {
+        let path = PathBuf::from(filename);
+        if path.is_absolute() {
+            return path;
+        }
+
+        self.file_stack
+            .last()
+            .and_then(|tokenizer| Path::new(&**tokenizer.filename()).parent())
+            .map(|dir| dir.join(&path))
+            .unwrap_or(path)
+    }
+}
+
+fn syntax_error(token: &Token) -> ParseError {
+    ParseError::new(&token.loc, format!("syntax error at \"{}\"", token.text))
+}
+
+fn parse_float(token: &Token) -> Result<Float, ParseError> {
+    token
+        .text
+        .parse::<Float>()
+        .map_err(|_| ParseError::new(&token.loc, format!("\"{}\": expected a number", token.text)))
+}
+
+fn add_parameter_value(param: &mut ParsedParameter, value: &Token) -> Result<(), ParseError> {
+    let (has_numbers, has_strings, has_bools) = (
+        !param.floats.is_empty() || !param.ints.is_empty(),
+        !param.strings.is_empty(),
+        !param.bools.is_empty(),
+    );
+
+    let mixed_types = || {
+        ParseError::new(
+            &value.loc,
+            format!("\"{}\": parameter has mixed types", param.name),
+        )
+    };
+
+    if value.is_quoted() {
+        let string = value.dequote()?;
+        // pbrt-v3 wrote Boolean values as quoted strings.
+        if param.ty == "bool" && (string == "true" || string == "false") {
+            if has_numbers || has_strings {
+                return Err(mixed_types());
+            }
+            param.bools.push(string == "true");
+        } else {
+            if has_numbers || has_bools {
+                return Err(mixed_types());
+            }
+            param.strings.push(string);
+        }
+    } else if value.text == "true" || value.text == "false" {
+        if has_numbers || has_strings {
+            return Err(mixed_types());
+        }
+        param.bools.push(value.text == "true");
+    } else {
+        if has_strings || has_bools {
+            return Err(mixed_types());
+        }
+        if param.ty == "integer" {
+            let int = value.text.parse::<i32>().map_err(|_| {
+                ParseError::new(
+                    &value.loc,
+                    format!("\"{}\": expected an integer", value.text),
+                )
+            })?;
+            param.ints.push(int);
+        } else {
+            param.floats.push(parse_float(value)?);
+        }
+    }
+
+    Ok(())
+}
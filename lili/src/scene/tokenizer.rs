@@ -0,0 +1,169 @@
+//! Splits pbrt-v4 scene descriptions into tokens.
+use std::sync::Arc;
+
+use crate::error::FileLoc;
+
+use super::parser::ParseError;
+
+/// A single token of a scene description, along with where it was found.
+#[derive(Debug, Clone)]
+pub struct Token {
+    /// The text of the token. Quoted strings keep their quotes.
+    pub text: String,
+    /// The location of the first character of the token.
+    pub loc: FileLoc,
+}
+
+impl Token {
+    /// Returns `true` if the token is a double-quoted string.
+    pub fn is_quoted(&self) -> bool {
+        self.text.len() >= 2 && self.text.starts_with('"') && self.text.ends_with('"')
+    }
+
+    /// Returns the contents of a quoted string token with its escape sequences expanded.
+    pub fn dequote(&self) -> Result<String, ParseError> {
+        if !self.is_quoted() {
+            return Err(ParseError::new(
+                &self.loc,
+                format!("expected quoted string, got \"{}\"", self.text),
+            ));
+        }
+
+        let inner = &self.text[1..self.text.len() - 1];
+        let mut result = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('b') => result.push('\x08'),
+                Some('f') => result.push('\x0c'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some('\\') => result.push('\\'),
+                Some('\'') => result.push('\''),
+                Some('"') => result.push('"'),
+                Some(c) => {
+                    return Err(ParseError::new(
+                        &self.loc,
+                        format!("unexpected escaped character '{c}'"),
+                    ))
+                }
+                None => {
+                    return Err(ParseError::new(
+                        &self.loc,
+                        "premature end of string after '\\'",
+                    ))
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Produces tokens from the contents of a single scene file.
+///
+/// Tokens are separated by whitespace. Quoted strings and the brackets `[` and `]` are always
+/// tokens of their own, and `#` starts a comment that runs to the end of the line.
+pub struct Tokenizer {
+    contents: Vec<char>,
+    pos: usize,
+    loc: FileLoc,
+}
+
+impl Tokenizer {
+    /// Creates a tokenizer over `contents`, reporting locations in `filename`.
+    pub fn new(contents: &str, filename: Arc<str>) -> Self {
+        Self {
+            contents: contents.chars().collect(),
+            pos: 0,
+            loc: FileLoc::new(filename),
+        }
+    }
+
+    /// The name of the file this tokenizer reads from.
+    pub fn filename(&self) -> &Arc<str> {
+        &self.loc.filename
+    }
+
+    fn get_char(&mut self) -> Option<char> {
+        let c = *self.contents.get(self.pos)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.loc.line += 1;
+            self.loc.column = 0;
+        } else {
+            self.loc.column += 1;
+        }
+        Some(c)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.contents.get(self.pos).copied()
+    }
+
+    /// Returns the next token, or `None` once the end of the file is reached.
+    pub fn next_token(&mut self) -> Result<Option<Token>, ParseError> {
+        loop {
+            let start = self.pos;
+            let Some(c) = self.get_char() else {
+                return Ok(None);
+            };
+            let loc = self.loc.clone();
+
+            match c {
+                ' ' | '\n' | '\t' | '\r' => continue,
+                '#' => {
+                    while let Some(c) = self.peek_char() {
+                        if c == '\n' || c == '\r' {
+                            break;
+                        }
+                        self.get_char();
+                    }
+                }
+                '"' => {
+                    let mut escaped = false;
+                    loop {
+                        match self.get_char() {
+                            None => {
+                                return Err(ParseError::new(
+                                    &loc,
+                                    "premature end of file in string",
+                                ))
+                            }
+                            Some('\n') => {
+                                return Err(ParseError::new(&loc, "unterminated string"));
+                            }
+                            Some('"') if !escaped => break,
+                            Some('\\') if !escaped => escaped = true,
+                            Some(_) => escaped = false,
+                        }
+                    }
+                    return Ok(Some(self.token(start, loc)));
+                }
+                '[' | ']' => return Ok(Some(self.token(start, loc))),
+                _ => {
+                    while let Some(c) = self.peek_char() {
+                        if matches!(c, ' ' | '\n' | '\t' | '\r' | '"' | '[' | ']') {
+                            break;
+                        }
+                        self.get_char();
+                    }
+                    return Ok(Some(self.token(start, loc)));
+                }
+            }
+        }
+    }
+
+    fn token(&self, start: usize, loc: FileLoc) -> Token {
+        Token {
+            text: self.contents[start..self.pos].iter().collect(),
+            loc,
+        }
+    }
+}
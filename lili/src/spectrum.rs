@@ -0,0 +1,68 @@
+//! Spectral distributions and their point samples.
+use std::fmt;
+
+mod sampled;
+pub use sampled::*;
+
+mod spectra;
+pub use spectra::*;
+
+mod rgb;
+pub use rgb::*;
+
+mod cie;
+
+pub mod named;
+
+use crate::{color::Xyz, Float};
+
+/// The shortest wavelength in nanometers that is considered when rendering.
+pub const LAMBDA_MIN: Float = 360.0;
+
+/// The longest wavelength in nanometers that is considered when rendering.
+pub const LAMBDA_MAX: Float = 830.0;
+
+/// The number of wavelengths carried by each camera ray.
+pub const N_SPECTRUM_SAMPLES: usize = 4;
+
+/// How a spectrum given as an RGB color is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumType {
+    /// A reflectance or transmittance, which is between 0 and 1.
+    Albedo,
+    /// A quantity without an upper bound, such as a scattering coefficient.
+    Unbounded,
+    /// Emitted radiance, relative to the illuminant of the color space.
+    Illuminant,
+}
+
+/// A distribution of a spectral quantity, such as emitted radiance or reflectance, over
+/// wavelength.
+pub trait Spectrum: fmt::Debug + Send + Sync {
+    /// The value of the distribution at the wavelength `lambda`, in nanometers.
+    fn evaluate(&self, lambda: Float) -> Float;
+
+    /// An upper bound on the value of the distribution over all wavelengths.
+    fn max_value(&self) -> Float;
+
+    /// The values of the distribution of the sampled wavelengths.
+    fn sample(&self, lambda: &SampledWavelengths) -> SampledSpectrum {
+        SampledSpectrum::from_values(lambda.lambda().map(|l| self.evaluate(l)))
+    }
+}
+
+/// The integral of the product of two spectra over the visible range, computed at 1nm steps.
+pub fn inner_product(f: &dyn Spectrum, g: &dyn Spectrum) -> Float {
+    (LAMBDA_MIN as i32..=LAMBDA_MAX as i32)
+        .map(|lambda| f.evaluate(lambda as Float) * g.evaluate(lambda as Float))
+        .sum()
+}
+
+/// The color of a spectrum, scaled so that a constant spectrum of 1 has a luminance of 1.
+pub fn spectrum_to_xyz(s: &dyn Spectrum) -> Xyz {
+    Xyz::new(
+        inner_product(named::x(), s),
+        inner_product(named::y(), s),
+        inner_product(named::z(), s),
+    ) / named::CIE_Y_INTEGRAL
+}
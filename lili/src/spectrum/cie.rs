@@ -0,0 +1,160 @@
+//! The tables of the CIE that spectra are measured against: the color matching functions of the
+//! standard observer and the components of daylight.
+//!
+//! The `rgb2spec_opt` example also compiles this file to fit the RGB-to-spectrum tables, so it
+//! only depends on the `Float` type of its parent module.
+use super::Float;
+
+// The CIE 1931 2° standard observer from 360nm to 830nm, as tabulated by the CIE at 5nm steps
+
+/// The wavelength of the first samples of the CIE tables, and the step between their samples.
+pub const CIE_LAMBDA_START: Float = 360.0;
+pub const CIE_LAMBDA_STEP: Float = 5.0;
+
+/// The values of the X, Y and Z color matching functions at each wavelength.
+#[allow(clippy::excessive_precision)]
+pub const CIE_XYZ: [[Float; 3]; 95] = [
+    [0.0001299, 0.000003917, 0.0006061],
+    [0.0002321, 0.000006965, 0.001086],
+    [0.0004149, 0.00001239, 0.001946],
+    [0.0007416, 0.00002202, 0.003486],
+    [0.001368, 0.000039, 0.006450001],
+    [0.002236, 0.000064, 0.01054999],
+    [0.004243, 0.00012, 0.02005001],
+    [0.00765, 0.000217, 0.03621],
+    [0.01431, 0.000396, 0.06785001],
+    [0.02319, 0.00064, 0.1102],
+    [0.04351, 0.00121, 0.2074],
+    [0.07763, 0.00218, 0.3713],
+    [0.13438, 0.004, 0.6456],
+    [0.21477, 0.0073, 1.0390501],
+    [0.2839, 0.0116, 1.3856],
+    [0.3285, 0.01684, 1.62296],
+    [0.34828, 0.023, 1.74706],
+    [0.34806, 0.0298, 1.7826],
+    [0.3362, 0.038, 1.77211],
+    [0.3187, 0.048, 1.7441],
+    [0.2908, 0.06, 1.6692],
+    [0.2511, 0.0739, 1.5281],
+    [0.19536, 0.09098, 1.28764],
+    [0.1421, 0.1126, 1.0419],
+    [0.09564, 0.13902, 0.8129501],
+    [0.05795001, 0.1693, 0.6162],
+    [0.03201, 0.20802, 0.46518],
+    [0.0147, 0.2586, 0.3533],
+    [0.0049, 0.323, 0.272],
+    [0.0024, 0.4073, 0.2123],
+    [0.0093, 0.503, 0.1582],
+    [0.0291, 0.6082, 0.1117],
+    [0.06327, 0.71, 0.07824999],
+    [0.1096, 0.7932, 0.05725001],
+    [0.1655, 0.862, 0.04216],
+    [0.2257499, 0.9148501, 0.02984],
+    [0.2904, 0.954, 0.0203],
+    [0.3597, 0.9803, 0.0134],
+    [0.4334499, 0.9949501, 0.008749999],
+    [0.5120501, 1.0, 0.005749999],
+    [0.5945, 0.995, 0.0039],
+    [0.6784, 0.9786, 0.002749999],
+    [0.7621, 0.952, 0.0021],
+    [0.8425, 0.9154, 0.0018],
+    [0.9163, 0.87, 0.001650001],
+    [0.9786, 0.8163, 0.0014],
+    [1.0263, 0.757, 0.0011],
+    [1.0567, 0.6949, 0.001],
+    [1.0622, 0.631, 0.0008],
+    [1.0456, 0.5668, 0.0006],
+    [1.0026, 0.503, 0.00034],
+    [0.9384, 0.4412, 0.00024],
+    [0.8544499, 0.381, 0.00019],
+    [0.7514, 0.321, 0.0001],
+    [0.6424, 0.265, 0.00004999999],
+    [0.5419, 0.217, 0.00003],
+    [0.4479, 0.175, 0.00002],
+    [0.3608, 0.1382, 0.00001],
+    [0.2835, 0.107, 0.0],
+    [0.2187, 0.0816, 0.0],
+    [0.1649, 0.061, 0.0],
+    [0.1212, 0.04458, 0.0],
+    [0.0874, 0.032, 0.0],
+    [0.0636, 0.0232, 0.0],
+    [0.04677, 0.017, 0.0],
+    [0.0329, 0.01192, 0.0],
+    [0.0227, 0.00821, 0.0],
+    [0.01584, 0.005723, 0.0],
+    [0.01135916, 0.004102, 0.0],
+    [0.008110916, 0.002929, 0.0],
+    [0.005790346, 0.002091, 0.0],
+    [0.004109457, 0.001484, 0.0],
+    [0.002899327, 0.001047, 0.0],
+    [0.00204919, 0.00074, 0.0],
+    [0.001439971, 0.00052, 0.0],
+    [0.0009999493, 0.0003611, 0.0],
+    [0.0006900786, 0.0002492, 0.0],
+    [0.0004760213, 0.0001719, 0.0],
+    [0.0003323011, 0.00012, 0.0],
+    [0.0002348261, 0.0000848, 0.0],
+    [0.0001661505, 0.00006, 0.0],
+    [0.000117413, 0.0000424, 0.0],
+    [0.00008307527, 0.00003, 0.0],
+    [0.00005870652, 0.0000212, 0.0],
+    [0.00004150994, 0.00001499, 0.0],
+    [0.00002935326, 0.0000106, 0.0],
+    [0.00002067383, 0.000007465, 0.0],
+    [0.00001455977, 0.000005257, 0.0],
+    [0.00001025398, 0.000003702, 0.0],
+    [0.000007221456, 0.000002607, 0.0],
+    [0.000005085868, 0.000001836, 0.0],
+    [0.000003581652, 0.000001293, 0.0],
+    [0.000002522525, 0.000000911, 0.0],
+    [0.000001776509, 0.000000641, 0.0],
+    [0.000001251141, 0.000000451, 0.0],
+];
+
+/// The spectral components of daylight from 300nm to 830nm at 10nm steps, from CIE 15, which are
+/// combined to give the CIE D series of illuminants.
+pub const CIE_S0: [Float; 54] = [
+    0.04, 6.0, 29.6, 55.3, 57.3, 61.8, 61.5, 68.8, 63.4, 65.8, 94.8, 104.8, 105.9, 96.8, 113.9,
+    125.6, 125.5, 121.3, 121.3, 113.5, 113.1, 110.8, 106.5, 108.8, 105.3, 104.4, 100.0, 96.0, 95.1,
+    89.1, 90.5, 90.3, 88.4, 84.0, 85.1, 81.9, 82.6, 84.9, 81.3, 71.9, 74.3, 76.4, 63.3, 71.7, 77.0,
+    65.2, 47.7, 68.6, 65.0, 66.0, 61.0, 53.3, 58.9, 61.9,
+];
+
+pub const CIE_S1: [Float; 54] = [
+    0.02, 4.5, 22.4, 42.0, 40.6, 41.6, 38.0, 42.4, 38.5, 35.0, 43.4, 46.3, 43.9, 37.1, 36.7, 35.9,
+    32.6, 27.9, 24.3, 20.1, 16.2, 13.2, 8.6, 6.1, 4.2, 1.9, 0.0, -1.6, -3.5, -3.5, -5.8, -7.2,
+    -8.6, -9.5, -10.9, -10.7, -12.0, -14.0, -13.6, -12.0, -13.3, -12.9, -10.6, -11.6, -12.2, -10.2,
+    -7.8, -11.2, -10.4, -10.6, -9.7, -8.3, -9.3, -9.8,
+];
+
+pub const CIE_S2: [Float; 54] = [
+    0.0, 2.0, 4.0, 8.5, 7.8, 6.7, 5.3, 6.1, 2.0, 1.2, -1.1, -0.5, -0.7, -1.2, -2.6, -2.9, -2.8,
+    -2.6, -2.6, -1.8, -1.5, -1.3, -1.2, -1.0, -0.5, -0.3, 0.0, 0.2, 0.5, 2.1, 3.2, 4.1, 4.7, 5.1,
+    6.7, 7.3, 8.6, 9.8, 10.2, 8.3, 9.6, 8.5, 7.0, 7.6, 8.0, 6.7, 5.2, 7.4, 6.8, 7.0, 6.4, 5.5, 6.1,
+    6.5,
+];
+
+/// The CIE D illuminant with correlated color temperature `t` Kelvin, as interleaved
+/// wavelength/value pairs from 300nm to 830nm at 10nm steps.
+pub fn daylight(t: Float) -> Vec<Float> {
+    // The chromaticity of daylight at the temperature
+    let (t2, t3) = (t * t, t * t * t);
+    let x = if t <= 7000.0 {
+        -4.607e9 / t3 + 2.9678e6 / t2 + 0.09911e3 / t + 0.244063
+    } else {
+        -2.0064e9 / t3 + 1.9018e6 / t2 + 0.24748e3 / t + 0.23704
+    };
+    let y = -3.0 * x * x + 2.87 * x - 0.275;
+
+    // The weights of the components that give that chromaticity
+    let m = 0.0241 + 0.2562 * x - 0.7341 * y;
+    let m1 = (-1.3515 - 1.7703 * x + 5.9114 * y) / m;
+    let m2 = (0.03 - 31.4424 * x + 30.0717 * y) / m;
+
+    (0..CIE_S0.len())
+        .flat_map(|i| {
+            let lambda = 300.0 + 10.0 * i as Float;
+            [lambda, CIE_S0[i] + m1 * CIE_S1[i] + m2 * CIE_S2[i]]
+        })
+        .collect()
+}
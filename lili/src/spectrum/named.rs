@@ -0,0 +1,174 @@
+//! Built-in spectra that scene files can refer to by name, and the CIE color matching functions.
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    math::{sqr, FloatExt},
+    spectrum::{
+        blackbody, cie, DenselySampledSpectrum, PiecewiseLinearSpectrum, Spectrum, LAMBDA_MAX,
+        LAMBDA_MIN,
+    },
+    Float,
+};
+
+/// Samples the color matching function `c` of the CIE table at every integer wavelength,
+/// interpolating linearly between its samples.
+fn densely_sampled(c: usize) -> DenselySampledSpectrum {
+    DenselySampledSpectrum::from_fn(
+        |lambda| {
+            let t = ((lambda - cie::CIE_LAMBDA_START) / cie::CIE_LAMBDA_STEP).max(0.0);
+            let i = (t as usize).min(cie::CIE_XYZ.len() - 2);
+            (t - i as Float).lerp(cie::CIE_XYZ[i][c], cie::CIE_XYZ[i + 1][c])
+        },
+        LAMBDA_MIN as i32,
+        LAMBDA_MAX as i32,
+    )
+}
+
+/// The CIE X color matching function.
+pub fn x() -> &'static DenselySampledSpectrum {
+    static X: OnceLock<DenselySampledSpectrum> = OnceLock::new();
+    X.get_or_init(|| densely_sampled(0))
+}
+
+/// The CIE Y color matching function, which is also the luminous efficiency of the human eye.
+pub fn y() -> &'static DenselySampledSpectrum {
+    static Y: OnceLock<DenselySampledSpectrum> = OnceLock::new();
+    Y.get_or_init(|| densely_sampled(1))
+}
+
+/// The CIE Z color matching function.
+pub fn z() -> &'static DenselySampledSpectrum {
+    static Z: OnceLock<DenselySampledSpectrum> = OnceLock::new();
+    Z.get_or_init(|| densely_sampled(2))
+}
+
+/// The integral of the CIE Y color matching function over its 1nm samples, which is the
+/// luminance of a spectrum with a constant value of 1.
+#[allow(clippy::excessive_precision)]
+pub const CIE_Y_INTEGRAL: Float = 106.856895;
+
+/// The CIE D illuminant with correlated color temperature `t` Kelvin, normalized to a luminance
+/// of 1.
+///
+/// The CIE defines the series for temperatures between 4000K and 25000K.
+pub fn std_illuminant_d(t: Float) -> PiecewiseLinearSpectrum {
+    PiecewiseLinearSpectrum::from_interleaved(&cie::daylight(t), true)
+        .expect("daylight components are sampled at increasing wavelengths")
+}
+
+/// Samples `f` from 300nm to 830nm at 5nm steps as interleaved wavelength/value pairs.
+fn tabulate(f: impl Fn(Float) -> Float) -> Vec<Float> {
+    (300..=830)
+        .step_by(5)
+        .flat_map(|lambda| [lambda as Float, f(lambda as Float)])
+        .collect()
+}
+
+/// The refractive index of BK7 glass, from its Sellmeier coefficients.
+#[allow(clippy::excessive_precision)]
+fn bk7_eta(lambda: Float) -> Float {
+    const B: [Float; 3] = [1.03961212, 0.231792344, 1.01046945];
+    const C: [Float; 3] = [0.00600069867, 0.0200179144, 103.560653];
+
+    // The Sellmeier equation is expressed in micrometers
+    let l2 = sqr(lambda * 1e-3);
+    (1.0 + (0..3).map(|i| B[i] * l2 / (l2 - C[i])).sum::<Float>()).sqrt()
+}
+
+// Approximate complex refractive indices of metals at 50nm steps, as interleaved
+// wavelength/value pairs
+
+const AG_ETA: [Float; 18] = [
+    400.0, 0.05, 450.0, 0.04, 500.0, 0.05, 550.0, 0.06, 600.0, 0.06, 650.0, 0.05, 700.0, 0.04,
+    750.0, 0.03, 800.0, 0.04,
+];
+const AG_K: [Float; 18] = [
+    400.0, 2.10, 450.0, 2.65, 500.0, 3.13, 550.0, 3.59, 600.0, 4.00, 650.0, 4.48, 700.0, 4.84,
+    750.0, 5.24, 800.0, 5.60,
+];
+
+const AL_ETA: [Float; 18] = [
+    400.0, 0.49, 450.0, 0.62, 500.0, 0.77, 550.0, 0.96, 600.0, 1.20, 650.0, 1.49, 700.0, 1.83,
+    750.0, 2.40, 800.0, 2.80,
+];
+const AL_K: [Float; 18] = [
+    400.0, 4.86, 450.0, 5.47, 500.0, 6.08, 550.0, 6.69, 600.0, 7.26, 650.0, 7.79, 700.0, 8.31,
+    750.0, 8.62, 800.0, 8.45,
+];
+
+const AU_ETA: [Float; 18] = [
+    400.0, 1.47, 450.0, 1.38, 500.0, 0.97, 550.0, 0.35, 600.0, 0.25, 650.0, 0.17, 700.0, 0.16,
+    750.0, 0.16, 800.0, 0.16,
+];
+const AU_K: [Float; 18] = [
+    400.0, 1.95, 450.0, 1.90, 500.0, 1.87, 550.0, 2.71, 600.0, 2.98, 650.0, 3.40, 700.0, 3.95,
+    750.0, 4.45, 800.0, 4.95,
+];
+
+const CU_ETA: [Float; 18] = [
+    400.0, 1.18, 450.0, 1.17, 500.0, 1.12, 550.0, 1.02, 600.0, 0.27, 650.0, 0.21, 700.0, 0.21,
+    750.0, 0.23, 800.0, 0.26,
+];
+const CU_K: [Float; 18] = [
+    400.0, 2.21, 450.0, 2.40, 500.0, 2.58, 550.0, 2.58, 600.0, 3.40, 650.0, 3.67, 700.0, 4.21,
+    750.0, 4.60, 800.0, 5.01,
+];
+
+fn named_spectra() -> &'static HashMap<&'static str, Arc<dyn Spectrum>> {
+    static SPECTRA: OnceLock<HashMap<&'static str, Arc<dyn Spectrum>>> = OnceLock::new();
+    SPECTRA.get_or_init(|| {
+        let interleaved = |samples: &[Float], normalize: bool| -> Arc<dyn Spectrum> {
+            Arc::new(
+                PiecewiseLinearSpectrum::from_interleaved(samples, normalize)
+                    .expect("built-in spectra are sampled at increasing wavelengths"),
+            )
+        };
+
+        let mut spectra: HashMap<&'static str, Arc<dyn Spectrum>> = HashMap::new();
+        spectra.insert(
+            "stdillum-A",
+            interleaved(&tabulate(|l| blackbody(l, 2856.0)), true),
+        );
+        spectra.insert("stdillum-D50", Arc::new(std_illuminant_d(5003.0)));
+        spectra.insert("stdillum-D65", Arc::new(std_illuminant_d(6504.0)));
+        spectra.insert(
+            "stdillum-E",
+            interleaved(&[LAMBDA_MIN, 1.0, LAMBDA_MAX, 1.0], true),
+        );
+        spectra.insert("illum-acesD60", Arc::new(std_illuminant_d(6000.0)));
+        spectra.insert("glass-BK7", interleaved(&tabulate(bk7_eta), false));
+        spectra.insert("metal-Ag-eta", interleaved(&AG_ETA, false));
+        spectra.insert("metal-Ag-k", interleaved(&AG_K, false));
+        spectra.insert("metal-Al-eta", interleaved(&AL_ETA, false));
+        spectra.insert("metal-Al-k", interleaved(&AL_K, false));
+        spectra.insert("metal-Au-eta", interleaved(&AU_ETA, false));
+        spectra.insert("metal-Au-k", interleaved(&AU_K, false));
+        spectra.insert("metal-Cu-eta", interleaved(&CU_ETA, false));
+        spectra.insert("metal-Cu-k", interleaved(&CU_K, false));
+        spectra
+    })
+}
+
+/// Returns the built-in spectrum with the given name, such as `"stdillum-D65"` or
+/// `"metal-Au-eta"`.
+///
+/// # Examples
+///
+/// ```
+/// use lili::spectrum::named;
+///
+/// let d65 = named::get("stdillum-D65").unwrap();
+/// assert!(d65.evaluate(560.0) > 0.0);
+/// assert!(named::get("unobtainium").is_none());
+/// ```
+pub fn get(name: &str) -> Option<Arc<dyn Spectrum>> {
+    named_spectra().get(name).cloned()
+}
+
+/// The names of all built-in spectra.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    named_spectra().keys().copied()
+}
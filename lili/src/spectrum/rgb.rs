@@ -0,0 +1,106 @@
+//! Spectra that reproduce RGB colors.
+use std::sync::Arc;
+
+use crate::{
+    color::{Rgb, RgbColorSpace, RgbSigmoidPolynomial},
+    spectrum::{DenselySampledSpectrum, Spectrum},
+    Float,
+};
+
+/// A reflectance spectrum with the given color, which is between 0 and 1 at every wavelength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RgbAlbedoSpectrum {
+    rsp: RgbSigmoidPolynomial,
+}
+
+impl RgbAlbedoSpectrum {
+    /// Creates the spectrum of `rgb` in `cs`, whose components must be between 0 and 1.
+    pub fn new(cs: &RgbColorSpace, rgb: Rgb) -> Self {
+        debug_assert!(
+            rgb.max_component_value() <= 1.0 && rgb.min_component_value() >= 0.0,
+            "albedo components must be between 0 and 1"
+        );
+        Self {
+            rsp: cs.to_rgb_coeffs(rgb),
+        }
+    }
+}
+
+impl Spectrum for RgbAlbedoSpectrum {
+    fn evaluate(&self, lambda: Float) -> Float {
+        self.rsp.evaluate(lambda)
+    }
+
+    fn max_value(&self) -> Float {
+        self.rsp.max_value()
+    }
+}
+
+/// A spectrum with the given color and no upper bound, such as for scattering coefficients.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RgbUnboundedSpectrum {
+    scale: Float,
+    rsp: RgbSigmoidPolynomial,
+}
+
+impl RgbUnboundedSpectrum {
+    /// Creates the spectrum of `rgb` in `cs`, whose components must not be negative.
+    ///
+    /// The color is scaled into the range of the sigmoid polynomials, with its largest component
+    /// mapped to 1/2 to leave room for the spectrum to vary on either side.
+    pub fn new(cs: &RgbColorSpace, rgb: Rgb) -> Self {
+        let m = rgb.max_component_value();
+        let scale = 2.0 * m;
+        let rsp = if scale != 0.0 {
+            cs.to_rgb_coeffs(rgb / scale)
+        } else {
+            cs.to_rgb_coeffs(Rgb::default())
+        };
+        Self { scale, rsp }
+    }
+}
+
+impl Spectrum for RgbUnboundedSpectrum {
+    fn evaluate(&self, lambda: Float) -> Float {
+        self.scale * self.rsp.evaluate(lambda)
+    }
+
+    fn max_value(&self) -> Float {
+        self.scale * self.rsp.max_value()
+    }
+}
+
+/// An emission spectrum with the given color, which is the spectrum of the color multiplied by
+/// the illuminant of the color space.
+#[derive(Debug, Clone)]
+pub struct RgbIlluminantSpectrum {
+    scale: Float,
+    rsp: RgbSigmoidPolynomial,
+    illuminant: Arc<DenselySampledSpectrum>,
+}
+
+impl RgbIlluminantSpectrum {
+    /// Creates the spectrum of `rgb` in `cs`, whose components must not be negative.
+    pub fn new(cs: &RgbColorSpace, rgb: Rgb) -> Self {
+        let RgbUnboundedSpectrum { scale, rsp } = RgbUnboundedSpectrum::new(cs, rgb);
+        Self {
+            scale,
+            rsp,
+            illuminant: cs.illuminant.clone(),
+        }
+    }
+
+    pub fn illuminant(&self) -> &DenselySampledSpectrum {
+        &self.illuminant
+    }
+}
+
+impl Spectrum for RgbIlluminantSpectrum {
+    fn evaluate(&self, lambda: Float) -> Float {
+        self.scale * self.rsp.evaluate(lambda) * self.illuminant.evaluate(lambda)
+    }
+
+    fn max_value(&self) -> Float {
+        self.scale * self.rsp.max_value() * self.illuminant.max_value()
+    }
+}
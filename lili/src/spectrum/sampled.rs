@@ -0,0 +1,281 @@
+//! Spectral quantities point sampled at a fixed number of wavelengths.
+use std::ops::{Index, IndexMut};
+
+use auto_ops::{impl_op_ex, impl_op_ex_commutative};
+
+use crate::{
+    color::{Rgb, RgbColorSpace, Xyz},
+    math::{
+        sampling::{sample_visible_wavelengths, visible_wavelengths_pdf},
+        Float, FloatExt,
+    },
+    spectrum::{named, Spectrum, LAMBDA_MAX, LAMBDA_MIN, N_SPECTRUM_SAMPLES},
+};
+
+/// The values of a spectral distribution at the wavelengths of a [`SampledWavelengths`].
+///
+/// All arithmetic is component-wise.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SampledSpectrum {
+    values: [Float; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledSpectrum {
+    /// Creates a spectrum with the same value at every wavelength.
+    pub fn new(c: Float) -> Self {
+        Self {
+            values: [c; N_SPECTRUM_SAMPLES],
+        }
+    }
+
+    pub fn from_values(values: [Float; N_SPECTRUM_SAMPLES]) -> Self {
+        Self { values }
+    }
+
+    pub fn values(&self) -> &[Float; N_SPECTRUM_SAMPLES] {
+        &self.values
+    }
+
+    /// Returns `true` if the spectrum is not zero at any of the wavelengths.
+    pub fn nonzero(&self) -> bool {
+        self.values.iter().any(|v| *v != 0.0)
+    }
+
+    pub fn min_component_value(&self) -> Float {
+        self.values
+            .iter()
+            .copied()
+            .fold(Float::INFINITY, Float::min)
+    }
+
+    pub fn max_component_value(&self) -> Float {
+        self.values
+            .iter()
+            .copied()
+            .fold(Float::NEG_INFINITY, Float::max)
+    }
+
+    pub fn average(&self) -> Float {
+        self.values.iter().sum::<Float>() / N_SPECTRUM_SAMPLES as Float
+    }
+
+    pub fn map(&self, f: impl Fn(Float) -> Float) -> Self {
+        Self {
+            values: self.values.map(f),
+        }
+    }
+
+    pub fn exp(&self) -> Self {
+        self.map(Float::exp)
+    }
+
+    /// The square root of each value, with negative values clamped to zero.
+    pub fn sqrt(&self) -> Self {
+        self.map(Float::safe_sqrt)
+    }
+
+    pub fn clamp(&self, low: Float, high: Float) -> Self {
+        self.map(|v| v.clamp(low, high))
+    }
+
+    /// Clamps negative values to zero.
+    pub fn clamp_zero(&self) -> Self {
+        self.map(|v| v.max(0.0))
+    }
+
+    /// Divides `self` by `rhs`, giving zero wherever `rhs` is zero.
+    pub fn safe_div(&self, rhs: &Self) -> Self {
+        Self {
+            values: std::array::from_fn(|i| {
+                if rhs.values[i] != 0.0 {
+                    self.values[i] / rhs.values[i]
+                } else {
+                    0.0
+                }
+            }),
+        }
+    }
+
+    /// The color of the spectrum, estimated from its values at the sampled wavelengths.
+    pub fn to_xyz(&self, lambda: &SampledWavelengths) -> Xyz {
+        let pdf = lambda.pdf();
+        let component = |cie: &dyn Spectrum| {
+            (cie.sample(lambda) * self).safe_div(&pdf).average() / named::CIE_Y_INTEGRAL
+        };
+        Xyz::new(
+            component(named::x()),
+            component(named::y()),
+            component(named::z()),
+        )
+    }
+
+    /// The luminance of the spectrum, estimated from its values at the sampled wavelengths.
+    pub fn y(&self, lambda: &SampledWavelengths) -> Float {
+        (named::y().sample(lambda) * self)
+            .safe_div(&lambda.pdf())
+            .average()
+            / named::CIE_Y_INTEGRAL
+    }
+
+    /// The color of the spectrum in the color space `cs`.
+    pub fn to_rgb(&self, lambda: &SampledWavelengths, cs: &RgbColorSpace) -> Rgb {
+        cs.to_rgb(self.to_xyz(lambda))
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(Float, Float) -> Float) -> Self {
+        Self {
+            values: std::array::from_fn(|i| f(self.values[i], rhs.values[i])),
+        }
+    }
+}
+
+impl Index<usize> for SampledSpectrum {
+    type Output = Float;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.values[i]
+    }
+}
+
+impl IndexMut<usize> for SampledSpectrum {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.values[i]
+    }
+}
+
+// Arithmetic operations
+
+impl_op_ex!(+|a: &SampledSpectrum, b: &SampledSpectrum| -> SampledSpectrum { a.zip_with(b, |x, y| x + y) });
+impl_op_ex!(
+    -|a: &SampledSpectrum, b: &SampledSpectrum| -> SampledSpectrum { a.zip_with(b, |x, y| x - y) }
+);
+impl_op_ex!(
+    *|a: &SampledSpectrum, b: &SampledSpectrum| -> SampledSpectrum { a.zip_with(b, |x, y| x * y) }
+);
+impl_op_ex!(/|a: &SampledSpectrum, b: &SampledSpectrum| -> SampledSpectrum {
+    debug_assert!(b.values.iter().all(|v| *v != 0.0), "division by a zero spectrum value");
+    a.zip_with(b, |x, y| x / y)
+});
+
+impl_op_ex_commutative!(+|a: &SampledSpectrum, b: Float| -> SampledSpectrum { a.map(|x| x + b) });
+impl_op_ex_commutative!(*|a: &SampledSpectrum, b: Float| -> SampledSpectrum { a.map(|x| x * b) });
+impl_op_ex!(-|a: &SampledSpectrum, b: Float| -> SampledSpectrum { a.map(|x| x - b) });
+impl_op_ex!(-|a: Float, b: &SampledSpectrum| -> SampledSpectrum { b.map(|x| a - x) });
+impl_op_ex!(/|a: &SampledSpectrum, b: Float| -> SampledSpectrum {
+    debug_assert!(b != 0.0, "division of a spectrum by zero");
+    a.map(|x| x / b)
+});
+
+impl_op_ex!(-|a: &SampledSpectrum| -> SampledSpectrum { a.map(|x| -x) });
+
+// Assignment operations
+
+impl_op_ex!(+= |a: &mut SampledSpectrum, b: &SampledSpectrum| { *a = *a + b; });
+impl_op_ex!(-= |a: &mut SampledSpectrum, b: &SampledSpectrum| { *a = *a - b; });
+impl_op_ex!(*= |a: &mut SampledSpectrum, b: &SampledSpectrum| { *a = *a * b; });
+impl_op_ex!(/= |a: &mut SampledSpectrum, b: &SampledSpectrum| { *a = *a / b; });
+impl_op_ex!(*= |a: &mut SampledSpectrum, b: Float| { *a = *a * b; });
+impl_op_ex!(/= |a: &mut SampledSpectrum, b: Float| { *a = *a / b; });
+
+/// The wavelengths that a camera ray carries, along with the probability densities they were
+/// sampled with.
+///
+/// # Examples
+///
+/// ```
+/// use lili::spectrum::SampledWavelengths;
+///
+/// let mut lambda = SampledWavelengths::sample_visible(0.5);
+/// assert!(!lambda.secondary_terminated());
+///
+/// // Only the first wavelength is followed after a dispersive interaction
+/// lambda.terminate_secondary();
+/// assert!(lambda.secondary_terminated());
+/// assert!(lambda.pdf()[0] > 0.0);
+/// assert_eq!(lambda.pdf()[1], 0.0);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SampledWavelengths {
+    lambda: [Float; N_SPECTRUM_SAMPLES],
+    pdf: [Float; N_SPECTRUM_SAMPLES],
+}
+
+impl SampledWavelengths {
+    /// Samples wavelengths uniformly between `lambda_min` and `lambda_max`.
+    ///
+    /// Only the first wavelength depends on `u`; the others are spaced evenly after it, wrapping
+    /// around at the end of the range, which stratifies them.
+    pub fn sample_uniform(u: Float, lambda_min: Float, lambda_max: Float) -> Self {
+        let first = u.lerp(lambda_min, lambda_max);
+        let delta = (lambda_max - lambda_min) / N_SPECTRUM_SAMPLES as Float;
+        let mut lambda = [first; N_SPECTRUM_SAMPLES];
+        for i in 1..N_SPECTRUM_SAMPLES {
+            lambda[i] = lambda[i - 1] + delta;
+            if lambda[i] > lambda_max {
+                lambda[i] = lambda_min + (lambda[i] - lambda_max);
+            }
+        }
+
+        Self {
+            lambda,
+            pdf: [1.0 / (lambda_max - lambda_min); N_SPECTRUM_SAMPLES],
+        }
+    }
+
+    /// Samples wavelengths uniformly over the full range used for rendering.
+    pub fn sample_uniform_full(u: Float) -> Self {
+        Self::sample_uniform(u, LAMBDA_MIN, LAMBDA_MAX)
+    }
+
+    /// Samples wavelengths with a density that favours those the human eye is most sensitive to.
+    ///
+    /// As with [`SampledWavelengths::sample_uniform`], the samples are stratified.
+    pub fn sample_visible(u: Float) -> Self {
+        let mut lambda = [0.0; N_SPECTRUM_SAMPLES];
+        let mut pdf = [0.0; N_SPECTRUM_SAMPLES];
+        for i in 0..N_SPECTRUM_SAMPLES {
+            let mut up = u + i as Float / N_SPECTRUM_SAMPLES as Float;
+            if up > 1.0 {
+                up -= 1.0;
+            }
+            lambda[i] = sample_visible_wavelengths(up);
+            pdf[i] = visible_wavelengths_pdf(lambda[i]);
+        }
+        Self { lambda, pdf }
+    }
+
+    pub fn lambda(&self) -> &[Float; N_SPECTRUM_SAMPLES] {
+        &self.lambda
+    }
+
+    /// The probability densities of the wavelengths.
+    pub fn pdf(&self) -> SampledSpectrum {
+        SampledSpectrum::from_values(self.pdf)
+    }
+
+    /// Stops following all but the first wavelength, such as after refraction through a
+    /// dispersive medium sent each wavelength in a different direction.
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+
+        // The remaining wavelength now accounts for all of the samples
+        for pdf in &mut self.pdf[1..] {
+            *pdf = 0.0;
+        }
+        self.pdf[0] /= N_SPECTRUM_SAMPLES as Float;
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|pdf| *pdf == 0.0)
+    }
+}
+
+impl Index<usize> for SampledWavelengths {
+    type Output = Float;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.lambda[i]
+    }
+}
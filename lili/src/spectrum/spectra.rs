@@ -0,0 +1,231 @@
+//! The basic ways of representing a spectral distribution.
+use crate::{
+    math::FloatExt,
+    spectrum::{inner_product, named, Spectrum, LAMBDA_MAX, LAMBDA_MIN},
+    Float,
+};
+
+/// A spectrum with the same value at every wavelength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantSpectrum {
+    c: Float,
+}
+
+impl ConstantSpectrum {
+    pub fn new(c: Float) -> Self {
+        Self { c }
+    }
+}
+
+impl Spectrum for ConstantSpectrum {
+    fn evaluate(&self, _lambda: Float) -> Float {
+        self.c
+    }
+
+    fn max_value(&self) -> Float {
+        self.c
+    }
+}
+
+/// A spectrum stored as its values at every integer wavelength in a range, which makes it cheap
+/// to evaluate at the cost of memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenselySampledSpectrum {
+    lambda_min: i32,
+    lambda_max: i32,
+    values: Vec<Float>,
+}
+
+impl DenselySampledSpectrum {
+    /// Samples `spec` at every integer wavelength from `lambda_min` to `lambda_max`.
+    pub fn new(spec: &dyn Spectrum, lambda_min: i32, lambda_max: i32) -> Self {
+        Self::from_fn(|lambda| spec.evaluate(lambda), lambda_min, lambda_max)
+    }
+
+    /// Samples the function `f` at every integer wavelength from `lambda_min` to `lambda_max`.
+    pub fn from_fn(f: impl Fn(Float) -> Float, lambda_min: i32, lambda_max: i32) -> Self {
+        Self {
+            lambda_min,
+            lambda_max,
+            values: (lambda_min..=lambda_max)
+                .map(|lambda| f(lambda as Float))
+                .collect(),
+        }
+    }
+
+    /// Samples `spec` over the full range of wavelengths used for rendering.
+    pub fn from_spectrum(spec: &dyn Spectrum) -> Self {
+        Self::new(spec, LAMBDA_MIN as i32, LAMBDA_MAX as i32)
+    }
+}
+
+impl Spectrum for DenselySampledSpectrum {
+    /// The value at the nearest integer wavelength, or zero outside of the sampled range.
+    fn evaluate(&self, lambda: Float) -> Float {
+        let offset = lambda.round() as i32 - self.lambda_min;
+        if offset < 0 || lambda.round() as i32 > self.lambda_max {
+            return 0.0;
+        }
+        self.values[offset as usize]
+    }
+
+    fn max_value(&self) -> Float {
+        self.values
+            .iter()
+            .copied()
+            .fold(Float::NEG_INFINITY, Float::max)
+    }
+}
+
+/// A spectrum that is linearly interpolated between values at arbitrary wavelengths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiecewiseLinearSpectrum {
+    lambdas: Vec<Float>,
+    values: Vec<Float>,
+}
+
+impl PiecewiseLinearSpectrum {
+    /// Creates a spectrum from values at increasing wavelengths.
+    ///
+    /// Returns `None` if the lengths differ or the wavelengths are not increasing.
+    pub fn new(lambdas: Vec<Float>, values: Vec<Float>) -> Option<Self> {
+        if lambdas.len() != values.len() || lambdas.windows(2).any(|w| w[0] >= w[1]) {
+            return None;
+        }
+        Some(Self { lambdas, values })
+    }
+
+    /// Creates a spectrum from interleaved wavelength/value pairs, such as `[400.0, 0.5, 500.0,
+    /// 0.7]`.
+    ///
+    /// The first and last values are extended to cover all wavelengths used for rendering. If
+    /// `normalize` is set, the spectrum is scaled to have a luminance of 1.
+    ///
+    /// Returns `None` if the pairs are incomplete or the wavelengths are not increasing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lili::spectrum::{PiecewiseLinearSpectrum, Spectrum};
+    ///
+    /// let spec = PiecewiseLinearSpectrum::from_interleaved(&[400.0, 1.0, 600.0, 3.0], false).unwrap();
+    /// assert_eq!(spec.evaluate(500.0), 2.0);
+    /// assert_eq!(spec.evaluate(700.0), 3.0);
+    /// assert_eq!(spec.max_value(), 3.0);
+    ///
+    /// assert!(PiecewiseLinearSpectrum::from_interleaved(&[600.0, 1.0, 400.0, 3.0], false).is_none());
+    /// ```
+    pub fn from_interleaved(samples: &[Float], normalize: bool) -> Option<Self> {
+        if samples.is_empty() || !samples.len().is_multiple_of(2) {
+            return None;
+        }
+
+        let mut lambdas = Vec::with_capacity(samples.len() / 2 + 2);
+        let mut values = Vec::with_capacity(samples.len() / 2 + 2);
+
+        // Extend the end values so that the spectrum covers the visible range
+        if samples[0] > LAMBDA_MIN {
+            lambdas.push(LAMBDA_MIN - 1.0);
+            values.push(samples[1]);
+        }
+        for pair in samples.chunks_exact(2) {
+            lambdas.push(pair[0]);
+            values.push(pair[1]);
+        }
+        if samples[samples.len() - 2] < LAMBDA_MAX {
+            lambdas.push(LAMBDA_MAX + 1.0);
+            values.push(samples[samples.len() - 1]);
+        }
+
+        let mut spec = Self::new(lambdas, values)?;
+        if normalize {
+            let scale = named::CIE_Y_INTEGRAL / inner_product(&spec, named::y());
+            spec.scale(scale);
+        }
+        Some(spec)
+    }
+
+    pub fn scale(&mut self, s: Float) {
+        for value in &mut self.values {
+            *value *= s;
+        }
+    }
+}
+
+impl Spectrum for PiecewiseLinearSpectrum {
+    /// The interpolated value, or zero outside of the range of wavelengths.
+    fn evaluate(&self, lambda: Float) -> Float {
+        let (Some(first), Some(last)) = (self.lambdas.first(), self.lambdas.last()) else {
+            return 0.0;
+        };
+        if lambda < *first || lambda > *last {
+            return 0.0;
+        }
+
+        // The index of the segment that lambda lies in
+        let o = self
+            .lambdas
+            .partition_point(|l| *l <= lambda)
+            .clamp(1, self.lambdas.len() - 1)
+            - 1;
+        let t = (lambda - self.lambdas[o]) / (self.lambdas[o + 1] - self.lambdas[o]);
+        t.lerp(self.values[o], self.values[o + 1])
+    }
+
+    fn max_value(&self) -> Float {
+        self.values
+            .iter()
+            .copied()
+            .fold(Float::NEG_INFINITY, Float::max)
+    }
+}
+
+/// The emitted radiance of a blackbody at wavelength `lambda` nanometers and temperature `t`
+/// Kelvin, given by Planck's law.
+#[allow(clippy::excessive_precision)]
+pub fn blackbody(lambda: Float, t: Float) -> Float {
+    if t <= 0.0 {
+        return 0.0;
+    }
+    const C: Float = 299792458.0;
+    const H: Float = 6.62606957e-34;
+    const KB: Float = 1.3806488e-23;
+
+    // Planck's law is expressed in meters
+    let l = lambda * 1e-9;
+    (2.0 * H * C * C) / (l.powi(5) * (((H * C) / (l * KB * t)).exp() - 1.0))
+}
+
+/// The emission spectrum of a blackbody, normalized to have a maximum value of 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlackbodySpectrum {
+    t: Float,
+    normalization_factor: Float,
+}
+
+impl BlackbodySpectrum {
+    /// Creates the spectrum of a blackbody at temperature `t` Kelvin.
+    #[allow(clippy::excessive_precision)]
+    pub fn new(t: Float) -> Self {
+        // Wien's displacement law gives the wavelength of the peak emission
+        let lambda_max = 2.8977721e-3 / t;
+        Self {
+            t,
+            normalization_factor: 1.0 / blackbody(lambda_max * 1e9, t),
+        }
+    }
+
+    pub fn temperature(&self) -> Float {
+        self.t
+    }
+}
+
+impl Spectrum for BlackbodySpectrum {
+    fn evaluate(&self, lambda: Float) -> Float {
+        blackbody(lambda, self.t) * self.normalization_factor
+    }
+
+    fn max_value(&self) -> Float {
+        1.0
+    }
+}
@@ -1,4 +1,4 @@
-use std::ffi::OsString;
+use std::{ffi::OsString, process};
 
 use clap::{Parser, ValueEnum};
 use lili::{scene::BasicSceneBuilder, Context, Options, RenderingCoordinateSystem};
@@ -73,7 +73,13 @@ fn main() {
 
     let context = Context::new(&options);
 
-    let scene = BasicSceneBuilder::parse_files(options.scenes);
+    let scene = match BasicSceneBuilder::parse_files(options.scenes) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
 
     context.render(scene);
 }